@@ -35,6 +35,11 @@ nearest = IntervalOperations.nearest
 count_overlaps = IntervalOperations.count_overlaps
 coverage = IntervalOperations.coverage
 merge = IntervalOperations.merge
+liftover = IntervalOperations.liftover
+sv_match = IntervalOperations.sv_match
+build_index = IntervalOperations.build_index
+load_index = IntervalOperations.load_index
+drop_index = IntervalOperations.drop_index
 
 from .range_utils import Utils
 
@@ -47,8 +52,31 @@ from .range_op import IntervalOperations as range_operations
 from .range_utils import Utils as utils
 from .sql import SQL as data_processing
 
-from .quality_control_op import base_sequence_content
+from .quality_control_op import (
+    base_sequence_content,
+    base_sequence_quality,
+    per_sequence_gc_content,
+    per_sequence_quality,
+    sequence_length_distribution,
+    sequence_duplication_levels,
+    overrepresented_sequences,
+    adapter_content,
+    kmer_count,
+    detect_phred_encoding,
+    reencode_quality,
+    fastq_qc_report,
+    DEFAULT_QC_THRESHOLDS,
+    write_fastqc_data,
+)
 from .quality_control_viz import plot_base_content
+from .fastq_op import demultiplex, extract_umi, umi_dedup
+from .bam_op import (
+    flagstat,
+    idxstats,
+    insert_size_histogram,
+    insert_size_metrics,
+    mapq_distribution,
+)
 
 POLARS_BIO_MAX_THREADS = "datafusion.execution.target_partitions"
 
@@ -68,5 +96,26 @@ __all__ = [
     "ObjectStorageOptions",
     "set_option",
     "base_sequence_content",
-    "plot_base_content"
+    "base_sequence_quality",
+    "per_sequence_quality",
+    "per_sequence_gc_content",
+    "sequence_length_distribution",
+    "sequence_duplication_levels",
+    "overrepresented_sequences",
+    "adapter_content",
+    "kmer_count",
+    "detect_phred_encoding",
+    "reencode_quality",
+    "fastq_qc_report",
+    "DEFAULT_QC_THRESHOLDS",
+    "write_fastqc_data",
+    "plot_base_content",
+    "extract_umi",
+    "umi_dedup",
+    "demultiplex",
+    "flagstat",
+    "idxstats",
+    "insert_size_histogram",
+    "insert_size_metrics",
+    "mapq_distribution",
 ]
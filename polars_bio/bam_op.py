@@ -0,0 +1,211 @@
+from typing import Optional, Union
+
+import pandas as pd
+import polars as pl
+
+from polars_bio.polars_bio import (
+    py_flagstat_frame,
+    py_flagstat_scan,
+    py_idxstats_frame,
+    py_idxstats_scan,
+    py_insert_size_histogram_frame,
+    py_insert_size_histogram_scan,
+    py_mapq_distribution_frame,
+    py_mapq_distribution_scan,
+)
+
+from .context import ctx
+from .quality_control_op import _qc_input
+
+
+def flagstat(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+) -> pl.DataFrame:
+    """
+    Count alignments by flag category, like `samtools flagstat`.
+    The counts are aggregated in parallel across the partitions of the BAM table.
+
+    Parameters:
+        data: A path to a BAM or Parquet file, a table registered with [register_bam](api.md#polars_bio.register_bam), or a polars/pandas DataFrame with `flags`, `chrom`, `mate_chrom` and `mapping_quality` columns, e.g. from [read_bam](api.md#polars_bio.read_bam).
+
+    Returns:
+        A DataFrame with one row per `category` (`total`, `primary`, `secondary`, `supplementary`, `duplicates`, `primary_duplicates`, `mapped`, `primary_mapped`, `paired`, `read1`, `read2`, `properly_paired`, `with_mate_mapped`, `singletons`, `mate_on_different_chrom` and `mate_on_different_chrom_mapq5`) and the `qc_passed` and `qc_failed` counts of alignments without and with the QC-fail flag.
+
+    !!! note
+        As in samtools, the pair categories from `paired` on only count primary alignments.
+    """
+    df, table_path = _qc_input(data, "flags")
+    if df is not None:
+        return py_flagstat_frame(ctx, df.to_arrow().to_reader()).to_polars()
+    else:
+        return py_flagstat_scan(ctx, table_path).to_polars()
+
+
+def idxstats(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+) -> pl.DataFrame:
+    """
+    Count mapped and unmapped alignments per reference sequence, like `samtools idxstats`.
+    Unlike samtools, the counts come from reading the alignments rather than the BAM index.
+
+    Parameters:
+        data: A path to a BAM or Parquet file, a table registered with [register_bam](api.md#polars_bio.register_bam), or a polars/pandas DataFrame with `chrom` and `flags` columns.
+
+    Returns:
+        A DataFrame with the `chrom`, `length`, `mapped` and `unmapped` columns, and a last `*` row counting unplaced reads.
+
+    !!! note
+        Reference lengths are read from the header of local BAM files, in which case the rows follow the header order and include references without reads. For other inputs `length` is null and rows are sorted by `chrom`.
+    """
+    df, table_path = _qc_input(data, "flags")
+    if df is not None:
+        return py_idxstats_frame(ctx, df.to_arrow().to_reader()).to_polars()
+    else:
+        return py_idxstats_scan(ctx, table_path).to_polars()
+
+
+def _group_by(
+    df: Union[pl.DataFrame, None], group_by: Optional[Union[str, list]]
+) -> list:
+    """Normalizes the grouping columns and checks that frames have them."""
+    if group_by is None:
+        group_by = []
+    elif isinstance(group_by, str):
+        group_by = [group_by]
+    missing = [c for c in group_by if df is not None and c not in df.columns]
+    if missing:
+        raise ValueError(f"Input data must have the grouping columns: {missing}")
+    return group_by
+
+
+def insert_size_histogram(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    group_by: Optional[Union[str, list]] = None,
+    include_duplicates: bool = False,
+) -> pl.DataFrame:
+    """
+    Histogram of the insert sizes of proper pairs by pair orientation, like the histogram of Picard `CollectInsertSizeMetrics`.
+    As in Picard, every pair with both mates mapped to the same chromosome is counted once, from its primary, QC-passed read 2 alignment.
+
+    Parameters:
+        data: A path to a BAM or Parquet file, a table registered with [register_bam](api.md#polars_bio.register_bam), or a polars/pandas DataFrame with `chrom`, `start`, `end`, `flags`, `mate_chrom` and `mate_start` columns.
+        group_by: The columns to compute separate histograms for, e.g. `chrom`, or a read group column of the input.
+        include_duplicates: Whether to count pairs flagged as duplicates.
+
+    Returns:
+        A DataFrame with the `group_by` columns, `orientation` (`FR`, `RF` or `TANDEM`), `insert_size`, `count` and `fraction` of pairs of the group and orientation.
+
+    !!! note
+        The BAM reader exposes neither the template length nor the read group tag. The insert size is the span from the leftmost to the rightmost aligned base of the pair, assuming the mate spans as many reference bases as read 2. To group by read group, add it as a column of a DataFrame input.
+    """
+    df, table_path = _qc_input(data, "flags")
+    group_by = _group_by(df, group_by)
+    if df is not None:
+        return py_insert_size_histogram_frame(
+            ctx, df.to_arrow().to_reader(), group_by, include_duplicates
+        ).to_polars()
+    else:
+        return py_insert_size_histogram_scan(
+            ctx, table_path, group_by, include_duplicates
+        ).to_polars()
+
+
+def _weighted_median(
+    histogram: pl.DataFrame, keys: list, value: str
+) -> pl.DataFrame:
+    """Median of `value` weighted by `count` for every group of `keys`."""
+    return (
+        histogram.sort(keys + [value])
+        .group_by(keys, maintain_order=True)
+        .agg(
+            pl.col(value)
+            .filter(pl.col("count").cum_sum() * 2 >= pl.col("count").sum())
+            .first()
+        )
+    )
+
+
+def insert_size_metrics(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    group_by: Optional[Union[str, list]] = None,
+    include_duplicates: bool = False,
+    deviations: float = 10.0,
+) -> pl.DataFrame:
+    """
+    Summary metrics of the insert sizes of proper pairs by pair orientation, like Picard `CollectInsertSizeMetrics`.
+
+    Parameters:
+        data: The alignments, as for [insert_size_histogram](api.md#polars_bio.insert_size_histogram).
+        group_by: The columns to compute separate metrics for, e.g. `chrom`.
+        include_duplicates: Whether to count pairs flagged as duplicates.
+        deviations: As Picard's `DEVIATIONS`, the mean and standard deviation only count insert sizes up to the median plus this many median absolute deviations.
+
+    Returns:
+        A DataFrame with the `group_by` columns, `orientation`, `read_pairs`, `min_insert_size`, `max_insert_size`, `mean_insert_size`, `standard_deviation`, `median_insert_size` and `median_absolute_deviation`. The standard deviation is null for fewer than two pairs.
+    """
+    histogram = insert_size_histogram(data, group_by, include_duplicates)
+    keys = _group_by(None, group_by) + ["orientation"]
+    medians = _weighted_median(histogram, keys, "insert_size").rename(
+        {"insert_size": "median_insert_size"}
+    )
+    absolute_deviations = histogram.join(medians, on=keys).with_columns(
+        (pl.col("insert_size") - pl.col("median_insert_size"))
+        .abs()
+        .alias("deviation")
+    )
+    mads = _weighted_median(absolute_deviations, keys, "deviation").rename(
+        {"deviation": "median_absolute_deviation"}
+    )
+    # Like Picard, leave the long tail of e.g. chimeric pairs out of the mean
+    # and standard deviation
+    trimmed = (
+        absolute_deviations.join(mads, on=keys)
+        .filter(
+            pl.col("insert_size")
+            <= pl.col("median_insert_size")
+            + deviations * pl.col("median_absolute_deviation")
+        )
+    )
+    pairs = pl.col("count").sum()
+    mean = (pl.col("insert_size") * pl.col("count")).sum() / pairs
+    metrics = histogram.group_by(keys, maintain_order=True).agg(
+        pairs.cast(pl.UInt64).alias("read_pairs"),
+        pl.col("insert_size").min().alias("min_insert_size"),
+        pl.col("insert_size").max().alias("max_insert_size"),
+    )
+    moments = trimmed.group_by(keys, maintain_order=True).agg(
+        mean.alias("mean_insert_size"),
+        pl.when(pairs > 1)
+        .then(
+            (
+                (pl.col("count") * (pl.col("insert_size") - mean) ** 2).sum()
+                / (pairs - 1)
+            ).sqrt()
+        )
+        .alias("standard_deviation"),
+    )
+    return metrics.join(moments, on=keys).join(medians, on=keys).join(mads, on=keys)
+
+
+def mapq_distribution(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    group_by: Optional[Union[str, list]] = None,
+) -> pl.DataFrame:
+    """
+    Distribution of the mapping qualities of primary, QC-passed mapped alignments, like the mapping quality histogram of Qualimap.
+
+    Parameters:
+        data: A path to a BAM or Parquet file, a table registered with [register_bam](api.md#polars_bio.register_bam), or a polars/pandas DataFrame with `flags` and `mapping_quality` columns.
+        group_by: The columns to compute separate distributions for, e.g. `chrom`, or a read group column of the input.
+
+    Returns:
+        A DataFrame with the `group_by` columns, `mapping_quality`, `count` and `fraction` of alignments of the group.
+    """
+    df, table_path = _qc_input(data, "mapping_quality")
+    group_by = _group_by(df, group_by)
+    if df is not None:
+        return py_mapq_distribution_frame(
+            ctx, df.to_arrow().to_reader(), group_by
+        ).to_polars()
+    else:
+        return py_mapq_distribution_scan(ctx, table_path, group_by).to_polars()
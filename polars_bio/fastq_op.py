@@ -0,0 +1,172 @@
+from typing import Optional, Union
+
+import pandas as pd
+import polars as pl
+
+from polars_bio.polars_bio import (
+    py_demultiplex_frame,
+    py_demultiplex_scan,
+    py_extract_umi_frame,
+    py_extract_umi_scan,
+    py_umi_dedup_frame,
+    py_umi_dedup_scan,
+)
+
+from .context import ctx
+from .quality_control_op import _qc_input
+from .sql import SQL
+
+UNDETERMINED_SAMPLE = "Undetermined"
+
+
+def extract_umi(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    pattern: str,
+    column: str = "sequence",
+    quality_column: str = "quality_scores",
+) -> pl.DataFrame:
+    """
+    Move UMI bases from the start of reads into a `umi` column, as marked by an umi_tools-style pattern.
+    The same extraction is available in SQL as `extract_umi(sequence, quality_scores, 'NNNNNNXX')`, returning a struct, and UMIs appended to read names can be read with `umi_from_name(name[, separator])`.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame.
+        pattern: The pattern of the start of reads, where `N` marks UMI bases, `C` cell barcode bases and `X` bases kept in the read, e.g. `NNNNNNXXXX`.
+        column: The name of the sequence column.
+        quality_column: The name of the quality column.
+
+    Returns:
+        The input with the UMI (and cell barcode) bases and their qualities removed from the reads, and `umi` (and `cell_barcode`) columns. Reads shorter than the pattern are dropped.
+    """
+    df, table_path = _qc_input(data, column)
+    if df is not None:
+        return py_extract_umi_frame(
+            ctx, df.to_arrow().to_reader(), column, quality_column, pattern
+        ).to_polars()
+    else:
+        return py_extract_umi_scan(
+            ctx, table_path, column, quality_column, pattern
+        ).to_polars()
+
+
+def umi_dedup(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    umi_column: str = "umi",
+    column: str = "sequence",
+    prefix_length: Optional[int] = None,
+    max_distance: int = 1,
+    output: str = "reads",
+) -> pl.DataFrame:
+    """
+    Deduplicate reads by UMI and sequence, clustering UMIs that differ by sequencing errors with the umi_tools directional method.
+    A UMI absorbs the UMIs of the same sequence within `max_distance` mismatches that have at most half (plus one) of its count.
+
+    Parameters:
+        data: A path to a Parquet file, a registered table, or a polars/pandas DataFrame with a UMI column, e.g. from [extract_umi](api.md#polars_bio.extract_umi).
+        umi_column: The name of the UMI column.
+        column: The name of the sequence column.
+        prefix_length: The number of leading bases of reads to group by together with the UMI. If *None*, whole sequences are compared.
+        max_distance: The maximum Hamming distance between UMIs of a cluster.
+        output: `reads` to keep one read per UMI group, preferring reads carrying the group's UMI, or `groups` to keep all reads annotated with their group.
+
+    Returns:
+        The reads with `group_id` and `final_umi` (the representative UMI of the group) columns. Reads without a UMI or sequence are dropped.
+    """
+    if output not in ("reads", "groups"):
+        raise ValueError("output must be 'reads' or 'groups'")
+    df, table_path = _qc_input(data, column)
+    if df is not None and umi_column not in df.columns:
+        raise ValueError(f"Input data must have a '{umi_column}' column")
+    groups = output == "groups"
+
+    if df is not None:
+        return py_umi_dedup_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            umi_column,
+            column,
+            prefix_length,
+            max_distance,
+            groups,
+        ).to_polars()
+    else:
+        return py_umi_dedup_scan(
+            ctx, table_path, umi_column, column, prefix_length, max_distance, groups
+        ).to_polars()
+
+
+def _sample_sheet(sample_sheet: Union[pl.DataFrame, pd.DataFrame, str]) -> pl.DataFrame:
+    """Reads a sample sheet given as a frame, a CSV path or a registered table."""
+    if isinstance(sample_sheet, pd.DataFrame):
+        sample_sheet = pl.from_pandas(sample_sheet)
+    elif isinstance(sample_sheet, str):
+        if sample_sheet.lower().endswith(".csv"):
+            sample_sheet = pl.read_csv(sample_sheet)
+        else:
+            sample_sheet = SQL.sql(f"SELECT * FROM {sample_sheet}").collect()
+    missing = [c for c in ("sample", "i7") if c not in sample_sheet.columns]
+    if missing:
+        raise ValueError(f"The sample sheet must have the columns: {missing}")
+    if "i5" not in sample_sheet.columns:
+        sample_sheet = sample_sheet.with_columns(pl.lit(None, pl.Utf8).alias("i5"))
+    return sample_sheet.select(
+        pl.col("sample").cast(pl.Utf8),
+        pl.col("i7").cast(pl.Utf8),
+        pl.col("i5").cast(pl.Utf8),
+    )
+
+
+def demultiplex(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    sample_sheet: Union[pl.DataFrame, pd.DataFrame, str],
+    max_mismatches: int = 1,
+    index_column: str = "description",
+) -> tuple:
+    """
+    Assign reads to samples by their index barcodes.
+    Reads matching no sample with at most `max_mismatches` mismatches per index, or matching several samples equally well, are `Undetermined`.
+
+    !!! note
+        If barcodes of different samples are too close for a read to be assigned unambiguously with `max_mismatches`, the number of mismatches is lowered until they are not, as bcl2fastq would ask to.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame.
+        sample_sheet: A polars/pandas DataFrame, a CSV path or a registered table with `sample`, `i7` and, for dual indexes, `i5` columns.
+        max_mismatches: The maximum number of mismatches per index.
+        index_column: The column holding the barcodes of reads, either an Illumina header comment such as `1:N:0:ACGTACGT+TTGGCCAA` or bare `i7+i5` barcodes.
+
+    Returns:
+        A tuple of the reads with `sample` and `barcode_mismatches` columns, and a summary with the `read_count`, `percent` and `perfect_barcode_count` (reads without mismatches) of every sample and of `Undetermined` reads.
+    """
+    sheet = _sample_sheet(sample_sheet)
+    samples = list(sheet.iter_rows())
+    df, table_path = _qc_input(data, index_column)
+
+    if df is not None:
+        reads = py_demultiplex_frame(
+            ctx, df.to_arrow().to_reader(), samples, index_column, max_mismatches
+        ).to_polars()
+    else:
+        reads = py_demultiplex_scan(
+            ctx, table_path, samples, index_column, max_mismatches
+        ).to_polars()
+
+    counts = reads.group_by("sample").agg(
+        pl.len().cast(pl.UInt64).alias("read_count"),
+        (pl.col("barcode_mismatches") == 0)
+        .sum()
+        .cast(pl.UInt64)
+        .alias("perfect_barcode_count"),
+    )
+    names = pl.DataFrame(
+        {"sample": sheet["sample"].to_list() + [UNDETERMINED_SAMPLE]}
+    )
+    summary = names.join(counts, on="sample", how="left").with_columns(
+        pl.col("read_count").fill_null(0),
+        pl.col("perfect_barcode_count").fill_null(0),
+    )
+    total = max(reads.height, 1)
+    summary = summary.with_columns(
+        (pl.col("read_count") * 100.0 / total).alias("percent")
+    ).select("sample", "read_count", "percent", "perfect_barcode_count")
+    return reads, summary
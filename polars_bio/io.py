@@ -303,6 +303,8 @@ class IOOperations:
         timeout: int = 300,
         compression_type: str = "auto",
         streaming: bool = False,
+        paired_path: Union[str, None] = None,
+        interleaved: bool = False,
     ) -> Union[pl.LazyFrame, pl.DataFrame]:
         """
         Read a FASTQ file into a LazyFrame.
@@ -318,6 +320,8 @@ class IOOperations:
             timeout: The timeout in seconds for reading the file from object storage.
             compression_type: The compression type of the FASTQ file. If not specified, it will be detected automatically based on the file extension. BGZF and GZIP compressions are supported ('bgz', 'gz').
             streaming: Whether to read the FASTQ file in streaming mode.
+            paired_path: The path to the R2 file of paired-end reads. Mates are read in lockstep with the R1 `path` into `name`, `seq1`, `qual1`, `seq2` and `qual2` columns, and an error is raised if their names get out of sync.
+            interleaved: Whether `path` is an interleaved paired-end file with mates in consecutive records, read into the same columns as with `paired_path`.
 
         !!! Example
 
@@ -351,6 +355,8 @@ class IOOperations:
         fastq_read_options = FastqReadOptions(
             thread_num=thread_num,
             object_storage_options=object_storage_options,
+            paired_path=paired_path,
+            interleaved=interleaved,
         )
         read_options = ReadOptions(fastq_read_options=fastq_read_options)
         if streaming:
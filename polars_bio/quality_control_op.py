@@ -1,27 +1,75 @@
+import queue
+import threading
 import polars as pl
 import pandas as pd
+import pyarrow as pa
 from pathlib import Path
-from typing import Union
+from typing import Optional, Union
 from polars_bio import read_fastq, overlap
-from polars_bio.polars_bio import py_base_sequence_content_frame, py_base_sequence_content_scan
+from polars_bio.polars_bio import (
+    InputFormat,
+    py_register_table,
+    py_base_sequence_content_frame,
+    py_base_sequence_content_scan,
+    stream_base_sequence_content_frame,
+    stream_base_sequence_content_scan,
+    py_base_sequence_quality_frame,
+    py_base_sequence_quality_scan,
+    py_per_sequence_quality_frame,
+    py_per_sequence_quality_scan,
+    py_per_sequence_gc_content_frame,
+    py_per_sequence_gc_content_scan,
+    py_gc_content_fit_frame,
+    py_sequence_length_distribution_frame,
+    py_sequence_length_distribution_scan,
+    py_sequence_duplication_levels_frame,
+    py_sequence_duplication_levels_scan,
+    py_overrepresented_sequences_frame,
+    py_overrepresented_sequences_scan,
+    py_adapter_content_frame,
+    py_adapter_content_scan,
+    py_kmer_count_frame,
+    py_kmer_count_scan,
+    py_detect_phred_encoding_frame,
+    py_detect_phred_encoding_scan,
+    py_reencode_quality_frame,
+    py_reencode_quality_scan,
+    py_fastq_qc_report_frame,
+    py_fastq_qc_report_scan,
+)
 from .context import ctx
+from .range_op_helpers import stream_wrapper
 
-def base_sequence_content(
-    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str]
-) -> pl.DataFrame:
+QC_FASTQ_TABLE = "qc_fastq"
+
+
+def _qc_input(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    column: str,
+    streaming: bool = False,
+) -> tuple:
+    """Returns a polars DataFrame to be passed as a frame or a path/table name to be scanned.
+    With `streaming`, FASTQ files are registered as a table and scanned instead of being read into memory,
+    and LazyFrames are kept lazy to be read with `_frame_reader`."""
     table_path = None
     df = None
 
     if isinstance(data, str):
         path = Path(data)
-        if not path.exists():
-            raise FileNotFoundError(f"File not found: {data}") 
-        if path.suffix.lower() in ['.fastq', '.fq']:
-            df = read_fastq(data).collect()
+        if path.suffix.lower() in ['.fastq', '.fq'] or path.name.lower().endswith(('.fastq.gz', '.fastq.bgz')):
+            if not path.exists():
+                raise FileNotFoundError(f"File not found: {data}")
+            if streaming:
+                py_register_table(ctx, data, QC_FASTQ_TABLE, InputFormat.Fastq, None)
+                table_path = QC_FASTQ_TABLE
+            else:
+                df = read_fastq(data).collect()
+        elif path.suffix and not path.exists():
+            raise FileNotFoundError(f"File not found: {data}")
         else:
             table_path = data
     elif isinstance(data, pl.LazyFrame):
-        df = data.collect()
+        df = data if streaming else data.collect()
     elif isinstance(data, pd.DataFrame):
         df = pl.from_pandas(data)
     elif isinstance(data, pl.DataFrame):
@@ -29,15 +77,848 @@ def base_sequence_content(
     else:
         raise TypeError(f"Unsupported input type: {type(data)}")
 
+    if df is not None and column not in _columns(df):
+        raise ValueError(f"Input data must have a '{column}' column")
+    return df, table_path
+
+
+def _columns(df: Union[pl.DataFrame, pl.LazyFrame]) -> list:
+    return df.collect_schema().names() if isinstance(df, pl.LazyFrame) else df.columns
+
+
+def _frame_reader(df: Union[pl.DataFrame, pl.LazyFrame]) -> pa.RecordBatchReader:
+    """Arrow reader over a frame. LazyFrames are run once by the streaming engine in a background thread
+    whose batches are handed over, a few at a time, as the reader is consumed."""
+    if isinstance(df, pl.DataFrame):
+        return df.to_arrow().to_reader()
+    schema = df.clear().collect().to_arrow().schema
+
+    def _batches():
+        batches = queue.Queue(maxsize=4)
+
+        def _push(batch: pl.DataFrame) -> pl.DataFrame:
+            batches.put(batch)
+            return batch.clear()
+
+        def _run():
+            try:
+                df.map_batches(_push, streamable=True).collect(streaming=True)
+                batches.put(None)
+            except Exception as e:
+                batches.put(e)
+
+        threading.Thread(target=_run, daemon=True).start()
+        while (batch := batches.get()) is not None:
+            if isinstance(batch, Exception):
+                raise batch
+            yield from batch.to_arrow().cast(schema).to_batches()
+
+    return pa.RecordBatchReader.from_batches(schema, _batches())
+
+
+def _quality_encoding(df, table_path, phred_offset: Union[int, str]) -> tuple:
+    """Resolves the declared Phred offset, or detects the encoding when set to `auto`, as an (encoding, offset) tuple.
+    Solexa scores are not Phred scores below Q10 and are rejected rather than being read with offset 64."""
+    if phred_offset == "auto":
+        if df is not None:
+            result = py_detect_phred_encoding_frame(
+                ctx, df.to_arrow().to_reader(), "quality_scores", 10_000
+            )
+        else:
+            result = py_detect_phred_encoding_scan(
+                ctx, table_path, "quality_scores", 10_000
+            )
+        encoding, offset, min_char, _ = result
+        if encoding == "solexa":
+            raise ValueError(
+                f"Quality scores look Solexa encoded (lowest character {min_char!r}), "
+                "convert them to Phred scores with reencode_quality(data, source='solexa') first"
+            )
+        return encoding, offset
+    if phred_offset not in (33, 64):
+        raise ValueError("phred_offset must be 33, 64 or 'auto'")
+    return f"phred{phred_offset}", phred_offset
+
+
+def _phred_offset(df, table_path, phred_offset: Union[int, str]) -> int:
+    """Resolves the declared Phred offset or detects it when set to `auto`."""
+    return _quality_encoding(df, table_path, phred_offset)[1]
+
+
+def base_sequence_content(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    group_by: Optional[Union[str, list]] = None,
+    column: str = "sequence",
+    percentages: bool = False,
+    streaming: bool = False,
+) -> Union[pl.DataFrame, pl.LazyFrame]:
+    """
+    Per-position base counts of reads. Bases are matched case-insensitively, IUPAC ambiguity codes and other characters are counted as `other`.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame.
+        group_by: Columns, e.g. sample, lane, read group or barcode, to compute the base content for separately in a single pass.
+        column: The name of the sequence column.
+        percentages: Whether to also report the percentage of each base category at each position (`a_percent`, ..., `other_percent`).
+        streaming: Whether to return a LazyFrame computing the result when collected. Frames are then read batch by batch and FASTQ files are scanned rather than being loaded into memory first.
+
+    Returns:
+        A DataFrame (or LazyFrame with `streaming`) with the `a_count`, `c_count`, `g_count`, `t_count`, `n_count` and `other_count` of each read position. Grouped results start with the grouping columns and also have a (1-based) `position` column.
+
+    !!! note
+        A streamed frame can only be scanned once, so the returned LazyFrame should be collected once.
+    """
+    if isinstance(group_by, str):
+        group_by = [group_by]
+    group_by = list(group_by or [])
+    df, table_path = _qc_input(data, column, streaming)
+    missing = [c for c in group_by if df is not None and c not in _columns(df)]
+    if missing:
+        raise ValueError(f"Input data must have the grouping columns: {missing}")
+
+    if streaming:
+        if df is not None:
+            pyldf = stream_base_sequence_content_frame(
+                ctx, _frame_reader(df), column, group_by, percentages
+            )
+        else:
+            pyldf = stream_base_sequence_content_scan(
+                ctx, table_path, column, group_by, percentages
+            )
+        return stream_wrapper(pyldf)
     if df is not None:
-        if "sequence" not in df.columns:
-            raise ValueError("Input data must have a 'sequence' column")
         return py_base_sequence_content_frame(
             ctx,
-            df.to_arrow().to_reader()
+            df.to_arrow().to_reader(),
+            column,
+            group_by,
+            percentages
         ).to_polars()
     else:
         return py_base_sequence_content_scan(
+            ctx,
+            table_path,
+            column,
+            group_by,
+            percentages
+        ).to_polars()
+
+
+def base_sequence_quality(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    phred_offset: Union[int, str] = 33,
+) -> pl.DataFrame:
+    """
+    Per-base sequence quality of FASTQ reads, computed from the `quality_scores` column.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame with a `quality_scores` column.
+        phred_offset: The quality encoding offset, 33 or 64, or `auto` to detect it with [detect_phred_encoding](api.md#polars_bio.detect_phred_encoding). Solexa encoded reads must first be converted with [reencode_quality](api.md#polars_bio.reencode_quality).
+
+    Returns:
+        A DataFrame with one row per (1-based) read position and the mean, median, lower/upper quartiles and 10th/90th percentiles of the Phred scores.
+    """
+    df, table_path = _qc_input(data, "quality_scores")
+    phred_offset = _phred_offset(df, table_path, phred_offset)
+
+    if df is not None:
+        return py_base_sequence_quality_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            phred_offset
+        ).to_polars()
+    else:
+        return py_base_sequence_quality_scan(
+            ctx,
+            table_path,
+            phred_offset
+        ).to_polars()
+
+
+def per_sequence_quality(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    phred_offset: Union[int, str] = 33,
+) -> pl.DataFrame:
+    """
+    Distribution of the mean Phred quality of FASTQ reads, as in FastQC's per-sequence quality scores module.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame with a `quality_scores` column.
+        phred_offset: The quality encoding offset, 33 or 64, or `auto` to detect it with [detect_phred_encoding](api.md#polars_bio.detect_phred_encoding). Solexa encoded reads must first be converted with [reencode_quality](api.md#polars_bio.reencode_quality).
+
+    Returns:
+        A DataFrame with the number of reads (`count`) per mean quality (`quality`, rounded down).
+    """
+    df, table_path = _qc_input(data, "quality_scores")
+    phred_offset = _phred_offset(df, table_path, phred_offset)
+
+    if df is not None:
+        return py_per_sequence_quality_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            phred_offset
+        ).to_polars()
+    else:
+        return py_per_sequence_quality_scan(
+            ctx,
+            table_path,
+            phred_offset
+        ).to_polars()
+
+
+def per_sequence_gc_content(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str]
+) -> pl.DataFrame:
+    """
+    Distribution of the GC percentage of reads together with the fitted normal distribution, as in FastQC's per-sequence GC content module.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame with a `sequence` column.
+
+    Returns:
+        A DataFrame with one row per GC percentage (0-100) with the observed number of reads (`count`) and the expected number of reads under a normal distribution centered on the mode of the observed one (`theoretical_count`).
+    """
+    df, table_path = _qc_input(data, "sequence")
+
+    if df is not None:
+        return py_per_sequence_gc_content_frame(
+            ctx,
+            df.to_arrow().to_reader()
+        ).to_polars()
+    else:
+        return py_per_sequence_gc_content_scan(
             ctx,
             table_path
         ).to_polars()
+
+
+def sequence_length_distribution(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    bin_size: int = 1,
+) -> pl.DataFrame:
+    """
+    Distribution of read lengths, optionally binned for long-read (ONT/PacBio) data.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame with a `sequence` column.
+        bin_size: The width of length bins. The default of 1 counts reads per exact length.
+
+    Returns:
+        A DataFrame with the first (`length`) and last (`length_end`) length of each non-empty bin and the number of reads in it (`count`).
+    """
+    if bin_size < 1:
+        raise ValueError("bin_size must be a positive integer")
+    df, table_path = _qc_input(data, "sequence")
+
+    if df is not None:
+        return py_sequence_length_distribution_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            bin_size
+        ).to_polars()
+    else:
+        return py_sequence_length_distribution_scan(
+            ctx,
+            table_path,
+            bin_size
+        ).to_polars()
+
+
+def sequence_duplication_levels(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    max_unique: int = 100_000,
+) -> tuple:
+    """
+    Sequence duplication levels of FASTQ reads, computed as in FastQC.
+
+    !!! note
+        Only the distinct sequences seen before `max_unique` of them were collected are tracked, reads longer than 75bp are truncated to 50bp, and the levels are extrapolated to the whole input.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame with a `sequence` column.
+        max_unique: The maximum number of distinct sequences to track.
+
+    Returns:
+        A tuple of a DataFrame with the percentage of deduplicated (`percent_deduplicated`) and of all (`percent_total`) sequences per duplication level, and the percentage of sequences remaining after deduplication.
+    """
+    df, table_path = _qc_input(data, "sequence")
+
+    if df is not None:
+        result = py_sequence_duplication_levels_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            max_unique
+        ).to_polars()
+    else:
+        result = py_sequence_duplication_levels_scan(
+            ctx,
+            table_path,
+            max_unique
+        ).to_polars()
+    percent_remaining = result["percent_remaining"][0] if len(result) > 0 else 0.0
+    return result.drop("percent_remaining"), percent_remaining
+
+
+def overrepresented_sequences(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    min_percentage: float = 0.1,
+    max_unique: int = 100_000,
+) -> pl.DataFrame:
+    """
+    Sequences making up more than `min_percentage` of all FASTQ reads, as in FastQC's overrepresented sequences module.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame with a `sequence` column.
+        min_percentage: The minimum percentage of all reads for a sequence to be reported.
+        max_unique: The maximum number of distinct sequences to track, see [sequence_duplication_levels](api.md#polars_bio.sequence_duplication_levels).
+
+    Returns:
+        A DataFrame with the overrepresented sequences, their `count` and `percentage` of all reads, sorted by decreasing count.
+    """
+    df, table_path = _qc_input(data, "sequence")
+
+    if df is not None:
+        return py_overrepresented_sequences_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            max_unique,
+            min_percentage
+        ).to_polars()
+    else:
+        return py_overrepresented_sequences_scan(
+            ctx,
+            table_path,
+            max_unique,
+            min_percentage
+        ).to_polars()
+
+
+def adapter_content(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    adapters: Optional[Union[dict, pl.DataFrame, pd.DataFrame]] = None,
+) -> pl.DataFrame:
+    """
+    Cumulative percentage of reads containing each adapter at or before each read position, as in FastQC's adapter content module.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame with a `sequence` column.
+        adapters: The adapters to search for, either as a dictionary of names to sequences or a DataFrame with `name` and `sequence` columns. By default the Illumina Universal, Illumina Small RNA 3'/5', Nextera Transposase and SOLiD Small RNA adapters are used.
+
+    Returns:
+        A DataFrame with the (1-based) `position`, the `adapter` name and the `percentage` of reads in which the adapter starts at or before this position.
+    """
+    if adapters is not None:
+        if isinstance(adapters, pd.DataFrame):
+            adapters = pl.from_pandas(adapters)
+        if isinstance(adapters, pl.DataFrame):
+            if not {"name", "sequence"}.issubset(adapters.columns):
+                raise ValueError("Adapter table must have 'name' and 'sequence' columns")
+            adapters = dict(zip(adapters["name"], adapters["sequence"]))
+        adapters = list(adapters.items())
+    df, table_path = _qc_input(data, "sequence")
+
+    if df is not None:
+        return py_adapter_content_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            adapters
+        ).to_polars()
+    else:
+        return py_adapter_content_scan(
+            ctx,
+            table_path,
+            adapters
+        ).to_polars()
+
+
+def kmer_count(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    k: int = 7,
+    canonical: bool = False,
+    min_count: int = 1,
+    positions: bool = False,
+    column: str = "sequence",
+) -> pl.DataFrame:
+    """
+    Count k-mers in a sequence column of FASTQ, FASTA or BAM-derived data.
+
+    !!! note
+        K-mers are 2-bit packed, so `k` can be at most 31, and k-mers containing bases other than A, C, G and T are skipped. Counts are aggregated by DataFusion, so they are merged across partitions and spilled to disk under a memory limit.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame.
+        k: The k-mer length.
+        canonical: Whether to count a k-mer and its reverse complement together, as the lexicographically smaller of the two.
+        min_count: The minimum total count of reported k-mers.
+        positions: Whether to report counts per (0-based) k-mer start position with the `enrichment` over the count expected from the overall k-mer and position frequencies.
+        column: The name of the sequence column.
+
+    Returns:
+        A DataFrame with `kmer` and `count` columns, and `position`, `total_count` and `enrichment` columns if `positions` is set.
+    """
+    if not 1 <= k <= 31:
+        raise ValueError("k must be between 1 and 31")
+    df, table_path = _qc_input(data, column)
+
+    if df is not None:
+        return py_kmer_count_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            column,
+            k,
+            canonical,
+            min_count,
+            positions
+        ).to_polars()
+    else:
+        return py_kmer_count_scan(
+            ctx,
+            table_path,
+            column,
+            k,
+            canonical,
+            min_count,
+            positions
+        ).to_polars()
+
+
+def detect_phred_encoding(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    sample_size: int = 10_000,
+    column: str = "quality_scores",
+) -> dict:
+    """
+    Detect the quality encoding of FASTQ reads from the range of quality characters of the first reads.
+
+    !!! note
+        Ranges compatible with both Phred+33 and Phred+64, e.g. of reads with only high quality scores, are reported as Phred+33.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame.
+        sample_size: The number of reads to sample.
+        column: The name of the quality column.
+
+    Returns:
+        A dictionary with the `encoding` (`phred33` for Sanger/Illumina 1.8+, `phred64` for Illumina 1.3-1.7 or `solexa`), its `offset` and the lowest (`min_char`) and highest (`max_char`) quality characters seen.
+    """
+    df, table_path = _qc_input(data, column)
+
+    if df is not None:
+        result = py_detect_phred_encoding_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            column,
+            sample_size
+        )
+    else:
+        result = py_detect_phred_encoding_scan(
+            ctx,
+            table_path,
+            column,
+            sample_size
+        )
+    return dict(zip(["encoding", "offset", "min_char", "max_char"], result))
+
+
+def reencode_quality(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    source: str,
+    target: str = "phred33",
+    column: str = "quality_scores",
+) -> pl.DataFrame:
+    """
+    Re-encode quality strings between the `phred33`, `phred64` and `solexa` encodings.
+    The same conversion is available in SQL as `reencode_quality(quality_scores, 'phred64', 'phred33')`.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame.
+        source: The encoding of the input, or `auto` to detect it with [detect_phred_encoding](api.md#polars_bio.detect_phred_encoding). Solexa encoded reads must first be converted with [reencode_quality](api.md#polars_bio.reencode_quality).
+        target: The encoding of the output.
+        column: The name of the quality column.
+
+    Returns:
+        The input with the quality column re-encoded.
+    """
+    df, table_path = _qc_input(data, column)
+    if source == "auto":
+        source = detect_phred_encoding(
+            df if df is not None else table_path, column=column
+        )["encoding"]
+
+    if df is not None:
+        return py_reencode_quality_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            column,
+            source,
+            target
+        ).to_polars()
+    else:
+        return py_reencode_quality_scan(
+            ctx,
+            table_path,
+            column,
+            source,
+            target
+        ).to_polars()
+
+
+DEFAULT_QC_THRESHOLDS = {
+    "duplication": {"warn": 70, "error": 50},
+    "n_content": {"warn": 5, "error": 20},
+    "overrepresented": {"warn": 0.1, "error": 1},
+    "quality_base_lower": {"warn": 10, "error": 5},
+    "quality_base_median": {"warn": 25, "error": 20},
+    "sequence": {"warn": 10, "error": 20},
+    "gc_sequence": {"warn": 15, "error": 30},
+    "quality_sequence": {"warn": 27, "error": 20},
+    "adapter": {"warn": 5, "error": 10},
+}
+"""FastQC's default pass/warn/fail thresholds, keyed as in its `limits.txt`."""
+
+
+def _status_above(value: float, limits: dict) -> str:
+    if value > limits["error"]:
+        return "fail"
+    if value > limits["warn"]:
+        return "warn"
+    return "pass"
+
+
+def _status_below(value: float, limits: dict) -> str:
+    if value < limits["error"]:
+        return "fail"
+    if value < limits["warn"]:
+        return "warn"
+    return "pass"
+
+
+def _worst(*statuses: str) -> str:
+    for status in ("fail", "warn"):
+        if status in statuses:
+            return status
+    return "pass"
+
+
+def _gc_content_fit(histogram: list) -> list:
+    """Normal distribution of `per_sequence_gc_content` fitted to the GC histogram of the report."""
+    if not histogram:
+        return [
+            {"gc_content": gc, "count": 0, "theoretical_count": 0.0}
+            for gc in range(101)
+        ]
+    observed = pl.DataFrame(
+        histogram, schema={"gc_content": pl.UInt64, "count": pl.UInt64}
+    )
+    return py_gc_content_fit_frame(
+        ctx, observed.to_arrow().to_reader()
+    ).to_polars().to_dicts()
+
+
+def fastq_qc_report(
+    data: Union[pl.DataFrame, pl.LazyFrame, pd.DataFrame, str],
+    thresholds: Optional[dict] = None,
+    phred_offset: Union[int, str] = 33,
+    adapters: Optional[Union[dict, pl.DataFrame, pd.DataFrame]] = None,
+    max_unique: int = 100_000,
+) -> dict:
+    """
+    Compute all FastQC modules in a single pass over FASTQ reads and grade them with FastQC's pass/warn/fail thresholds.
+
+    Parameters:
+        data: A path to a FASTQ or Parquet file, a registered table, or a polars/pandas DataFrame with `sequence` and `quality_scores` columns.
+        thresholds: Overrides of [DEFAULT_QC_THRESHOLDS](api.md#polars_bio.DEFAULT_QC_THRESHOLDS), e.g. `{"adapter": {"warn": 1, "error": 5}}`.
+        phred_offset: The quality encoding offset, 33 or 64, or `auto` to detect it. Solexa encoded reads must first be converted with [reencode_quality](api.md#polars_bio.reencode_quality).
+        adapters: The adapters to search for, see [adapter_content](api.md#polars_bio.adapter_content).
+        max_unique: The maximum number of distinct sequences to track, see [sequence_duplication_levels](api.md#polars_bio.sequence_duplication_levels).
+
+    Returns:
+        A JSON-serializable dictionary with one entry per module, each with its `status` (`pass`, `warn` or `fail`) and `data`.
+    """
+    limits = {key: dict(value) for key, value in DEFAULT_QC_THRESHOLDS.items()}
+    for key, value in (thresholds or {}).items():
+        if key not in limits:
+            raise ValueError(f"Unknown QC threshold: {key}")
+        limits[key].update(value)
+    if adapters is not None:
+        if isinstance(adapters, pd.DataFrame):
+            adapters = pl.from_pandas(adapters)
+        if isinstance(adapters, pl.DataFrame):
+            adapters = dict(zip(adapters["name"], adapters["sequence"]))
+        adapters = list(adapters.items())
+
+    df, table_path = _qc_input(data, "sequence")
+    if df is not None and "quality_scores" not in df.columns:
+        raise ValueError("Input data must have a 'quality_scores' column")
+    encoding, phred_offset = _quality_encoding(df, table_path, phred_offset)
+    # Report every sequence that is at least flagged as a warning
+    min_percentage = min(limits["overrepresented"].values())
+    if df is not None:
+        result = py_fastq_qc_report_frame(
+            ctx,
+            df.to_arrow().to_reader(),
+            phred_offset,
+            max_unique,
+            adapters,
+            min_percentage
+        ).to_polars()
+    else:
+        result = py_fastq_qc_report_scan(
+            ctx,
+            table_path,
+            phred_offset,
+            max_unique,
+            adapters,
+            min_percentage
+        ).to_polars()
+    row = result.row(0, named=True)
+    total = row["total_sequences"]
+
+    base_quality = row["base_sequence_quality"] or []
+    base_quality_status = _worst(
+        *(
+            _worst(
+                _status_below(p["lower_quartile"], limits["quality_base_lower"]),
+                _status_below(p["median"], limits["quality_base_median"]),
+            )
+            for p in base_quality
+        )
+    )
+
+    sequence_quality = row["per_sequence_quality"] or []
+    mode_quality = (
+        max(sequence_quality, key=lambda r: r["count"])["quality"]
+        if sequence_quality
+        else 0
+    )
+
+    base_content = []
+    n_content = []
+    gc_bases = acgt_bases = 0
+    for position, counts in enumerate(row["base_sequence_content"] or [], start=1):
+        acgt = sum(counts[f"{b}_count"] for b in "acgt")
+        gc_bases += counts["g_count"] + counts["c_count"]
+        acgt_bases += acgt
+        percentages = {
+            b.upper(): counts[f"{b}_count"] / acgt * 100 if acgt else 0.0
+            for b in "acgt"
+        }
+        base_content.append({"position": position, **percentages})
+        all_bases = acgt + counts["n_count"]
+        n_content.append(
+            {
+                "position": position,
+                "N": counts["n_count"] / all_bases * 100 if all_bases else 0.0,
+            }
+        )
+    content_difference = max(
+        (max(abs(p["A"] - p["T"]), abs(p["G"] - p["C"])) for p in base_content),
+        default=0.0,
+    )
+
+    gc_fit = _gc_content_fit(row["per_sequence_gc_content"] or [])
+    gc_deviation = (
+        sum(abs(r["count"] - r["theoretical_count"]) for r in gc_fit) / total * 100
+        if total
+        else 0.0
+    )
+
+    lengths = row["sequence_length_distribution"] or []
+    min_length, max_length = row["min_length"] or 0, row["max_length"] or 0
+    if min_length == 0:
+        length_status = "fail"
+    elif min_length != max_length:
+        length_status = "warn"
+    else:
+        length_status = "pass"
+
+    duplication = row["sequence_duplication_levels"] or []
+    percent_remaining = duplication[0]["percent_remaining"] if duplication else 100.0
+    overrepresented = row["overrepresented_sequences"] or []
+    adapter = row["adapter_content"] or []
+
+    return {
+        "basic_statistics": {
+            "status": "pass",
+            "data": {
+                "total_sequences": total,
+                "total_bases": row["total_bases"],
+                "min_length": min_length,
+                "max_length": max_length,
+                "gc_content": gc_bases / acgt_bases * 100 if acgt_bases else 0.0,
+                "encoding": encoding,
+            },
+        },
+        "per_base_sequence_quality": {
+            "status": base_quality_status,
+            "data": base_quality,
+        },
+        "per_sequence_quality_scores": {
+            "status": (
+                _status_below(mode_quality, limits["quality_sequence"])
+                if sequence_quality
+                else "pass"
+            ),
+            "data": sequence_quality,
+        },
+        "per_base_sequence_content": {
+            "status": _status_above(content_difference, limits["sequence"]),
+            "data": base_content,
+        },
+        "per_sequence_gc_content": {
+            "status": _status_above(gc_deviation, limits["gc_sequence"]),
+            "data": gc_fit,
+        },
+        "per_base_n_content": {
+            "status": _status_above(
+                max((p["N"] for p in n_content), default=0.0), limits["n_content"]
+            ),
+            "data": n_content,
+        },
+        "sequence_length_distribution": {"status": length_status, "data": lengths},
+        "sequence_duplication_levels": {
+            "status": _status_below(percent_remaining, limits["duplication"]),
+            "data": {
+                "percent_remaining": percent_remaining,
+                "levels": [
+                    {k: v for k, v in level.items() if k != "percent_remaining"}
+                    for level in duplication
+                ],
+            },
+        },
+        "overrepresented_sequences": {
+            "status": _status_above(
+                max((s["percentage"] for s in overrepresented), default=0.0),
+                limits["overrepresented"],
+            ),
+            "data": overrepresented,
+        },
+        "adapter_content": {
+            "status": _status_above(
+                max((a["percentage"] for a in adapter), default=0.0), limits["adapter"]
+            ),
+            "data": adapter,
+        },
+    }
+
+
+_FASTQC_MODULES = [
+    ("per_base_sequence_quality", "Per base sequence quality"),
+    ("per_sequence_quality_scores", "Per sequence quality scores"),
+    ("per_base_sequence_content", "Per base sequence content"),
+    ("per_sequence_gc_content", "Per sequence GC content"),
+    ("per_base_n_content", "Per base N content"),
+    ("sequence_length_distribution", "Sequence Length Distribution"),
+    ("sequence_duplication_levels", "Sequence Duplication Levels"),
+    ("overrepresented_sequences", "Overrepresented sequences"),
+    ("adapter_content", "Adapter Content"),
+]
+
+
+def _fastqc_rows(module: str, data) -> list:
+    """Header and value rows of a module in the `fastqc_data.txt` layout."""
+    if module == "per_base_sequence_quality":
+        columns = [
+            "mean",
+            "median",
+            "lower_quartile",
+            "upper_quartile",
+            "percentile_10",
+            "percentile_90",
+        ]
+        return [
+            [
+                "#Base",
+                "Mean",
+                "Median",
+                "Lower Quartile",
+                "Upper Quartile",
+                "10th Percentile",
+                "90th Percentile",
+            ]
+        ] + [[p["position"]] + [p[c] for c in columns] for p in data]
+    if module == "per_sequence_quality_scores":
+        return [["#Quality", "Count"]] + [[r["quality"], r["count"]] for r in data]
+    if module == "per_base_sequence_content":
+        return [["#Base", "G", "A", "T", "C"]] + [
+            [p["position"], p["G"], p["A"], p["T"], p["C"]] for p in data
+        ]
+    if module == "per_sequence_gc_content":
+        return [["#GC Content", "Count"]] + [
+            [r["gc_content"], r["count"]] for r in data
+        ]
+    if module == "per_base_n_content":
+        return [["#Base", "N-Count"]] + [[p["position"], p["N"]] for p in data]
+    if module == "sequence_length_distribution":
+        return [["#Length", "Count"]] + [[r["length"], r["count"]] for r in data]
+    if module == "sequence_duplication_levels":
+        return [
+            ["#Total Deduplicated Percentage", data["percent_remaining"]],
+            ["#Duplication Level", "Percentage of deduplicated", "Percentage of total"],
+        ] + [
+            [r["duplication_level"], r["percent_deduplicated"], r["percent_total"]]
+            for r in data["levels"]
+        ]
+    if module == "overrepresented_sequences":
+        return [["#Sequence", "Count", "Percentage", "Possible Source"]] + [
+            [r["sequence"], r["count"], r["percentage"], "No Hit"] for r in data
+        ]
+    if module == "adapter_content":
+        adapters = list(dict.fromkeys(r["adapter"] for r in data))
+        positions = {}
+        for r in data:
+            positions.setdefault(r["position"], {})[r["adapter"]] = r["percentage"]
+        return [["#Position"] + adapters] + [
+            [position] + [values.get(a, 0.0) for a in adapters]
+            for position, values in sorted(positions.items())
+        ]
+    raise ValueError(f"Unknown QC module: {module}")
+
+
+def write_fastqc_data(report: dict, path: str, filename: str) -> None:
+    """
+    Write a [fastq_qc_report](api.md#polars_bio.fastq_qc_report) in FastQC's `fastqc_data.txt` layout, so that it can be aggregated by MultiQC's FastQC module.
+
+    !!! note
+        MultiQC only picks up files whose name ends with `fastqc_data.txt`, e.g. `sample_fastqc_data.txt`. The sample name is taken from `filename`.
+
+    Parameters:
+        report: The QC report.
+        path: The output file path.
+        filename: The name of the FASTQ file the report was computed for.
+    """
+    stats = report["basic_statistics"]
+    encoding = (
+        "Sanger / Illumina 1.9"
+        if stats["data"]["encoding"] == "phred33"
+        else "Illumina 1.5"
+    )
+    min_length, max_length = stats["data"]["min_length"], stats["data"]["max_length"]
+    lines = [
+        "##FastQC\t0.12.1",
+        f">>Basic Statistics\t{stats['status']}",
+        "#Measure\tValue",
+        f"Filename\t{filename}",
+        "File type\tConventional base calls",
+        f"Encoding\t{encoding}",
+        f"Total Sequences\t{stats['data']['total_sequences']}",
+        f"Total Bases\t{stats['data']['total_bases']} bp",
+        "Sequences flagged as poor quality\t0",
+        "Sequence length\t"
+        + (
+            str(min_length)
+            if min_length == max_length
+            else f"{min_length}-{max_length}"
+        ),
+        f"%GC\t{round(stats['data']['gc_content'])}",
+        ">>END_MODULE",
+    ]
+    for module, title in _FASTQC_MODULES:
+        lines.append(f">>{title}\t{report[module]['status']}")
+        lines.extend(
+            "\t".join(str(v) for v in row)
+            for row in _fastqc_rows(module, report[module]["data"])
+        )
+        lines.append(">>END_MODULE")
+    with open(path, "w") as f:
+        f.write("\n".join(lines) + "\n")
@@ -6,7 +6,13 @@ import polars as pl
 from datafusion import col, literal
 from typing_extensions import TYPE_CHECKING, Union
 
-from polars_bio.polars_bio import ReadOptions
+from polars_bio.polars_bio import (
+    IntervalIndexInfo,
+    ReadOptions,
+    SvMatchOptions,
+    py_build_interval_index_frame,
+    py_build_interval_index_scan,
+)
 
 from .constants import DEFAULT_INTERVAL_COLUMNS
 from .context import ctx
@@ -16,7 +22,14 @@ from .interval_op_helpers import (
     prevent_column_collision,
     read_df_to_datafusion,
 )
-from .range_op_helpers import _validate_overlap_input, range_operation
+from .range_op_helpers import (
+    _validate_overlap_input,
+    indexed_range_operation,
+    liftover_operation,
+    range_operation,
+    sv_match_operation,
+)
+from .range_op_io import _df_to_reader
 
 __all__ = ["overlap", "nearest", "count_overlaps", "merge"]
 
@@ -43,6 +56,7 @@ class IntervalOperations:
         streaming: bool = False,
         read_options1: Union[ReadOptions, None] = None,
         read_options2: Union[ReadOptions, None] = None,
+        sorted_input: bool = False,
     ) -> Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame]:
         """
         Find pairs of overlapping genomic intervals.
@@ -50,7 +64,7 @@ class IntervalOperations:
 
         Parameters:
             df1: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table (see [register_vcf](api.md#polars_bio.register_vcf)). CSV with a header, BED and Parquet are supported.
-            df2: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED  and Parquet are supported.
+            df2: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED  and Parquet are supported. It can also be an interval index built with [build_index](api.md#polars_bio.build_index), which is reused instead of building the interval trees again.
             how: How to handle the overlaps on the two dataframes. inner: use intersection of the set of intervals from df1 and df2, optional.
             overlap_filter: FilterOp, optional. The type of overlap to consider(Weak or Strict). Strict for **0-based**, Weak for **1-based** coordinate systems.
             cols1: The names of columns containing the chromosome, start and end of the
@@ -64,6 +78,7 @@ class IntervalOperations:
             streaming: **EXPERIMENTAL** If True, use Polars [streaming](features.md#streaming) engine.
             read_options1: Additional options for reading the input files.
             read_options2: Additional options for reading the input files.
+            sorted_input: If True, the inputs are declared to be sorted by contig and start position and a streaming sweep-line algorithm is used instead of building an interval tree. Memory usage is bounded by the number of intervals overlapping at any locus. Both inputs must use the same contig order, otherwise an error is raised.
 
         Returns:
             **polars.LazyFrame** or polars.DataFrame or pandas.DataFrame of the overlapping intervals.
@@ -116,7 +131,12 @@ class IntervalOperations:
             columns_2=cols2,
             overlap_alg=algorithm,
             streaming=streaming,
+            sorted_input=sorted_input,
         )
+        if isinstance(df2, IntervalIndexInfo):
+            return indexed_range_operation(
+                df1, df2, range_options, output_type, ctx, read_options1
+            )
         return range_operation(
             df1, df2, range_options, output_type, ctx, read_options1, read_options2
         )
@@ -133,6 +153,7 @@ class IntervalOperations:
         output_type: str = "polars.LazyFrame",
         streaming: bool = False,
         read_options: Union[ReadOptions, None] = None,
+        sorted_input: bool = False,
     ) -> Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame]:
         """
         Find pairs of closest genomic intervals.
@@ -140,7 +161,7 @@ class IntervalOperations:
 
         Parameters:
             df1: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table (see [register_vcf](api.md#polars_bio.register_vcf)). CSV with a header, BED and Parquet are supported.
-            df2: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED  and Parquet are supported.
+            df2: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED  and Parquet are supported. It can also be an interval index built with [build_index](api.md#polars_bio.build_index), which is reused instead of building the interval trees again.
             overlap_filter: FilterOp, optional. The type of overlap to consider(Weak or Strict). Strict for **0-based**, Weak for **1-based** coordinate systems.
             cols1: The names of columns containing the chromosome, start and end of the
                 genomic intervals, provided separately for each set.
@@ -151,6 +172,7 @@ class IntervalOperations:
             output_type: Type of the output. default is "polars.LazyFrame", "polars.DataFrame", or "pandas.DataFrame" or "datafusion.DataFrame" are also supported.
             streaming: **EXPERIMENTAL** If True, use Polars [streaming](features.md#streaming) engine.
             read_options: Additional options for reading the input files.
+            sorted_input: If True, the inputs are declared to be sorted by contig and start position and a streaming sweep-line algorithm is used instead of building an interval tree. Memory usage is bounded by the number of intervals overlapping at any locus. Both inputs must use the same contig order, otherwise an error is raised.
 
 
         Returns:
@@ -179,7 +201,12 @@ class IntervalOperations:
             columns_1=cols1,
             columns_2=cols2,
             streaming=streaming,
+            sorted_input=sorted_input,
         )
+        if isinstance(df2, IntervalIndexInfo):
+            return indexed_range_operation(
+                df1, df2, range_options, output_type, ctx, read_options
+            )
         return range_operation(df1, df2, range_options, output_type, ctx, read_options)
 
     @staticmethod
@@ -194,6 +221,7 @@ class IntervalOperations:
         output_type: str = "polars.LazyFrame",
         streaming: bool = False,
         read_options: Union[ReadOptions, None] = None,
+        sorted_input: bool = False,
     ) -> Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame]:
         """
         Calculate intervals coverage.
@@ -201,7 +229,7 @@ class IntervalOperations:
 
         Parameters:
             df1: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table (see [register_vcf](api.md#polars_bio.register_vcf)). CSV with a header, BED and Parquet are supported.
-            df2: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED  and Parquet are supported.
+            df2: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED  and Parquet are supported. It can also be an interval index built with [build_index](api.md#polars_bio.build_index), which is reused instead of building the interval trees again.
             overlap_filter: FilterOp, optional. The type of overlap to consider(Weak or Strict). Strict for **0-based**, Weak for **1-based** coordinate systems.
             cols1: The names of columns containing the chromosome, start and end of the
                 genomic intervals, provided separately for each set.
@@ -212,6 +240,7 @@ class IntervalOperations:
             output_type: Type of the output. default is "polars.LazyFrame", "polars.DataFrame", or "pandas.DataFrame" or "datafusion.DataFrame" are also supported.
             streaming: **EXPERIMENTAL** If True, use Polars [streaming](features.md#streaming) engine.
             read_options: Additional options for reading the input files.
+            sorted_input: If True, the inputs are declared to be sorted by contig and start position and a streaming sweep-line algorithm is used instead of building an interval tree. Memory usage is bounded by the number of intervals overlapping at any locus. Both inputs must use the same contig order, otherwise an error is raised.
 
 
         Returns:
@@ -240,7 +269,12 @@ class IntervalOperations:
             columns_1=cols1,
             columns_2=cols2,
             streaming=streaming,
+            sorted_input=sorted_input,
         )
+        if isinstance(df2, IntervalIndexInfo):
+            return indexed_range_operation(
+                df1, df2, range_options, output_type, ctx, read_options
+            )
         return range_operation(df2, df1, range_options, output_type, ctx, read_options)
 
     @staticmethod
@@ -255,6 +289,7 @@ class IntervalOperations:
         output_type: str = "polars.LazyFrame",
         streaming: bool = False,
         naive_query: bool = True,
+        sorted_input: bool = False,
     ) -> Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame]:
         """
         Count pairs of overlapping genomic intervals.
@@ -262,7 +297,7 @@ class IntervalOperations:
 
         Parameters:
             df1: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table (see [register_vcf](api.md#polars_bio.register_vcf)). CSV with a header, BED and Parquet are supported.
-            df2: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED  and Parquet are supported.
+            df2: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED  and Parquet are supported. It can also be an interval index built with [build_index](api.md#polars_bio.build_index), which is reused instead of building the interval trees again.
             overlap_filter: FilterOp, optional. The type of overlap to consider(Weak or Strict). Strict for **0-based**, Weak for **1-based** coordinate systems.
             suffixes: Suffixes for the columns of the two overlapped sets.
             cols1: The names of columns containing the chromosome, start and end of the
@@ -273,6 +308,7 @@ class IntervalOperations:
             output_type: Type of the output. default is "polars.LazyFrame", "polars.DataFrame", or "pandas.DataFrame" or "datafusion.DataFrame" are also supported.
             naive_query: If True, use naive query for counting overlaps based on overlaps.
             streaming: **EXPERIMENTAL** If True, use Polars [streaming](features.md#streaming) engine.
+            sorted_input: If True, the inputs are declared to be sorted by contig and start position and a streaming sweep-line algorithm is used instead of building an interval tree. Memory usage is bounded by the number of intervals overlapping at any locus. Both inputs must use the same contig order, otherwise an error is raised.
         Returns:
             **polars.LazyFrame** or polars.DataFrame or pandas.DataFrame of the overlapping intervals.
 
@@ -315,7 +351,16 @@ class IntervalOperations:
         on_cols = [] if on_cols is None else on_cols
         cols1 = DEFAULT_INTERVAL_COLUMNS if cols1 is None else cols1
         cols2 = DEFAULT_INTERVAL_COLUMNS if cols2 is None else cols2
-        if naive_query:
+        if isinstance(df2, IntervalIndexInfo):
+            range_options = RangeOptions(
+                range_op=RangeOp.CountOverlapsNaive,
+                filter_op=overlap_filter,
+                suffixes=suffixes,
+                columns_1=cols1,
+                columns_2=df2.columns,
+            )
+            return indexed_range_operation(df1, df2, range_options, output_type, ctx)
+        if naive_query or sorted_input:
             range_options = RangeOptions(
                 range_op=RangeOp.CountOverlapsNaive,
                 filter_op=overlap_filter,
@@ -323,6 +368,7 @@ class IntervalOperations:
                 columns_1=cols1,
                 columns_2=cols2,
                 streaming=streaming,
+                sorted_input=sorted_input,
             )
             return range_operation(df2, df1, range_options, output_type, ctx)
         df1 = read_df_to_datafusion(my_ctx, df1)
@@ -416,6 +462,199 @@ class IntervalOperations:
 
         return convert_result(df, output_type, streaming)
 
+    @staticmethod
+    def build_index(
+        df: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
+        name: str,
+        cols: Union[list[str], None] = ["chrom", "start", "end"],
+        persist: bool = False,
+        read_options: Union[ReadOptions, None] = None,
+    ) -> IntervalIndexInfo:
+        """
+        Build a named interval index once and keep it in the session, so that it can be reused by many
+        [overlap](api.md#polars_bio.overlap), [nearest](api.md#polars_bio.nearest), [count_overlaps](api.md#polars_bio.count_overlaps)
+        and [coverage](api.md#polars_bio.coverage) calls (passed as `df2`) without rebuilding the interval trees.
+
+        Parameters:
+            df: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table. CSV with a header, BED and Parquet are supported.
+            name: The name of the index. Building an index with an existing name replaces it.
+            cols: The names of columns containing the chromosome, start and end of the genomic intervals.
+            persist: If True, the indexed intervals are also saved as a Parquet file in the session catalog directory, so that the index can be rebuilt later with [load_index](api.md#polars_bio.load_index).
+            read_options: Additional options for reading the input file.
+
+        Returns:
+            Information about the index (name, columns, number of intervals and the Parquet path if persisted).
+
+        Example:
+            ```python
+            import polars_bio as pb
+            exons = pb.build_index("/tmp/gencode_exons.parquet", "exons")
+            for sample in samples:
+                pb.count_overlaps(sample, exons, output_type="polars.DataFrame")
+            ```
+        """
+        cols = DEFAULT_INTERVAL_COLUMNS if cols is None else list(cols)
+        if isinstance(df, str):
+            return py_build_interval_index_scan(
+                ctx, df, name, cols, read_options, persist
+            )
+        return py_build_interval_index_frame(
+            ctx, _df_to_reader(df, cols[0]), name, cols, persist
+        )
+
+    @staticmethod
+    def load_index(
+        path: str,
+        name: str,
+        cols: Union[list[str], None] = ["chrom", "start", "end"],
+    ) -> IntervalIndexInfo:
+        """
+        Load an interval index persisted with [build_index](api.md#polars_bio.build_index) (`persist=True`).
+        Only the intervals are persisted, so the interval trees are rebuilt from them as with `build_index` on the Parquet file.
+
+        Parameters:
+            path: The path to the Parquet file of the persisted index.
+            name: The name of the index in the current session.
+            cols: The names of columns containing the chromosome, start and end of the genomic intervals.
+        """
+        return IntervalOperations.build_index(path, name, cols)
+
+    @staticmethod
+    def drop_index(name: str) -> bool:
+        """
+        Remove an interval index from the session. Returns False if there was no index with the given name.
+        """
+        return ctx.drop_interval_index(name)
+
+    @staticmethod
+    def liftover(
+        df: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
+        chain_path: str,
+        overlap_filter: FilterOp = FilterOp.Strict,
+        min_match: float = 0.95,
+        cols: Union[list[str], None] = ["chrom", "start", "end"],
+        output_type: str = "polars.LazyFrame",
+        read_options: Union[ReadOptions, None] = None,
+    ) -> tuple[
+        Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame],
+        Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame],
+    ]:
+        """
+        Convert genomic intervals between assemblies (e.g. hg19 to hg38 or CHM13) using a [UCSC chain file](https://genome.ucsc.edu/goldenPath/help/chain.html).
+        UCSC liftOver inspired API.
+
+        Parameters:
+            df: Can be a path to a file, a polars DataFrame, or a pandas DataFrame or a registered table (see [register_vcf](api.md#polars_bio.register_vcf)). CSV with a header, BED, VCF and Parquet are supported.
+            chain_path: The path to the chain file, plain or gzip-compressed (`.gz`).
+            overlap_filter: FilterOp, optional. The coordinate system of the input intervals. Strict for **0-based**, Weak for **1-based** coordinate systems (e.g. VCF).
+            min_match: The minimum fraction of bases of an interval that has to be mapped by a single chain.
+            cols: The names of columns containing the chromosome, start and end of the genomic intervals.
+            output_type: Type of the output. default is "polars.LazyFrame", "polars.DataFrame", or "pandas.DataFrame" or "datafusion.DataFrame" are also supported.
+            read_options: Additional options for reading the input file.
+
+        Returns:
+            A tuple of the mapped and unmapped intervals.
+            Mapped intervals keep all input columns, with the chromosome, start and end replaced by the new coordinates, and an additional `liftover_strand` column. If an interval is mapped to the reverse strand, `ref` and `alt` alleles are reverse complemented. The padding base of indels then ends rather than starts the alleles, with the coordinates still covering `ref`; normalize them against the target reference, e.g. with `bcftools norm`, to get left-anchored VCF records.
+            Unmapped intervals keep the original coordinates and have an additional `reason` column: `no_chain`, `no_overlap`, `partially_deleted`, `split` (the interval is spread over several chains) or `duplicated` (the interval is mapped by several chains).
+
+        Example:
+            ```python
+            import polars_bio as pb
+            mapped, unmapped = pb.liftover(
+                "/tmp/variants.vcf.gz",
+                "/tmp/hg19ToHg38.over.chain.gz",
+                overlap_filter=pb.FilterOp.Weak,
+                output_type="polars.DataFrame",
+            )
+            ```
+        """
+        cols = DEFAULT_INTERVAL_COLUMNS if cols is None else list(cols)
+        if not 0 < min_match <= 1:
+            raise ValueError("min_match must be in the range (0, 1]")
+        return liftover_operation(
+            df,
+            chain_path,
+            cols,
+            overlap_filter,
+            min_match,
+            output_type,
+            ctx,
+            read_options,
+        )
+
+    @staticmethod
+    def sv_match(
+        df1: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
+        df2: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
+        max_distance: int = 500,
+        min_reciprocal_overlap: float = 0.0,
+        match_type: bool = True,
+        use_confidence_intervals: bool = True,
+        suffixes: tuple[str, str] = ("_1", "_2"),
+        output_type: str = "polars.LazyFrame",
+        read_options1: Union[ReadOptions, None] = None,
+        read_options2: Union[ReadOptions, None] = None,
+    ) -> Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame]:
+        """
+        Match structural variant calls against a base (e.g. truth) set and return the best one-to-one matching pairs.
+        Truvari inspired API.
+
+        Parameters:
+            df1: SV calls. Can be a path to a VCF file, a polars DataFrame, or a pandas DataFrame or a registered table (see [register_vcf](api.md#polars_bio.register_vcf)) with the VCF columns.
+            df2: Base SV calls, in the same form as `df1`.
+            max_distance: The maximum distance between the start breakpoints and between the end breakpoints (or breakend mates) of matching calls.
+            min_reciprocal_overlap: The minimum reciprocal overlap of matching calls. It is not applied to insertions and breakends.
+            match_type: If True, only calls of the same SV type (without subtypes, e.g. `DUP:TANDEM` is `DUP`) are matched.
+            use_confidence_intervals: If True, breakpoints are widened by the `CIPOS` and `CIEND` confidence intervals before computing distances.
+            suffixes: Suffixes for the columns of the two sets.
+            output_type: Type of the output. default is "polars.LazyFrame", "polars.DataFrame", or "pandas.DataFrame" or "datafusion.DataFrame" are also supported.
+            read_options1: Additional options for reading the first input file.
+            read_options2: Additional options for reading the second input file.
+
+        Returns:
+            Pairs of matching calls with the columns of both sets and the match metrics: `svtype_1`, `svtype_2`, `start_distance`, `end_distance`, `reciprocal_overlap`, `size_similarity` and `score` (the mean of the available metrics, with the breakpoint distance scaled by `max_distance`).
+
+        Note:
+            SV type, length and confidence intervals are read from the `SVTYPE`, `SVLEN`, `END`, `CIPOS` and `CIEND` INFO fields, so they have to be included in `info_fields` of `VcfReadOptions`.
+            If `SVTYPE` is missing, the type is derived from the alleles. Breakend (`BND`) calls are matched on both breakends, with the mate position parsed from the ALT allele.
+            Each call is matched at most once, pairs are assigned greedily by decreasing score.
+
+        Example:
+            ```python
+            import polars_bio as pb
+            info = pb.VcfReadOptions(info_fields=["SVTYPE", "SVLEN", "END", "CIPOS", "CIEND"])
+            read_options = pb.ReadOptions(vcf_read_options=info)
+            matches = pb.sv_match(
+                "/tmp/calls.vcf.gz",
+                "/tmp/truth.vcf.gz",
+                min_reciprocal_overlap=0.5,
+                output_type="polars.DataFrame",
+                read_options1=read_options,
+                read_options2=read_options,
+            )
+            ```
+        """
+        if max_distance < 0:
+            raise ValueError("max_distance must be non-negative")
+        if not 0 <= min_reciprocal_overlap <= 1:
+            raise ValueError("min_reciprocal_overlap must be in the range [0, 1]")
+        sv_match_options = SvMatchOptions(
+            max_distance=max_distance,
+            min_reciprocal_overlap=min_reciprocal_overlap,
+            match_type=match_type,
+            use_confidence_intervals=use_confidence_intervals,
+            suffixes=suffixes,
+        )
+        return sv_match_operation(
+            df1,
+            df2,
+            sv_match_options,
+            output_type,
+            ctx,
+            read_options1,
+            read_options2,
+        )
+
     @staticmethod
     def merge(
         df: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
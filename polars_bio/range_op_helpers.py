@@ -1,14 +1,24 @@
 from pathlib import Path
 from typing import Union
 
+import datafusion
 import pandas as pd
 import polars as pl
 
 from polars_bio.polars_bio import (
     BioSessionContext,
+    FilterOp,
+    IntervalIndexInfo,
     RangeOp,
     RangeOptions,
     ReadOptions,
+    SvMatchOptions,
+    indexed_range_operation_frame,
+    indexed_range_operation_scan,
+    py_liftover_frame,
+    py_liftover_scan,
+    py_sv_match_frame,
+    py_sv_match_scan,
     range_operation_frame,
     range_operation_scan,
     stream_range_operation_scan,
@@ -120,6 +130,98 @@ def range_operation(
                 )
 
 
+def indexed_range_operation(
+    df: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
+    index: IntervalIndexInfo,
+    range_options: RangeOptions,
+    output_type: str,
+    ctx: BioSessionContext,
+    read_options: Union[ReadOptions, None] = None,
+) -> Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame]:
+    ctx.sync_options()
+    if isinstance(df, str):
+        result = indexed_range_operation_scan(
+            ctx, index.name, df, range_options, read_options
+        )
+    else:
+        result = indexed_range_operation_frame(
+            ctx,
+            index.name,
+            _df_to_reader(df, range_options.columns_1[0]),
+            range_options,
+        )
+    return _convert_result(result, output_type)
+
+
+def liftover_operation(
+    df: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
+    chain_path: str,
+    columns: list[str],
+    filter_op: FilterOp,
+    min_match: float,
+    output_type: str,
+    ctx: BioSessionContext,
+    read_options: Union[ReadOptions, None] = None,
+) -> tuple:
+    ctx.sync_options()
+    if isinstance(df, str):
+        mapped, unmapped = py_liftover_scan(
+            ctx, df, chain_path, columns, filter_op, min_match, read_options
+        )
+    else:
+        mapped, unmapped = py_liftover_frame(
+            ctx,
+            _df_to_reader(df, columns[0]),
+            chain_path,
+            columns,
+            filter_op,
+            min_match,
+        )
+    return _convert_result(mapped, output_type), _convert_result(
+        unmapped, output_type
+    )
+
+
+def sv_match_operation(
+    df1: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
+    df2: Union[str, pl.DataFrame, pl.LazyFrame, pd.DataFrame],
+    sv_match_options: SvMatchOptions,
+    output_type: str,
+    ctx: BioSessionContext,
+    read_options1: Union[ReadOptions, None] = None,
+    read_options2: Union[ReadOptions, None] = None,
+) -> Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame]:
+    ctx.sync_options()
+    if isinstance(df1, str) and isinstance(df2, str):
+        result = py_sv_match_scan(
+            ctx, df1, df2, sv_match_options, read_options1, read_options2
+        )
+    else:
+        result = py_sv_match_frame(
+            ctx,
+            _df_to_reader(df1, "chrom"),
+            _df_to_reader(df2, "chrom"),
+            sv_match_options,
+        )
+    return _convert_result(result, output_type)
+
+
+def _convert_result(
+    result: datafusion.DataFrame, output_type: str
+) -> Union[pl.LazyFrame, pl.DataFrame, pd.DataFrame, datafusion.DataFrame]:
+    if output_type == "polars.LazyFrame":
+        return result.to_polars().lazy()
+    elif output_type == "polars.DataFrame":
+        return result.to_polars()
+    elif output_type == "pandas.DataFrame":
+        return result.to_pandas()
+    elif output_type == "datafusion.DataFrame":
+        return result
+    raise ValueError(
+        "Only polars.LazyFrame, polars.DataFrame, and pandas.DataFrame are supported"
+    )
+
+
 def _validate_overlap_input(col1, col2, on_cols, suffixes, output_type, how):
     # TODO: Add support for on_cols ()
     assert on_cols is None, "on_cols is not supported yet"
@@ -180,6 +180,8 @@ class SQL:
         timeout: int = 300,
         enable_request_payer: bool = False,
         compression_type: str = "auto",
+        paired_path: Union[str, None] = None,
+        interleaved: bool = False,
     ) -> None:
         """
         Register a FASTQ file as a Datafusion table.
@@ -195,6 +197,8 @@ class SQL:
             compression_type: The compression type of the FASTQ file. If not specified, it will be detected automatically based on the file extension. BGZF and GZIP compression is supported ('bgz' and 'gz').
             max_retries:  The maximum number of retries for reading the file from object storage.
             timeout: The timeout in seconds for reading the file from object storage.
+            paired_path: The path to the R2 file of paired-end reads. Mates are read in lockstep with the R1 `path` into `name`, `seq1`, `qual1`, `seq2` and `qual2` columns, and an error is raised if their names get out of sync.
+            interleaved: Whether `path` is an interleaved paired-end file with mates in consecutive records, read into the same columns as with `paired_path`.
 
         !!! Example
             ```python
@@ -238,6 +242,8 @@ class SQL:
         fastq_read_options = FastqReadOptions(
             thread_num=thread_num,
             object_storage_options=object_storage_options,
+            paired_path=paired_path,
+            interleaved=interleaved,
         )
         read_options = ReadOptions(fastq_read_options=fastq_read_options)
         py_register_table(ctx, path, name, InputFormat.Fastq, read_options)
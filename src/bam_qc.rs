@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::sync::Arc;
+
+use arrow::array::{StringBuilder, UInt64Builder};
+use arrow::record_batch::RecordBatch;
+use arrow_array::{Array, ArrayRef, BooleanArray, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use datafusion::dataframe::DataFrame;
+use datafusion::error::{DataFusionError, Result};
+use exon::ExonSession;
+use flate2::read::MultiGzDecoder;
+use log::{debug, info};
+
+use crate::quality_control::string_array;
+
+const BAM_MAGIC: &[u8; 4] = b"BAM\x01";
+const UNPLACED_CHROM: &str = "*";
+
+/// Flags of primary alignments, i.e. neither secondary nor supplementary.
+const PRIMARY: &str = "flags & 2304 = 0";
+
+/// The `samtools flagstat` categories and the conditions on `flags` of the
+/// alignments they count. Pair categories only count primary alignments.
+fn flagstat_categories() -> Vec<(&'static str, String)> {
+    vec![
+        ("total", "TRUE".to_string()),
+        ("primary", PRIMARY.to_string()),
+        ("secondary", "flags & 256 <> 0".to_string()),
+        ("supplementary", "flags & 2048 <> 0".to_string()),
+        ("duplicates", "flags & 1024 <> 0".to_string()),
+        (
+            "primary_duplicates",
+            format!("flags & 1024 <> 0 AND {PRIMARY}"),
+        ),
+        ("mapped", "flags & 4 = 0".to_string()),
+        ("primary_mapped", format!("flags & 4 = 0 AND {PRIMARY}")),
+        ("paired", format!("flags & 1 <> 0 AND {PRIMARY}")),
+        ("read1", format!("flags & 65 = 65 AND {PRIMARY}")),
+        ("read2", format!("flags & 129 = 129 AND {PRIMARY}")),
+        (
+            "properly_paired",
+            format!("flags & 3 = 3 AND flags & 4 = 0 AND {PRIMARY}"),
+        ),
+        (
+            "with_mate_mapped",
+            format!("flags & 1 <> 0 AND flags & 12 = 0 AND {PRIMARY}"),
+        ),
+        (
+            "singletons",
+            format!("flags & 1 <> 0 AND flags & 12 = 8 AND {PRIMARY}"),
+        ),
+        (
+            "mate_on_different_chrom",
+            format!("flags & 1 <> 0 AND flags & 12 = 0 AND {PRIMARY} AND mate_chrom <> chrom"),
+        ),
+        (
+            "mate_on_different_chrom_mapq5",
+            format!(
+                "flags & 1 <> 0 AND flags & 12 = 0 AND {PRIMARY} AND mate_chrom <> chrom AND mapping_quality >= 5"
+            ),
+        ),
+    ]
+}
+
+/// Casts a count column of an aggregate to `UInt64`.
+pub(crate) fn count_array(array: &ArrayRef) -> Result<UInt64Array> {
+    let counts = arrow::compute::cast(array, &DataType::UInt64)?;
+    counts
+        .as_any()
+        .downcast_ref::<UInt64Array>()
+        .cloned()
+        .ok_or_else(|| DataFusionError::Internal("Expected UInt64Array".to_string()))
+}
+
+/// Counts the alignments of a BAM table by `samtools flagstat` category,
+/// separately for alignments passing and failing QC (flag 0x200). The counts
+/// are aggregated in parallel across the partitions of the table and returned
+/// as one row per category with `qc_passed` and `qc_failed` columns.
+pub(crate) async fn do_flagstat(ctx: &ExonSession, table_name: String) -> Result<DataFrame> {
+    let categories = flagstat_categories();
+    let counts = categories
+        .iter()
+        .map(|(name, condition)| {
+            format!("SUM(CASE WHEN {condition} THEN 1 ELSE 0 END) AS \"{name}\"")
+        })
+        .collect::<Vec<String>>()
+        .join(",\n            ");
+    let query = format!(
+        r#"
+        SELECT
+            flags & 512 <> 0 AS qc_failed,
+            {counts}
+        FROM {table_name}
+        GROUP BY flags & 512 <> 0
+        "#,
+    );
+    debug!("Query: {}", query);
+    let batches = ctx.session.sql(&query).await?.collect().await?;
+
+    let mut passed = vec![0u64; categories.len()];
+    let mut failed = vec![0u64; categories.len()];
+    for batch in batches.iter() {
+        let qc_failed = arrow::compute::cast(batch.column(0), &DataType::Boolean)?;
+        let qc_failed = qc_failed
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| DataFusionError::Internal("Expected BooleanArray".to_string()))?;
+        for (category, column) in batch.columns().iter().skip(1).enumerate() {
+            let counts = count_array(column)?;
+            for row in 0..batch.num_rows() {
+                let target = if qc_failed.value(row) {
+                    &mut failed
+                } else {
+                    &mut passed
+                };
+                target[category] += counts.value(row);
+            }
+        }
+    }
+    info!(
+        "Counted {} QC-passed and {} QC-failed alignments",
+        passed[0], failed[0]
+    );
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("category", DataType::Utf8, false),
+        Field::new("qc_passed", DataType::UInt64, false),
+        Field::new("qc_failed", DataType::UInt64, false),
+    ]));
+    let names: Vec<&str> = categories.iter().map(|(name, _)| *name).collect();
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(names)),
+            Arc::new(UInt64Array::from(passed)),
+            Arc::new(UInt64Array::from(failed)),
+        ],
+    )?;
+    ctx.session.read_batch(batch)
+}
+
+fn read_i32(reader: &mut impl Read) -> std::io::Result<i32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+/// Reads the reference sequence names and lengths from the header of a local
+/// BAM file, in header order.
+pub(crate) fn read_bam_references(path: &str) -> Result<Vec<(String, u64)>> {
+    let mut reader = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != BAM_MAGIC {
+        return Err(DataFusionError::Execution(format!(
+            "Not a BAM file: {}",
+            path
+        )));
+    }
+    let text_length = read_i32(&mut reader)? as u64;
+    std::io::copy(&mut (&mut reader).take(text_length), &mut std::io::sink())?;
+    let reference_count = read_i32(&mut reader)?;
+    let mut references = Vec::with_capacity(reference_count.max(0) as usize);
+    for _ in 0..reference_count {
+        let mut name = vec![0u8; read_i32(&mut reader)?.max(0) as usize];
+        reader.read_exact(&mut name)?;
+        let name = String::from_utf8_lossy(&name)
+            .trim_end_matches('\0')
+            .to_string();
+        references.push((name, read_i32(&mut reader)? as u64));
+    }
+    info!("Read {} references from: {}", references.len(), path);
+    Ok(references)
+}
+
+/// Counts the mapped and unmapped alignments of a BAM table per reference
+/// sequence like `samtools idxstats`, with unplaced reads under `*`. With the
+/// references of the BAM header, rows follow the header order and include
+/// the reference lengths; otherwise they are sorted by name and lengths are
+/// null.
+pub(crate) async fn do_idxstats(
+    ctx: &ExonSession,
+    table_name: String,
+    references: Option<Vec<(String, u64)>>,
+) -> Result<DataFrame> {
+    let query = format!(
+        r#"
+        SELECT
+            COALESCE(chrom, '{UNPLACED_CHROM}') AS chrom,
+            SUM(CASE WHEN flags & 4 = 0 THEN 1 ELSE 0 END) AS mapped,
+            SUM(CASE WHEN flags & 4 <> 0 THEN 1 ELSE 0 END) AS unmapped
+        FROM {table_name}
+        GROUP BY COALESCE(chrom, '{UNPLACED_CHROM}')
+        "#,
+    );
+    debug!("Query: {}", query);
+    let batches = ctx.session.sql(&query).await?.collect().await?;
+    let mut counts: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for batch in batches.iter() {
+        let chroms = string_array(batch.column(0))?;
+        let (mapped, unmapped) = (count_array(batch.column(1))?, count_array(batch.column(2))?);
+        for row in 0..batch.num_rows() {
+            counts.insert(
+                chroms.value(row).to_string(),
+                (mapped.value(row), unmapped.value(row)),
+            );
+        }
+    }
+
+    let unplaced = counts.remove(UNPLACED_CHROM).unwrap_or_default();
+    let mut rows: Vec<(String, Option<u64>, (u64, u64))> = Vec::new();
+    if let Some(references) = references {
+        for (name, length) in references {
+            let count = counts.remove(&name).unwrap_or_default();
+            rows.push((name, Some(length), count));
+        }
+    }
+    rows.extend(counts.into_iter().map(|(name, count)| (name, None, count)));
+    rows.push((UNPLACED_CHROM.to_string(), Some(0), unplaced));
+
+    let (mut chroms, mut lengths, mut mapped, mut unmapped) = (
+        StringBuilder::new(),
+        UInt64Builder::new(),
+        UInt64Builder::new(),
+        UInt64Builder::new(),
+    );
+    for (chrom, length, (mapped_count, unmapped_count)) in rows {
+        chroms.append_value(chrom);
+        lengths.append_option(length);
+        mapped.append_value(mapped_count);
+        unmapped.append_value(unmapped_count);
+    }
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("chrom", DataType::Utf8, false),
+        Field::new("length", DataType::UInt64, true),
+        Field::new("mapped", DataType::UInt64, false),
+        Field::new("unmapped", DataType::UInt64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(chroms.finish()),
+            Arc::new(lengths.finish()),
+            Arc::new(mapped.finish()),
+            Arc::new(unmapped.finish()),
+        ],
+    )?;
+    ctx.session.read_batch(batch)
+}
+
+/// Quoted grouping columns of a query, with a trailing comma when not empty.
+fn group_columns(group_by: &[String]) -> String {
+    group_by
+        .iter()
+        .map(|column| format!("\"{column}\", "))
+        .collect()
+}
+
+fn partition_by(group_by: &[String], extra: &[&str]) -> String {
+    let columns: Vec<String> = group_by
+        .iter()
+        .map(|column| format!("\"{column}\""))
+        .chain(extra.iter().map(|column| column.to_string()))
+        .collect();
+    if columns.is_empty() {
+        String::new()
+    } else {
+        format!("PARTITION BY {}", columns.join(", "))
+    }
+}
+
+/// Histogram of insert sizes of proper pairs like Picard
+/// `CollectInsertSizeMetrics`. As in Picard, every pair is counted once from
+/// its primary, QC-passed read 2 alignment, so mates need not be in the
+/// table. The BAM reader has no template length, so the insert size is the
+/// span from the leftmost to the rightmost aligned base of the pair, assuming
+/// the mate spans as many reference bases as the read. Pairs are split by
+/// orientation into `FR` (forward read 5' end before the reverse one), `RF`
+/// and `TANDEM` (both mates on the same strand), and grouped by the
+/// `group_by` columns.
+pub(crate) async fn do_insert_size_histogram(
+    ctx: &ExonSession,
+    table_name: String,
+    group_by: Vec<String>,
+    include_duplicates: bool,
+) -> Result<DataFrame> {
+    let duplicates = if include_duplicates {
+        ""
+    } else {
+        "AND flags & 1024 = 0"
+    };
+    let groups = group_columns(&group_by);
+    let query = format!(
+        r#"
+        WITH second_reads AS (
+            SELECT
+                {groups}flags,
+                CAST(start AS BIGINT) AS read_start,
+                CAST("end" AS BIGINT) AS read_end,
+                CAST(mate_start AS BIGINT) AS mate_start,
+                CAST(mate_start AS BIGINT) + CAST("end" AS BIGINT) - CAST(start AS BIGINT) AS mate_end
+            FROM {table_name}
+            WHERE flags & 131 = 131 AND flags & 12 = 0 AND flags & 2816 = 0 {duplicates}
+                AND chrom = mate_chrom
+        ),
+        pairs AS (
+            SELECT
+                {groups}
+                CASE
+                    WHEN flags & 16 = (flags & 32) / 2 THEN 'TANDEM'
+                    WHEN (CASE WHEN flags & 16 = 0 THEN read_start ELSE mate_start END)
+                        < (CASE WHEN flags & 16 = 0 THEN mate_end ELSE read_end END) THEN 'FR'
+                    ELSE 'RF'
+                END AS orientation,
+                (CASE WHEN read_end > mate_end THEN read_end ELSE mate_end END)
+                    - (CASE WHEN read_start < mate_start THEN read_start ELSE mate_start END)
+                    + 1 AS insert_size
+            FROM second_reads
+        ),
+        counts AS (
+            SELECT {groups}orientation, insert_size, COUNT(*) AS "count"
+            FROM pairs
+            GROUP BY {groups}orientation, insert_size
+        )
+        SELECT
+            *,
+            CAST("count" AS DOUBLE) / SUM("count") OVER ({partition}) AS fraction
+        FROM counts
+        ORDER BY {groups}orientation, insert_size
+        "#,
+        partition = partition_by(&group_by, &["orientation"]),
+    );
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
+
+/// Distribution of the mapping qualities of primary, QC-passed mapped
+/// alignments grouped by the `group_by` columns, with the fraction of
+/// alignments of each group.
+pub(crate) async fn do_mapq_distribution(
+    ctx: &ExonSession,
+    table_name: String,
+    group_by: Vec<String>,
+) -> Result<DataFrame> {
+    let groups = group_columns(&group_by);
+    let query = format!(
+        r#"
+        WITH counts AS (
+            SELECT {groups}mapping_quality, COUNT(*) AS "count"
+            FROM {table_name}
+            WHERE flags & 4 = 0 AND flags & 2816 = 0
+            GROUP BY {groups}mapping_quality
+        )
+        SELECT
+            *,
+            CAST("count" AS DOUBLE) / SUM("count") OVER ({partition}) AS fraction
+        FROM counts
+        ORDER BY {groups}mapping_quality
+        "#,
+        partition = partition_by(&group_by, &[]),
+    );
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
@@ -1,13 +1,20 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use datafusion::config::ConfigOptions;
 use datafusion::prelude::SessionConfig;
 use exon::config::ExonConfigExtension;
 use exon::ExonSession;
 use log::debug;
+use pyo3::exceptions::PyValueError;
 use pyo3::{pyclass, pymethods, PyResult};
 use sequila_core::session_context::SequilaConfig;
 
+use crate::interval_index::IntervalIndex;
+use crate::phred::register_phred_udfs;
+use crate::trimming::register_trimming_udfs;
+use crate::umi::register_umi_udfs;
+
 #[pyclass(name = "BioSessionContext")]
 // #[derive(Clone)]
 pub struct PyBioSessionContext {
@@ -16,6 +23,7 @@ pub struct PyBioSessionContext {
     #[pyo3(get, set)]
     pub seed: String,
     pub catalog_dir: String,
+    pub interval_indexes: Mutex<HashMap<String, Arc<IntervalIndex>>>,
 }
 
 #[pymethods]
@@ -24,6 +32,9 @@ impl PyBioSessionContext {
     #[new]
     pub fn new(seed: String, catalog_dir: String) -> PyResult<Self> {
         let ctx = create_context().unwrap();
+        register_phred_udfs(&ctx);
+        register_trimming_udfs(&ctx);
+        register_umi_udfs(&ctx);
         let session_config: HashMap<String, String> = HashMap::new();
 
         Ok(PyBioSessionContext {
@@ -31,6 +42,7 @@ impl PyBioSessionContext {
             session_config,
             seed,
             catalog_dir,
+            interval_indexes: Mutex::new(HashMap::new()),
         })
     }
     #[pyo3(signature = (key, value, temporary=Some(false)))]
@@ -47,6 +59,21 @@ impl PyBioSessionContext {
         self.session_config.get(key).map(|v| v.as_str())
     }
 
+    #[pyo3(signature = ())]
+    pub fn list_interval_indexes(&self) -> Vec<String> {
+        self.interval_indexes
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    #[pyo3(signature = (name))]
+    pub fn drop_interval_index(&self, name: &str) -> bool {
+        self.interval_indexes.lock().unwrap().remove(name).is_some()
+    }
+
     #[pyo3(signature = ())]
     pub fn sync_options(&mut self) {
         for (key, value) in self.session_config.iter() {
@@ -56,6 +83,19 @@ impl PyBioSessionContext {
     }
 }
 
+impl PyBioSessionContext {
+    pub fn interval_index(&self, name: &str) -> PyResult<Arc<IntervalIndex>> {
+        self.interval_indexes
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("Interval index {} is not registered", name))
+            })
+    }
+}
+
 pub fn set_option_internal(ctx: &ExonSession, key: &str, value: &str) {
     let state = ctx.session.state_ref();
     state
@@ -0,0 +1,234 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::array::{ArrayBuilder, StringBuilder, StructBuilder, UInt32Builder};
+use arrow_schema::{DataType, Field, Fields};
+use datafusion::dataframe::DataFrame;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use exon::ExonSession;
+use log::{debug, info, warn};
+
+use crate::quality_control::string_array;
+
+const UNDETERMINED_SAMPLE: &str = "Undetermined";
+
+/// Barcodes of a sample sheet entry. Samples without an i5 barcode are
+/// single-indexed and match reads on i7 alone.
+#[derive(Debug, Clone)]
+pub(crate) struct SampleBarcodes {
+    sample: String,
+    i7: Vec<u8>,
+    i5: Option<Vec<u8>>,
+}
+
+impl SampleBarcodes {
+    pub(crate) fn new(sample: String, i7: String, i5: Option<String>) -> Self {
+        Self {
+            sample,
+            i7: i7.to_ascii_uppercase().into_bytes(),
+            i5: i5
+                .filter(|i5| !i5.is_empty())
+                .map(|i5| i5.to_ascii_uppercase().into_bytes()),
+        }
+    }
+}
+
+/// Mismatches of a read barcode against a sample barcode, counting missing
+/// read bases as mismatches. Extra read bases are ignored.
+fn mismatches(read: &[u8], barcode: &[u8]) -> usize {
+    barcode
+        .iter()
+        .enumerate()
+        .filter(|(i, base)| read.get(*i).map(|b| b.to_ascii_uppercase()) != Some(**base))
+        .count()
+}
+
+/// Splits the index of an Illumina read header comment such as
+/// `1:N:0:ACGTACGT+TTGGCCAA` (or a bare `ACGTACGT+TTGGCCAA`) into the i7 and
+/// i5 barcodes.
+fn parse_index(index: &str) -> (&[u8], Option<&[u8]>) {
+    let index = index.rsplit(':').next().unwrap_or(index).trim();
+    match index.split_once('+') {
+        Some((i7, i5)) => (i7.as_bytes(), Some(i5.as_bytes())),
+        None => (index.as_bytes(), None),
+    }
+}
+
+/// Whether a read could match both samples with `max_mismatches` per index.
+fn collide(a: &SampleBarcodes, b: &SampleBarcodes, max_mismatches: usize) -> bool {
+    let close = |x: &[u8], y: &[u8]| mismatches(x, y).min(mismatches(y, x)) <= 2 * max_mismatches;
+    close(&a.i7, &b.i7)
+        && match (&a.i5, &b.i5) {
+            (Some(x), Some(y)) => close(x, y),
+            _ => true,
+        }
+}
+
+/// The largest number of mismatches up to `max_mismatches` for which no
+/// read can match two samples. Fails for samples with the same barcodes.
+fn collision_free_mismatches(samples: &[SampleBarcodes], max_mismatches: usize) -> Result<usize> {
+    let mut allowed = max_mismatches;
+    for (i, a) in samples.iter().enumerate() {
+        for b in samples.iter().skip(i + 1) {
+            while collide(a, b, allowed) {
+                if allowed == 0 {
+                    return Err(DataFusionError::Plan(format!(
+                        "Samples {} and {} cannot be told apart by their barcodes",
+                        a.sample, b.sample
+                    )));
+                }
+                allowed -= 1;
+            }
+        }
+    }
+    if allowed < max_mismatches {
+        warn!(
+            "Barcodes of the sample sheet collide with {} mismatches, allowing {}",
+            max_mismatches, allowed
+        );
+    }
+    Ok(allowed)
+}
+
+fn demultiplex_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("sample", DataType::Utf8, false),
+        Field::new("barcode_mismatches", DataType::UInt32, true),
+    ])
+}
+
+/// `demultiplex_sample(index)` assigns a read index to the sample whose
+/// barcodes it matches with at most `max_mismatches` mismatches per index,
+/// and the fewest in total. Reads matching no sample, or several equally
+/// well, are `Undetermined`.
+#[derive(Debug)]
+struct DemultiplexUdf {
+    signature: Signature,
+    samples: Vec<SampleBarcodes>,
+    max_mismatches: usize,
+}
+
+impl DemultiplexUdf {
+    fn assign(&self, index: &str) -> Option<(usize, usize)> {
+        let (i7, i5) = parse_index(index);
+        let mut best: Option<(usize, usize)> = None;
+        let mut tied = false;
+        for (sample, barcodes) in self.samples.iter().enumerate() {
+            let i7_mismatches = mismatches(i7, &barcodes.i7);
+            let i5_mismatches = match (&barcodes.i5, i5) {
+                (Some(barcode), Some(read)) => mismatches(read, barcode),
+                (Some(_), None) => continue,
+                (None, _) => 0,
+            };
+            if i7_mismatches > self.max_mismatches || i5_mismatches > self.max_mismatches {
+                continue;
+            }
+            let total = i7_mismatches + i5_mismatches;
+            match best {
+                Some((_, best_total)) if total > best_total => {},
+                Some((_, best_total)) if total == best_total => tied = true,
+                _ => {
+                    best = Some((sample, total));
+                    tied = false;
+                },
+            }
+        }
+        if tied {
+            None
+        } else {
+            best
+        }
+    }
+}
+
+impl ScalarUDFImpl for DemultiplexUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "demultiplex_sample"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Struct(demultiplex_fields()))
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let indexes = string_array(&args[0].clone().into_array(1)?)?;
+        let struct_builders: Vec<Box<dyn ArrayBuilder>> = vec![
+            Box::new(StringBuilder::new()),
+            Box::new(UInt32Builder::new()),
+        ];
+        let mut builder = StructBuilder::new(demultiplex_fields(), struct_builders);
+        for index in indexes.iter() {
+            let assignment = index.and_then(|index| self.assign(index));
+            let (sample, mismatches) = match assignment {
+                Some((sample, mismatches)) => (
+                    self.samples[sample].sample.as_str(),
+                    Some(mismatches as u32),
+                ),
+                None => (UNDETERMINED_SAMPLE, None),
+            };
+            builder
+                .field_builder::<StringBuilder>(0)
+                .unwrap()
+                .append_value(sample);
+            builder
+                .field_builder::<UInt32Builder>(1)
+                .unwrap()
+                .append_option(mismatches);
+            builder.append(true);
+        }
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+/// Assigns the reads of a table to the samples of a sample sheet from the
+/// barcodes in `index_column`, adding `sample` and `barcode_mismatches`
+/// columns. The number of mismatches is lowered when barcodes of different
+/// samples are too close for `max_mismatches`.
+pub(crate) async fn do_demultiplex(
+    ctx: &ExonSession,
+    table_name: String,
+    samples: Vec<SampleBarcodes>,
+    index_column: String,
+    max_mismatches: usize,
+) -> Result<DataFrame> {
+    if samples.is_empty() {
+        return Err(DataFusionError::Plan(
+            "The sample sheet has no samples".to_string(),
+        ));
+    }
+    let max_mismatches = collision_free_mismatches(&samples, max_mismatches)?;
+    info!(
+        "Demultiplexing {} samples with up to {} mismatches per index",
+        samples.len(),
+        max_mismatches
+    );
+    ctx.session.register_udf(ScalarUDF::from(DemultiplexUdf {
+        signature: Signature::exact(vec![DataType::Utf8], Volatility::Immutable),
+        samples,
+        max_mismatches,
+    }));
+    let query = format!(
+        r#"
+        WITH assigned AS (
+            SELECT *, demultiplex_sample("{index_column}") AS demultiplexed
+            FROM {table_name}
+        )
+        SELECT
+            * EXCLUDE (demultiplexed),
+            demultiplexed['sample'] AS sample,
+            demultiplexed['barcode_mismatches'] AS barcode_mismatches
+        FROM assigned
+        "#,
+    );
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
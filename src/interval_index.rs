@@ -0,0 +1,476 @@
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use arrow::compute::{interleave, take};
+use arrow_array::{new_empty_array, Array, ArrayRef, Int64Array, RecordBatch, UInt32Array};
+use arrow_schema::{DataType, Field, FieldRef, Schema, SchemaRef};
+use async_trait::async_trait;
+use coitrees::{COITree, Interval, IntervalTree};
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::Result;
+use datafusion::datasource::TableType;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::physical_expr::{EquivalenceProperties, Partitioning};
+use datafusion::physical_plan::repartition::RepartitionExec;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionMode, ExecutionPlan, PlanProperties,
+};
+use datafusion::prelude::{Expr, SessionContext};
+use fnv::FnvHashMap;
+use futures_util::stream::BoxStream;
+use futures_util::{StreamExt, TryStreamExt};
+
+use crate::option::{FilterOp, RangeOp};
+use crate::udtf::{get_coverage, get_join_col_arrays, merge_intervals};
+use crate::utils::with_suffix;
+
+/// Interval trees built once over a table and kept in the session, so that many
+/// overlap, nearest, count overlaps or coverage calls can be run against the same
+/// (e.g. annotation) intervals without rebuilding them every time.
+pub struct IntervalIndex {
+    schema: SchemaRef,
+    columns: (String, String, String),
+    batches: Vec<RecordBatch>,
+    trees: FnvHashMap<String, COITree<u64, u32>>,
+    merged_trees: FnvHashMap<String, COITree<(), u32>>,
+    // (start, row) and (end, row) sorted per contig, used by nearest
+    by_start: FnvHashMap<String, Vec<(i32, u64)>>,
+    by_end: FnvHashMap<String, Vec<(i32, u64)>>,
+    num_rows: usize,
+}
+
+/// Packs the batch and row number of an indexed interval into the tree metadata.
+fn pack_row(batch: usize, row: usize) -> u64 {
+    ((batch as u64) << 32) | row as u64
+}
+
+fn unpack_row(packed: u64) -> (usize, usize) {
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+impl IntervalIndex {
+    pub fn new(schema: SchemaRef, batches: Vec<RecordBatch>, columns: Vec<String>) -> Self {
+        let columns = (columns[0].clone(), columns[1].clone(), columns[2].clone());
+        let mut nodes = FnvHashMap::<String, Vec<Interval<u64>>>::default();
+        let mut num_rows = 0;
+        for (b, batch) in batches.iter().enumerate() {
+            let (contig_arr, start_arr, end_arr) = get_join_col_arrays(batch, columns.clone());
+            for i in 0..batch.num_rows() {
+                let contig = contig_arr.value(i);
+                let node = Interval::new(start_arr.value(i), end_arr.value(i), pack_row(b, i));
+                if let Some(node_arr) = nodes.get_mut(contig) {
+                    node_arr.push(node);
+                } else {
+                    nodes.insert(contig.to_string(), vec![node]);
+                }
+            }
+            num_rows += batch.num_rows();
+        }
+
+        let mut trees = FnvHashMap::default();
+        let mut merged_trees = FnvHashMap::default();
+        let mut by_start = FnvHashMap::default();
+        let mut by_end = FnvHashMap::default();
+        for (seqname, seqname_nodes) in nodes {
+            let mut starts: Vec<(i32, u64)> = seqname_nodes
+                .iter()
+                .map(|n| (n.first, n.metadata))
+                .collect();
+            let mut ends: Vec<(i32, u64)> =
+                seqname_nodes.iter().map(|n| (n.last, n.metadata)).collect();
+            starts.sort_unstable();
+            ends.sort_unstable();
+            let plain_nodes = seqname_nodes
+                .iter()
+                .map(|n| Interval::new(n.first, n.last, ()))
+                .collect();
+            trees.insert(seqname.clone(), COITree::new(&seqname_nodes));
+            merged_trees.insert(seqname.clone(), COITree::new(&merge_intervals(plain_nodes)));
+            by_start.insert(seqname.clone(), starts);
+            by_end.insert(seqname, ends);
+        }
+
+        Self {
+            schema,
+            columns,
+            batches,
+            trees,
+            merged_trees,
+            by_start,
+            by_end,
+            num_rows,
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn columns(&self) -> Vec<String> {
+        vec![
+            self.columns.0.clone(),
+            self.columns.1.clone(),
+            self.columns.2.clone(),
+        ]
+    }
+
+    pub fn batches(&self) -> &[RecordBatch] {
+        &self.batches
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Returns the overlapping interval (distance 0) or the closest one on either side.
+    fn nearest(
+        &self,
+        contig: &str,
+        (first, last): (i32, i32),
+        (start, end): (i32, i32),
+    ) -> Option<(u64, i64)> {
+        let tree = self.trees.get(contig)?;
+        let mut hit = None;
+        tree.query(first, last, |node| {
+            if hit.is_none() {
+                hit = Some(*node.metadata);
+            }
+        });
+        if let Some(row) = hit {
+            return Some((row, 0));
+        }
+        // nothing overlaps, so every interval ends before `first` or starts after `last`
+        let ends = &self.by_end[contig];
+        let left = match ends.partition_point(|(e, _)| *e < first) {
+            0 => None,
+            i => Some(ends[i - 1]),
+        };
+        let starts = &self.by_start[contig];
+        let right = starts
+            .get(starts.partition_point(|(s, _)| *s <= last))
+            .copied();
+        match (left, right) {
+            (Some((l_end, l_row)), Some((r_start, r_row))) => {
+                if r_start - end < start - l_end {
+                    Some((r_row, (r_start - end) as i64))
+                } else {
+                    Some((l_row, (start - l_end) as i64))
+                }
+            },
+            (Some((l_end, l_row)), None) => Some((l_row, (start - l_end) as i64)),
+            (None, Some((r_start, r_row))) => Some((r_row, (r_start - end) as i64)),
+            (None, None) => None,
+        }
+    }
+
+    fn take_rows(&self, rows: &[(usize, usize)]) -> Result<Vec<ArrayRef>> {
+        (0..self.schema.fields().len())
+            .map(|c| {
+                if rows.is_empty() {
+                    return Ok(new_empty_array(self.schema.field(c).data_type()));
+                }
+                let arrays: Vec<&dyn Array> =
+                    self.batches.iter().map(|b| b.column(c).as_ref()).collect();
+                Ok(interleave(&arrays, rows)?)
+            })
+            .collect()
+    }
+
+    fn join_batch(
+        &self,
+        batch: &RecordBatch,
+        columns: &(String, String, String),
+        range_op: &RangeOp,
+        filter_op: &FilterOp,
+        schema: SchemaRef,
+    ) -> Result<RecordBatch> {
+        let (contig_arr, start_arr, end_arr) = get_join_col_arrays(batch, columns.clone());
+        let mut values: Vec<i64> = Vec::with_capacity(batch.num_rows());
+        let mut query_rows: Vec<u32> = Vec::new();
+        let mut index_rows: Vec<(usize, usize)> = Vec::new();
+        for i in 0..batch.num_rows() {
+            let contig = contig_arr.value(i);
+            let (start, end) = (start_arr.value(i), end_arr.value(i));
+            let (first, last) = if *filter_op == FilterOp::Strict {
+                (start + 1, end - 1)
+            } else {
+                (start, end)
+            };
+            match range_op {
+                RangeOp::Overlap => {
+                    if let Some(tree) = self.trees.get(contig) {
+                        tree.query(first, last, |node| {
+                            query_rows.push(i as u32);
+                            index_rows.push(unpack_row(*node.metadata));
+                        });
+                    }
+                },
+                RangeOp::Nearest => {
+                    if let Some((row, distance)) = self.nearest(contig, (first, last), (start, end))
+                    {
+                        query_rows.push(i as u32);
+                        index_rows.push(unpack_row(row));
+                        values.push(distance);
+                    }
+                },
+                RangeOp::Coverage => values.push(match self.merged_trees.get(contig) {
+                    Some(tree) => get_coverage(tree, first, last) as i64,
+                    None => 0,
+                }),
+                _ => values.push(match self.trees.get(contig) {
+                    Some(tree) => tree.query_count(first, last) as i64,
+                    None => 0,
+                }),
+            }
+        }
+
+        let columns = match range_op {
+            RangeOp::Overlap | RangeOp::Nearest => {
+                let indices = UInt32Array::from(query_rows);
+                let mut columns = batch
+                    .columns()
+                    .iter()
+                    .map(|c| take(c.as_ref(), &indices, None))
+                    .collect::<std::result::Result<Vec<ArrayRef>, _>>()?;
+                columns.extend(self.take_rows(&index_rows)?);
+                if *range_op == RangeOp::Nearest {
+                    columns.push(Arc::new(Int64Array::from(values)));
+                }
+                columns
+            },
+            _ => {
+                let mut columns = batch.columns().to_vec();
+                columns.push(Arc::new(Int64Array::from(values)));
+                columns
+            },
+        };
+        Ok(RecordBatch::try_new(schema, columns)?)
+    }
+}
+
+pub struct IndexedRangeProvider {
+    session: Arc<SessionContext>,
+    index: Arc<IntervalIndex>,
+    query_table: String,
+    query_columns: (String, String, String),
+    range_op: RangeOp,
+    filter_op: FilterOp,
+    schema: SchemaRef,
+}
+
+impl IndexedRangeProvider {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session: Arc<SessionContext>,
+        index: Arc<IntervalIndex>,
+        query_table: String,
+        query_schema: Schema,
+        query_columns: Vec<String>,
+        range_op: RangeOp,
+        filter_op: FilterOp,
+        suffixes: (String, String),
+    ) -> Self {
+        let fields = match range_op {
+            RangeOp::Overlap | RangeOp::Nearest => {
+                let mut fields = [
+                    with_suffix(&query_schema, &suffixes.0),
+                    with_suffix(&index.schema(), &suffixes.1),
+                ]
+                .concat();
+                if range_op == RangeOp::Nearest {
+                    fields.push(FieldRef::new(Field::new(
+                        "distance",
+                        DataType::Int64,
+                        false,
+                    )));
+                }
+                fields
+            },
+            _ => {
+                let mut fields = query_schema.fields().to_vec();
+                let name = if range_op == RangeOp::Coverage {
+                    "coverage"
+                } else {
+                    "count"
+                };
+                fields.push(FieldRef::new(Field::new(name, DataType::Int64, false)));
+                fields
+            },
+        };
+        Self {
+            session,
+            index,
+            query_table,
+            query_columns: (
+                query_columns[0].clone(),
+                query_columns[1].clone(),
+                query_columns[2].clone(),
+            ),
+            range_op,
+            filter_op,
+            schema: Arc::new(Schema::new(fields)),
+        }
+    }
+}
+
+impl Debug for IndexedRangeProvider {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TableProvider for IndexedRangeProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        _projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let target_partitions = self
+            .session
+            .state()
+            .config()
+            .options()
+            .execution
+            .target_partitions;
+        Ok(Arc::new(IndexedRangeExec {
+            schema: self.schema.clone(),
+            session: Arc::clone(&self.session),
+            index: Arc::clone(&self.index),
+            query_table: self.query_table.clone(),
+            query_columns: self.query_columns.clone(),
+            range_op: self.range_op.clone(),
+            filter_op: self.filter_op.clone(),
+            cache: PlanProperties::new(
+                EquivalenceProperties::new(self.schema.clone()),
+                Partitioning::UnknownPartitioning(target_partitions),
+                ExecutionMode::Bounded,
+            ),
+        }))
+    }
+}
+
+struct IndexedRangeExec {
+    schema: SchemaRef,
+    session: Arc<SessionContext>,
+    index: Arc<IntervalIndex>,
+    query_table: String,
+    query_columns: (String, String, String),
+    range_op: RangeOp,
+    filter_op: FilterOp,
+    cache: PlanProperties,
+}
+
+impl Debug for IndexedRangeExec {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl DisplayAs for IndexedRangeExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "IndexedRangeExec: op={}", self.range_op)
+    }
+}
+
+impl ExecutionPlan for IndexedRangeExec {
+    fn name(&self) -> &str {
+        "IndexedRangeExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.cache
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let fut = get_stream(
+            Arc::clone(&self.session),
+            Arc::clone(&self.index),
+            self.query_table.clone(),
+            self.query_columns.clone(),
+            self.range_op.clone(),
+            self.filter_op.clone(),
+            self.schema.clone(),
+            self.cache.partitioning.partition_count(),
+            partition,
+            context,
+        );
+        let stream = futures::stream::once(fut).try_flatten();
+        let schema = self.schema.clone();
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, stream)))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn get_stream(
+    session: Arc<SessionContext>,
+    index: Arc<IntervalIndex>,
+    query_table: String,
+    query_columns: (String, String, String),
+    range_op: RangeOp,
+    filter_op: FilterOp,
+    new_schema: SchemaRef,
+    target_partitions: usize,
+    partition: usize,
+    context: Arc<TaskContext>,
+) -> Result<SendableRecordBatchStream> {
+    let plan = session
+        .table(query_table)
+        .await?
+        .create_physical_plan()
+        .await?;
+    let repartition_stream =
+        RepartitionExec::try_new(plan, Partitioning::RoundRobinBatch(target_partitions))?;
+    let partition_stream = repartition_stream.execute(partition, context)?;
+    let new_schema_out = new_schema.clone();
+
+    let iter = partition_stream.map(move |rb| match rb {
+        Ok(rb) => index.join_batch(
+            &rb,
+            &query_columns,
+            &range_op,
+            &filter_op,
+            new_schema.clone(),
+        ),
+        Err(e) => Err(e),
+    });
+
+    let adapted_stream =
+        RecordBatchStreamAdapter::new(new_schema_out, Box::pin(iter) as BoxStream<_>);
+    Ok(Box::pin(adapted_stream))
+}
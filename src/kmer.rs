@@ -0,0 +1,282 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayBuilder, ListBuilder, StringBuilder, StructBuilder, UInt32Builder, UInt64Builder,
+};
+use arrow_array::{Array, UInt64Array};
+use arrow_schema::{DataType, Field, Fields};
+use datafusion::dataframe::DataFrame;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use exon::ExonSession;
+use log::debug;
+
+use crate::quality_control::string_array;
+
+/// The largest k for which k-mers fit 2-bit packed in a `u64`.
+pub(crate) const MAX_K: usize = 31;
+
+fn encode_base(base: u8) -> Option<u64> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+/// Packed k-mers of a sequence with their 0-based start positions. K-mers
+/// containing bases other than A, C, G and T are skipped.
+fn packed_kmers(
+    sequence: &[u8],
+    k: usize,
+    canonical: bool,
+) -> impl Iterator<Item = (u64, u32)> + '_ {
+    let mask = (1u64 << (2 * k)) - 1;
+    let shift = 2 * (k as u64 - 1);
+    let mut forward = 0u64;
+    let mut reverse = 0u64;
+    let mut valid = 0usize;
+    sequence.iter().enumerate().filter_map(move |(i, base)| {
+        let Some(code) = encode_base(*base) else {
+            valid = 0;
+            return None;
+        };
+        forward = ((forward << 2) | code) & mask;
+        reverse = (reverse >> 2) | ((3 - code) << shift);
+        valid += 1;
+        if valid < k {
+            return None;
+        }
+        let kmer = if canonical {
+            forward.min(reverse)
+        } else {
+            forward
+        };
+        Some((kmer, (i + 1 - k) as u32))
+    })
+}
+
+fn unpack_kmer(kmer: u64, k: usize) -> String {
+    (0..k)
+        .rev()
+        .map(|i| match (kmer >> (2 * i)) & 3 {
+            0 => 'A',
+            1 => 'C',
+            2 => 'G',
+            _ => 'T',
+        })
+        .collect()
+}
+
+fn kmer_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("kmer", DataType::UInt64, false),
+        Field::new("position", DataType::UInt32, false),
+    ])
+}
+
+/// `kmers(sequence)` returns the packed k-mers of each sequence with their
+/// positions, so that they can be unnested and counted with SQL aggregates.
+#[derive(Debug)]
+struct KmersUdf {
+    signature: Signature,
+    k: usize,
+    canonical: bool,
+}
+
+impl ScalarUDFImpl for KmersUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "kmers"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::Struct(kmer_fields()),
+            true,
+        ))))
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let sequences = string_array(&args[0].clone().into_array(1)?)?;
+        let struct_builders: Vec<Box<dyn ArrayBuilder>> = vec![
+            Box::new(UInt64Builder::new()),
+            Box::new(UInt32Builder::new()),
+        ];
+        let mut list_builder = ListBuilder::new(StructBuilder::new(kmer_fields(), struct_builders));
+        for sequence in sequences.iter() {
+            let Some(sequence) = sequence else {
+                list_builder.append_null();
+                continue;
+            };
+            let struct_builder_ref = list_builder.values();
+            for (kmer, position) in packed_kmers(sequence.as_bytes(), self.k, self.canonical) {
+                struct_builder_ref
+                    .field_builder::<UInt64Builder>(0)
+                    .unwrap()
+                    .append_value(kmer);
+                struct_builder_ref
+                    .field_builder::<UInt32Builder>(1)
+                    .unwrap()
+                    .append_value(position);
+                struct_builder_ref.append(true);
+            }
+            list_builder.append(true);
+        }
+        Ok(ColumnarValue::Array(Arc::new(list_builder.finish())))
+    }
+}
+
+/// `kmer_sequence(kmer)` unpacks a k-mer produced by `kmers`.
+#[derive(Debug)]
+struct KmerSequenceUdf {
+    signature: Signature,
+    k: usize,
+}
+
+impl ScalarUDFImpl for KmerSequenceUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "kmer_sequence"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let array = args[0].clone().into_array(1)?;
+        let kmers = array
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| DataFusionError::Internal("Expected UInt64Array".to_string()))?;
+        let mut builder = StringBuilder::new();
+        for kmer in kmers.iter() {
+            match kmer {
+                Some(kmer) => builder.append_value(unpack_kmer(kmer, self.k)),
+                None => builder.append_null(),
+            }
+        }
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+pub(crate) fn register_kmer_udfs(ctx: &ExonSession, k: usize, canonical: bool) {
+    ctx.session.register_udf(ScalarUDF::from(KmersUdf {
+        signature: Signature::exact(vec![DataType::Utf8], Volatility::Immutable),
+        k,
+        canonical,
+    }));
+    ctx.session.register_udf(ScalarUDF::from(KmerSequenceUdf {
+        signature: Signature::exact(vec![DataType::UInt64], Volatility::Immutable),
+        k,
+    }));
+}
+
+/// Counts k-mers of `column`, keeping those seen at least `min_count` times.
+/// With `positions`, counts are reported per (0-based) k-mer start position
+/// together with the enrichment over the count expected from the overall
+/// k-mer and position frequencies. Counting is done with a SQL aggregate, so
+/// partial counts are merged across partitions and spilled under a memory
+/// limit like any other DataFusion aggregation.
+pub(crate) async fn do_kmer_count(
+    ctx: &ExonSession,
+    table_name: String,
+    column: String,
+    k: usize,
+    canonical: bool,
+    min_count: u64,
+    positions: bool,
+) -> Result<DataFrame> {
+    if k == 0 || k > MAX_K {
+        return Err(DataFusionError::Plan(format!(
+            "k must be between 1 and {}, got {}",
+            MAX_K, k
+        )));
+    }
+    register_kmer_udfs(ctx, k, canonical);
+    let query = if !positions {
+        format!(
+            r#"
+            WITH kmers AS (
+                SELECT unnest(kmers("{column}")) AS my_struct
+                FROM {table_name}
+            ),
+            counts AS (
+                SELECT my_struct['kmer'] AS kmer, count(*) AS count
+                FROM kmers
+                GROUP BY my_struct['kmer']
+                HAVING count(*) >= {min_count}
+            )
+            SELECT
+                kmer_sequence(kmer) AS kmer,
+                CAST(count AS BIGINT UNSIGNED) AS count
+            FROM counts
+            ORDER BY count DESC, kmer;
+            "#,
+        )
+    } else {
+        format!(
+            r#"
+            WITH kmers AS (
+                SELECT unnest(kmers("{column}")) AS my_struct
+                FROM {table_name}
+            ),
+            by_position AS (
+                SELECT
+                    my_struct['kmer'] AS kmer,
+                    my_struct['position'] AS position,
+                    count(*) AS observed
+                FROM kmers
+                GROUP BY my_struct['kmer'], my_struct['position']
+            ),
+            kmer_totals AS (
+                SELECT kmer, SUM(observed) AS total
+                FROM by_position
+                GROUP BY kmer
+                HAVING SUM(observed) >= {min_count}
+            ),
+            position_totals AS (
+                SELECT position, SUM(observed) AS total
+                FROM by_position
+                GROUP BY position
+            ),
+            grand_total AS (
+                SELECT CAST(SUM(observed) AS DOUBLE) AS total
+                FROM by_position
+            )
+            SELECT
+                kmer_sequence(b.kmer) AS kmer,
+                CAST(b.position AS BIGINT UNSIGNED) AS position,
+                CAST(b.observed AS BIGINT UNSIGNED) AS count,
+                CAST(kt.total AS BIGINT UNSIGNED) AS total_count,
+                CAST(b.observed AS DOUBLE) * g.total / (CAST(kt.total AS DOUBLE) * pt.total) AS enrichment
+            FROM by_position b
+            JOIN kmer_totals kt ON b.kmer = kt.kmer
+            JOIN position_totals pt ON b.position = pt.position
+            CROSS JOIN grand_total g
+            ORDER BY total_count DESC, kmer, position;
+            "#,
+        )
+    };
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
@@ -1,12 +1,24 @@
+mod bam_qc;
 mod context;
+mod demultiplex;
+mod interval_index;
+mod kmer;
+mod liftover;
 mod operation;
 mod option;
+mod paired_fastq;
+mod phred;
 mod quality_control;
 mod query;
 mod scan;
 mod streaming;
+mod sv_match;
+mod sweep_line;
+mod trimming;
 mod udtf;
+mod umi;
 mod utils;
+mod vcf_intervals;
 
 use std::string::ToString;
 use std::sync::{Arc, Mutex};
@@ -24,16 +36,33 @@ use polars_python::lazyframe::PyLazyFrame;
 use pyo3::prelude::*;
 use tokio::runtime::Runtime;
 
+use crate::bam_qc::{
+    do_flagstat, do_idxstats, do_insert_size_histogram, do_mapq_distribution, read_bam_references,
+};
 use crate::context::PyBioSessionContext;
-use crate::operation::do_range_operation;
+use crate::demultiplex::{do_demultiplex, SampleBarcodes};
+use crate::kmer::do_kmer_count;
+use crate::operation::{
+    build_interval_index, do_indexed_range_operation, do_liftover, do_range_operation, do_sv_match,
+};
 use crate::option::{
     pyobject_storage_options_to_object_storage_options, BamReadOptions, BedReadOptions, BioTable,
-    FastqReadOptions, FilterOp, GffReadOptions, InputFormat, PyObjectStorageOptions, RangeOp,
-    RangeOptions, ReadOptions, VcfReadOptions,
+    FastqReadOptions, FilterOp, GffReadOptions, InputFormat, IntervalIndexInfo,
+    PyObjectStorageOptions, RangeOp, RangeOptions, ReadOptions, SvMatchOptions, VcfReadOptions,
 };
-use crate::quality_control::{do_base_sequence_content, register_base_sequence_content};
-use crate::scan::{maybe_register_table, register_frame, register_table};
-use crate::streaming::RangeOperationScan;
+use crate::phred::{detect_phred_encoding, do_reencode_quality};
+use crate::quality_control::{
+    do_adapter_content, do_base_sequence_content, do_base_sequence_quality, do_fastq_qc_report,
+    do_gc_content_fit, do_overrepresented_sequences, do_per_sequence_gc_content,
+    do_per_sequence_quality, do_sequence_duplication_levels, do_sequence_length_distribution,
+    register_adapter_content, register_base_sequence_content, register_base_sequence_quality,
+    register_overrepresented_sequences, register_per_sequence_gc_content,
+    register_per_sequence_quality, register_sequence_duplication_levels,
+    register_sequence_length_distribution,
+};
+use crate::scan::{maybe_register_table, register_frame, register_frame_stream, register_table};
+use crate::streaming::{dataframe_to_lazy_frame, RangeOperationScan};
+use crate::umi::{do_extract_umi, do_umi_dedup};
 use crate::utils::convert_arrow_rb_schema_to_polars_df_schema;
 
 const LEFT_TABLE: &str = "s1";
@@ -52,8 +81,8 @@ fn range_operation_frame(
     #[allow(clippy::useless_conversion)]
     let rt = Runtime::new().unwrap();
     let ctx = &py_ctx.ctx;
-    register_frame(py_ctx, df1, LEFT_TABLE.to_string());
-    register_frame(py_ctx, df2, RIGHT_TABLE.to_string());
+    register_frame(py_ctx, df1, LEFT_TABLE.to_string())?;
+    register_frame(py_ctx, df2, RIGHT_TABLE.to_string())?;
     match limit {
         Some(l) => Ok(PyDataFrame::new(
             do_range_operation(
@@ -62,7 +91,7 @@ fn range_operation_frame(
                 range_options,
                 LEFT_TABLE.to_string(),
                 RIGHT_TABLE.to_string(),
-            )
+            )?
             .limit(0, Some(l))?,
         )),
         _ => {
@@ -72,7 +101,7 @@ fn range_operation_frame(
                 range_options,
                 LEFT_TABLE.to_string(),
                 RIGHT_TABLE.to_string(),
-            );
+            )?;
             let py_df = PyDataFrame::new(df);
             Ok(py_df)
         },
@@ -99,17 +128,17 @@ fn range_operation_scan(
         read_options1,
         ctx,
         &rt,
-    );
+    )?;
     let right_table = maybe_register_table(
         df_path_or_table2,
         &RIGHT_TABLE.to_string(),
         read_options2,
         ctx,
         &rt,
-    );
+    )?;
     match limit {
         Some(l) => Ok(PyDataFrame::new(
-            do_range_operation(ctx, &rt, range_options, left_table, right_table)
+            do_range_operation(ctx, &rt, range_options, left_table, right_table)?
                 .limit(0, Some(l))?,
         )),
         _ => Ok(PyDataFrame::new(do_range_operation(
@@ -118,7 +147,7 @@ fn range_operation_scan(
             range_options,
             left_table,
             right_table,
-        ))),
+        )?)),
     }
 }
 
@@ -145,16 +174,16 @@ fn stream_range_operation_scan(
             read_options1,
             ctx,
             &rt,
-        );
+        )?;
         let right_table = maybe_register_table(
             df_path_or_table2,
             &RIGHT_TABLE.to_string(),
             read_options2,
             ctx,
             &rt,
-        );
+        )?;
 
-        let df = do_range_operation(ctx, &rt, range_options, left_table, right_table);
+        let df = do_range_operation(ctx, &rt, range_options, left_table, right_table)?;
         let schema = df.schema().as_arrow();
         let polars_schema = convert_arrow_rb_schema_to_polars_df_schema(schema).unwrap();
         debug!("Schema: {:?}", polars_schema);
@@ -183,6 +212,223 @@ fn stream_range_operation_scan(
     })
 }
 
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, name, columns, read_options=None, persist=false))]
+fn py_build_interval_index_scan(
+    py: Python<'_>,
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    name: String,
+    columns: Vec<String>,
+    read_options: Option<ReadOptions>,
+    persist: bool,
+) -> PyResult<IntervalIndexInfo> {
+    #[allow(clippy::useless_conversion)]
+    py.allow_threads(|| {
+        let rt = Runtime::new()?;
+        let ctx = &py_ctx.ctx;
+        let table = maybe_register_table(
+            df_path_or_table,
+            &LEFT_TABLE.to_string(),
+            read_options,
+            ctx,
+            &rt,
+        )?;
+        Ok(build_interval_index(
+            py_ctx, &rt, table, name, columns, persist,
+        )?)
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, name, columns, persist=false))]
+fn py_build_interval_index_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    name: String,
+    columns: Vec<String>,
+    persist: bool,
+) -> PyResult<IntervalIndexInfo> {
+    #[allow(clippy::useless_conversion)]
+    let rt = Runtime::new()?;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    Ok(build_interval_index(
+        py_ctx,
+        &rt,
+        LEFT_TABLE.to_string(),
+        name,
+        columns,
+        persist,
+    )?)
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, index_name, df_path_or_table, range_options, read_options=None, limit=None))]
+fn indexed_range_operation_scan(
+    py_ctx: &PyBioSessionContext,
+    index_name: String,
+    df_path_or_table: String,
+    range_options: RangeOptions,
+    read_options: Option<ReadOptions>,
+    limit: Option<usize>,
+) -> PyResult<PyDataFrame> {
+    #[allow(clippy::useless_conversion)]
+    let rt = Runtime::new()?;
+    let ctx = &py_ctx.ctx;
+    let index = py_ctx.interval_index(&index_name)?;
+    let query_table = maybe_register_table(
+        df_path_or_table,
+        &RIGHT_TABLE.to_string(),
+        read_options,
+        ctx,
+        &rt,
+    )?;
+    let df = do_indexed_range_operation(ctx, &rt, index, range_options, query_table)?;
+    match limit {
+        Some(l) => Ok(PyDataFrame::new(df.limit(0, Some(l))?)),
+        _ => Ok(PyDataFrame::new(df)),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, index_name, df, range_options, limit=None))]
+fn indexed_range_operation_frame(
+    py_ctx: &PyBioSessionContext,
+    index_name: String,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    range_options: RangeOptions,
+    limit: Option<usize>,
+) -> PyResult<PyDataFrame> {
+    #[allow(clippy::useless_conversion)]
+    let rt = Runtime::new()?;
+    let ctx = &py_ctx.ctx;
+    let index = py_ctx.interval_index(&index_name)?;
+    register_frame(py_ctx, df, RIGHT_TABLE.to_string())?;
+    let df = do_indexed_range_operation(ctx, &rt, index, range_options, RIGHT_TABLE.to_string())?;
+    match limit {
+        Some(l) => Ok(PyDataFrame::new(df.limit(0, Some(l))?)),
+        _ => Ok(PyDataFrame::new(df)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, chain_path, columns, filter_op, min_match, read_options=None))]
+fn py_liftover_scan(
+    py: Python<'_>,
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    chain_path: String,
+    columns: Vec<String>,
+    filter_op: FilterOp,
+    min_match: f64,
+    read_options: Option<ReadOptions>,
+) -> PyResult<(PyDataFrame, PyDataFrame)> {
+    py.allow_threads(|| {
+        let rt = Runtime::new()?;
+        let ctx = &py_ctx.ctx;
+        let input_table = maybe_register_table(
+            df_path_or_table,
+            &LEFT_TABLE.to_string(),
+            read_options,
+            ctx,
+            &rt,
+        )?;
+        let (mapped, unmapped) = do_liftover(
+            ctx,
+            &rt,
+            input_table,
+            chain_path,
+            columns,
+            filter_op,
+            min_match,
+        )?;
+        Ok((PyDataFrame::new(mapped), PyDataFrame::new(unmapped)))
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, chain_path, columns, filter_op, min_match))]
+fn py_liftover_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    chain_path: String,
+    columns: Vec<String>,
+    filter_op: FilterOp,
+    min_match: f64,
+) -> PyResult<(PyDataFrame, PyDataFrame)> {
+    #[allow(clippy::useless_conversion)]
+    let rt = Runtime::new()?;
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    let (mapped, unmapped) = do_liftover(
+        ctx,
+        &rt,
+        LEFT_TABLE.to_string(),
+        chain_path,
+        columns,
+        filter_op,
+        min_match,
+    )?;
+    Ok((PyDataFrame::new(mapped), PyDataFrame::new(unmapped)))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table1, df_path_or_table2, sv_match_options, read_options1=None, read_options2=None))]
+fn py_sv_match_scan(
+    py: Python<'_>,
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table1: String,
+    df_path_or_table2: String,
+    sv_match_options: SvMatchOptions,
+    read_options1: Option<ReadOptions>,
+    read_options2: Option<ReadOptions>,
+) -> PyResult<PyDataFrame> {
+    py.allow_threads(|| {
+        let rt = Runtime::new()?;
+        let ctx = &py_ctx.ctx;
+        let left_table = maybe_register_table(
+            df_path_or_table1,
+            &LEFT_TABLE.to_string(),
+            read_options1,
+            ctx,
+            &rt,
+        )?;
+        let right_table = maybe_register_table(
+            df_path_or_table2,
+            &RIGHT_TABLE.to_string(),
+            read_options2,
+            ctx,
+            &rt,
+        )?;
+        let df = do_sv_match(ctx, &rt, sv_match_options, left_table, right_table)?;
+        Ok(PyDataFrame::new(df))
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df1, df2, sv_match_options))]
+fn py_sv_match_frame(
+    py_ctx: &PyBioSessionContext,
+    df1: PyArrowType<ArrowArrayStreamReader>,
+    df2: PyArrowType<ArrowArrayStreamReader>,
+    sv_match_options: SvMatchOptions,
+) -> PyResult<PyDataFrame> {
+    #[allow(clippy::useless_conversion)]
+    let rt = Runtime::new()?;
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df1, LEFT_TABLE.to_string())?;
+    register_frame(py_ctx, df2, RIGHT_TABLE.to_string())?;
+    let df = do_sv_match(
+        ctx,
+        &rt,
+        sv_match_options,
+        LEFT_TABLE.to_string(),
+        RIGHT_TABLE.to_string(),
+    )?;
+    Ok(PyDataFrame::new(df))
+}
+
 #[pyfunction]
 #[pyo3(signature = (py_ctx, path, name, input_format, read_options=None))]
 fn py_register_table(
@@ -216,7 +462,7 @@ fn py_register_table(
             &table_name,
             input_format.clone(),
             read_options,
-        ));
+        ))?;
         match rt.block_on(ctx.session.table(&table_name)) {
             Ok(table) => {
                 let schema = table.schema().as_arrow();
@@ -412,46 +658,834 @@ fn py_from_polars(
     py_ctx: &PyBioSessionContext,
     name: String,
     df: PyArrowType<ArrowArrayStreamReader>,
-) {
-    py.allow_threads(|| {
-        register_frame(py_ctx, df, name);
-    })
+) -> PyResult<()> {
+    py.allow_threads(|| Ok(register_frame(py_ctx, df, name)?))
 }
 
 #[pyfunction]
-#[pyo3(signature = (py_ctx, df))]
+#[pyo3(signature = (py_ctx, df, sequence_column="sequence".to_string(), group_by=vec![], percentages=false))]
 fn py_base_sequence_content_frame(
     py_ctx: &PyBioSessionContext,
     df: PyArrowType<ArrowArrayStreamReader>,
+    sequence_column: String,
+    group_by: Vec<String>,
+    percentages: bool,
 ) -> PyResult<PyDataFrame> {
     let rt = Runtime::new().unwrap();
     let ctx = &py_ctx.ctx;
-    register_frame(py_ctx, df, LEFT_TABLE.to_string());
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
     register_base_sequence_content(ctx);
 
     Ok(PyDataFrame::new(rt.block_on(do_base_sequence_content(
         ctx,
         LEFT_TABLE.to_string(),
+        sequence_column,
+        group_by,
+        percentages,
     ))))
 }
 
 #[pyfunction]
-#[pyo3(signature = (py_ctx, df_path_or_table))]
+#[pyo3(signature = (py_ctx, df_path_or_table, sequence_column="sequence".to_string(), group_by=vec![], percentages=false))]
 fn py_base_sequence_content_scan(
     py_ctx: &PyBioSessionContext,
     df_path_or_table: String,
+    sequence_column: String,
+    group_by: Vec<String>,
+    percentages: bool,
 ) -> PyResult<PyDataFrame> {
     let rt = Runtime::new().unwrap();
     let ctx = &py_ctx.ctx;
-    maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt);
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
     register_base_sequence_content(ctx);
 
     Ok(PyDataFrame::new(rt.block_on(do_base_sequence_content(
+        ctx,
+        table,
+        sequence_column,
+        group_by,
+        percentages,
+    ))))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, sequence_column="sequence".to_string(), group_by=vec![], percentages=false))]
+fn stream_base_sequence_content_frame(
+    py: Python<'_>,
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    sequence_column: String,
+    group_by: Vec<String>,
+    percentages: bool,
+) -> PyResult<PyLazyFrame> {
+    #[allow(clippy::useless_conversion)]
+    py.allow_threads(|| {
+        let rt = Runtime::new().unwrap();
+        let ctx = &py_ctx.ctx;
+        register_frame_stream(py_ctx, df, LEFT_TABLE.to_string());
+        register_base_sequence_content(ctx);
+
+        let df = rt.block_on(do_base_sequence_content(
+            ctx,
+            LEFT_TABLE.to_string(),
+            sequence_column,
+            group_by,
+            percentages,
+        ));
+        let lf = dataframe_to_lazy_frame(&rt, df).map_err(PyPolarsErr::from)?;
+        Ok(lf.into())
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, sequence_column="sequence".to_string(), group_by=vec![], percentages=false))]
+fn stream_base_sequence_content_scan(
+    py: Python<'_>,
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    sequence_column: String,
+    group_by: Vec<String>,
+    percentages: bool,
+) -> PyResult<PyLazyFrame> {
+    #[allow(clippy::useless_conversion)]
+    py.allow_threads(|| {
+        let rt = Runtime::new().unwrap();
+        let ctx = &py_ctx.ctx;
+        let table =
+            maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+        register_base_sequence_content(ctx);
+
+        let df = rt.block_on(do_base_sequence_content(
+            ctx,
+            table,
+            sequence_column,
+            group_by,
+            percentages,
+        ));
+        let lf = dataframe_to_lazy_frame(&rt, df).map_err(PyPolarsErr::from)?;
+        Ok(lf.into())
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, phred_offset=33))]
+fn py_base_sequence_quality_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    phred_offset: u8,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    register_base_sequence_quality(ctx, phred_offset);
+
+    Ok(PyDataFrame::new(rt.block_on(do_base_sequence_quality(
+        ctx,
+        LEFT_TABLE.to_string(),
+    ))))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, phred_offset=33))]
+fn py_base_sequence_quality_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    phred_offset: u8,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    register_base_sequence_quality(ctx, phred_offset);
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_base_sequence_quality(ctx, table)),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, phred_offset=33))]
+fn py_per_sequence_quality_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    phred_offset: u8,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    register_per_sequence_quality(ctx, phred_offset);
+
+    Ok(PyDataFrame::new(rt.block_on(do_per_sequence_quality(
+        ctx,
+        LEFT_TABLE.to_string(),
+    ))))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, phred_offset=33))]
+fn py_per_sequence_quality_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    phred_offset: u8,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    register_per_sequence_quality(ctx, phred_offset);
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_per_sequence_quality(ctx, table)),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df))]
+fn py_per_sequence_gc_content_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    register_per_sequence_gc_content(ctx);
+
+    Ok(PyDataFrame::new(rt.block_on(do_per_sequence_gc_content(
         ctx,
         LEFT_TABLE.to_string(),
     ))))
 }
 
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df))]
+fn py_gc_content_fit_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_gc_content_fit(
+        ctx,
+        LEFT_TABLE.to_string(),
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table))]
+fn py_per_sequence_gc_content_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    register_per_sequence_gc_content(ctx);
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_per_sequence_gc_content(ctx, table)),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, bin_size=1))]
+fn py_sequence_length_distribution_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    bin_size: u64,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    register_sequence_length_distribution(ctx, bin_size);
+
+    Ok(PyDataFrame::new(rt.block_on(
+        do_sequence_length_distribution(ctx, LEFT_TABLE.to_string(), bin_size),
+    )))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, bin_size=1))]
+fn py_sequence_length_distribution_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    bin_size: u64,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    register_sequence_length_distribution(ctx, bin_size);
+
+    Ok(PyDataFrame::new(rt.block_on(
+        do_sequence_length_distribution(ctx, table, bin_size),
+    )))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, max_unique=100000))]
+fn py_sequence_duplication_levels_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    max_unique: usize,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    register_sequence_duplication_levels(ctx, max_unique);
+
+    Ok(PyDataFrame::new(rt.block_on(
+        do_sequence_duplication_levels(ctx, LEFT_TABLE.to_string()),
+    )))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, max_unique=100000))]
+fn py_sequence_duplication_levels_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    max_unique: usize,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    register_sequence_duplication_levels(ctx, max_unique);
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_sequence_duplication_levels(ctx, table)),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, max_unique=100000, min_percentage=0.1))]
+fn py_overrepresented_sequences_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    max_unique: usize,
+    min_percentage: f64,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    register_overrepresented_sequences(ctx, max_unique, min_percentage);
+
+    Ok(PyDataFrame::new(rt.block_on(do_overrepresented_sequences(
+        ctx,
+        LEFT_TABLE.to_string(),
+    ))))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, max_unique=100000, min_percentage=0.1))]
+fn py_overrepresented_sequences_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    max_unique: usize,
+    min_percentage: f64,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    register_overrepresented_sequences(ctx, max_unique, min_percentage);
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_overrepresented_sequences(ctx, table)),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, adapters=None))]
+fn py_adapter_content_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    adapters: Option<Vec<(String, String)>>,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    register_adapter_content(ctx, adapters);
+
+    Ok(PyDataFrame::new(rt.block_on(do_adapter_content(
+        ctx,
+        LEFT_TABLE.to_string(),
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, adapters=None))]
+fn py_adapter_content_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    adapters: Option<Vec<(String, String)>>,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    register_adapter_content(ctx, adapters);
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_adapter_content(ctx, table))?,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, column, k, canonical, min_count, positions))]
+fn py_kmer_count_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    column: String,
+    k: usize,
+    canonical: bool,
+    min_count: u64,
+    positions: bool,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_kmer_count(
+        ctx,
+        LEFT_TABLE.to_string(),
+        column,
+        k,
+        canonical,
+        min_count,
+        positions,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, column, k, canonical, min_count, positions))]
+fn py_kmer_count_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    column: String,
+    k: usize,
+    canonical: bool,
+    min_count: u64,
+    positions: bool,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_kmer_count(
+        ctx, table, column, k, canonical, min_count, positions,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, column, sample_size))]
+fn py_detect_phred_encoding_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    column: String,
+    sample_size: usize,
+) -> PyResult<(String, u8, char, char)> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    let (encoding, min, max) = rt.block_on(detect_phred_encoding(
+        ctx,
+        LEFT_TABLE.to_string(),
+        column,
+        sample_size,
+    ))?;
+    Ok((encoding.name().to_string(), encoding.offset(), min, max))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, column, sample_size))]
+fn py_detect_phred_encoding_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    column: String,
+    sample_size: usize,
+) -> PyResult<(String, u8, char, char)> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    let (encoding, min, max) =
+        rt.block_on(detect_phred_encoding(ctx, table, column, sample_size))?;
+    Ok((encoding.name().to_string(), encoding.offset(), min, max))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, column, source, target))]
+fn py_reencode_quality_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    column: String,
+    source: String,
+    target: String,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_reencode_quality(
+        ctx,
+        LEFT_TABLE.to_string(),
+        column,
+        source,
+        target,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, column, source, target))]
+fn py_reencode_quality_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    column: String,
+    source: String,
+    target: String,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_reencode_quality(
+        ctx, table, column, source, target,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, phred_offset, max_unique, adapters, min_overrepresented_percentage))]
+fn py_fastq_qc_report_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    phred_offset: u8,
+    max_unique: usize,
+    adapters: Option<Vec<(String, String)>>,
+    min_overrepresented_percentage: f64,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_fastq_qc_report(
+        ctx,
+        LEFT_TABLE.to_string(),
+        phred_offset,
+        max_unique,
+        adapters,
+        min_overrepresented_percentage,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, phred_offset, max_unique, adapters, min_overrepresented_percentage))]
+fn py_fastq_qc_report_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    phred_offset: u8,
+    max_unique: usize,
+    adapters: Option<Vec<(String, String)>>,
+    min_overrepresented_percentage: f64,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_fastq_qc_report(
+        ctx,
+        table,
+        phred_offset,
+        max_unique,
+        adapters,
+        min_overrepresented_percentage,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, sequence_column, quality_column, pattern))]
+fn py_extract_umi_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    sequence_column: String,
+    quality_column: String,
+    pattern: String,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_extract_umi(
+        ctx,
+        LEFT_TABLE.to_string(),
+        sequence_column,
+        quality_column,
+        pattern,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, sequence_column, quality_column, pattern))]
+fn py_extract_umi_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    sequence_column: String,
+    quality_column: String,
+    pattern: String,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_extract_umi(
+        ctx,
+        table,
+        sequence_column,
+        quality_column,
+        pattern,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, umi_column, sequence_column, prefix_length, max_distance, groups))]
+fn py_umi_dedup_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    umi_column: String,
+    sequence_column: String,
+    prefix_length: Option<usize>,
+    max_distance: usize,
+    groups: bool,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_umi_dedup(
+        ctx,
+        LEFT_TABLE.to_string(),
+        umi_column,
+        sequence_column,
+        prefix_length,
+        max_distance,
+        groups,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, umi_column, sequence_column, prefix_length, max_distance, groups))]
+fn py_umi_dedup_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    umi_column: String,
+    sequence_column: String,
+    prefix_length: Option<usize>,
+    max_distance: usize,
+    groups: bool,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_umi_dedup(
+        ctx,
+        table,
+        umi_column,
+        sequence_column,
+        prefix_length,
+        max_distance,
+        groups,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, samples, index_column, max_mismatches))]
+fn py_demultiplex_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    samples: Vec<(String, String, Option<String>)>,
+    index_column: String,
+    max_mismatches: usize,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+    let samples = samples
+        .into_iter()
+        .map(|(sample, i7, i5)| SampleBarcodes::new(sample, i7, i5))
+        .collect();
+
+    Ok(PyDataFrame::new(rt.block_on(do_demultiplex(
+        ctx,
+        LEFT_TABLE.to_string(),
+        samples,
+        index_column,
+        max_mismatches,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, samples, index_column, max_mismatches))]
+fn py_demultiplex_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    samples: Vec<(String, String, Option<String>)>,
+    index_column: String,
+    max_mismatches: usize,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+    let samples = samples
+        .into_iter()
+        .map(|(sample, i7, i5)| SampleBarcodes::new(sample, i7, i5))
+        .collect();
+
+    Ok(PyDataFrame::new(rt.block_on(do_demultiplex(
+        ctx,
+        table,
+        samples,
+        index_column,
+        max_mismatches,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df))]
+fn py_flagstat_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_flagstat(ctx, LEFT_TABLE.to_string()))?,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table))]
+fn py_flagstat_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_flagstat(ctx, table))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df))]
+fn py_idxstats_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_idxstats(
+        ctx,
+        LEFT_TABLE.to_string(),
+        None,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table))]
+fn py_idxstats_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    // Reference lengths are only in the header of local BAM files.
+    let references = if df_path_or_table.to_lowercase().ends_with(".bam")
+        && std::path::Path::new(&df_path_or_table).exists()
+    {
+        Some(read_bam_references(&df_path_or_table)?)
+    } else {
+        None
+    };
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_idxstats(ctx, table, references))?,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, group_by, include_duplicates))]
+fn py_insert_size_histogram_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    group_by: Vec<String>,
+    include_duplicates: bool,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_insert_size_histogram(
+        ctx,
+        LEFT_TABLE.to_string(),
+        group_by,
+        include_duplicates,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, group_by, include_duplicates))]
+fn py_insert_size_histogram_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    group_by: Vec<String>,
+    include_duplicates: bool,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_insert_size_histogram(
+        ctx,
+        table,
+        group_by,
+        include_duplicates,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df, group_by))]
+fn py_mapq_distribution_frame(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    group_by: Vec<String>,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    register_frame(py_ctx, df, LEFT_TABLE.to_string())?;
+
+    Ok(PyDataFrame::new(rt.block_on(do_mapq_distribution(
+        ctx,
+        LEFT_TABLE.to_string(),
+        group_by,
+    ))?))
+}
+
+#[pyfunction]
+#[pyo3(signature = (py_ctx, df_path_or_table, group_by))]
+fn py_mapq_distribution_scan(
+    py_ctx: &PyBioSessionContext,
+    df_path_or_table: String,
+    group_by: Vec<String>,
+) -> PyResult<PyDataFrame> {
+    let rt = Runtime::new().unwrap();
+    let ctx = &py_ctx.ctx;
+    let table = maybe_register_table(df_path_or_table, &LEFT_TABLE.to_string(), None, ctx, &rt)?;
+
+    Ok(PyDataFrame::new(
+        rt.block_on(do_mapq_distribution(ctx, table, group_by))?,
+    ))
+}
+
 #[pymodule]
 fn polars_bio(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     pyo3_log::init();
@@ -459,6 +1493,14 @@ fn polars_bio(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(range_operation_scan, m)?)?;
     m.add_function(wrap_pyfunction!(stream_range_operation_scan, m)?)?;
     m.add_function(wrap_pyfunction!(py_register_table, m)?)?;
+    m.add_function(wrap_pyfunction!(py_build_interval_index_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_build_interval_index_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(indexed_range_operation_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(indexed_range_operation_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_liftover_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_liftover_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sv_match_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sv_match_frame, m)?)?;
     m.add_function(wrap_pyfunction!(py_read_table, m)?)?;
     m.add_function(wrap_pyfunction!(py_read_sql, m)?)?;
     m.add_function(wrap_pyfunction!(py_scan_sql, m)?)?;
@@ -468,6 +1510,45 @@ fn polars_bio(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(py_from_polars, m)?)?;
     m.add_function(wrap_pyfunction!(py_base_sequence_content_frame, m)?)?;
     m.add_function(wrap_pyfunction!(py_base_sequence_content_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(stream_base_sequence_content_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(stream_base_sequence_content_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_base_sequence_quality_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_base_sequence_quality_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_per_sequence_quality_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_per_sequence_quality_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_per_sequence_gc_content_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_per_sequence_gc_content_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_gc_content_fit_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sequence_length_distribution_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sequence_length_distribution_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sequence_duplication_levels_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sequence_duplication_levels_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_overrepresented_sequences_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_overrepresented_sequences_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_adapter_content_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_adapter_content_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_kmer_count_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_kmer_count_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_detect_phred_encoding_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_detect_phred_encoding_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_reencode_quality_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_reencode_quality_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_fastq_qc_report_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_fastq_qc_report_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_extract_umi_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_extract_umi_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_umi_dedup_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_umi_dedup_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_demultiplex_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_demultiplex_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_flagstat_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_flagstat_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_idxstats_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_idxstats_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_insert_size_histogram_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_insert_size_histogram_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_mapq_distribution_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(py_mapq_distribution_scan, m)?)?;
     // m.add_function(wrap_pyfunction!(unary_operation_scan, m)?)?;
     m.add_class::<PyBioSessionContext>()?;
     m.add_class::<FilterOp>()?;
@@ -480,6 +1561,8 @@ fn polars_bio(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<FastqReadOptions>()?;
     m.add_class::<BamReadOptions>()?;
     m.add_class::<BedReadOptions>()?;
+    m.add_class::<IntervalIndexInfo>()?;
+    m.add_class::<SvMatchOptions>()?;
     m.add_class::<PyObjectStorageOptions>()?;
     Ok(())
 }
@@ -0,0 +1,601 @@
+use std::any::Any;
+use std::cmp::{max, min};
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::Arc;
+
+use arrow::compute::{cast, take};
+use arrow_array::{Array, ArrayRef, Int64Array, RecordBatch, StringArray, UInt32Array};
+use arrow_schema::{DataType, Field, FieldRef, Schema, SchemaRef};
+use async_trait::async_trait;
+use coitrees::{COITree, Interval, IntervalTree};
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::TableType;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::physical_expr::{EquivalenceProperties, Partitioning};
+use datafusion::physical_plan::repartition::RepartitionExec;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionMode, ExecutionPlan, PlanProperties,
+};
+use datafusion::prelude::{Expr, SessionContext};
+use flate2::read::MultiGzDecoder;
+use fnv::FnvHashMap;
+use futures_util::stream::BoxStream;
+use futures_util::{StreamExt, TryStreamExt};
+use log::info;
+
+use crate::option::FilterOp;
+use crate::udtf::get_join_col_arrays;
+
+pub const LIFTOVER_STRAND_COLUMN: &str = "liftover_strand";
+pub const UNMAPPED_REASON_COLUMN: &str = "reason";
+
+/// Header of a single UCSC chain: the target ("to") side of the alignment.
+#[derive(Clone, Debug)]
+struct Chain {
+    query_name: String,
+    query_size: i64,
+    query_reverse: bool,
+}
+
+/// Ungapped aligned block of a chain. Coordinates are 0-based half-open,
+/// `query_start` is given on the strand of the chain's query side.
+#[derive(Clone, Debug)]
+struct ChainBlock {
+    chain: usize,
+    target_start: i64,
+    query_start: i64,
+    size: i64,
+}
+
+/// Chain blocks indexed by source contig with one COITree per contig.
+pub struct ChainIndex {
+    chains: Vec<Chain>,
+    blocks: Vec<ChainBlock>,
+    trees: FnvHashMap<String, COITree<u32, u32>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiftoverStatus {
+    Mapped {
+        contig: String,
+        start: i64,
+        end: i64,
+        reverse: bool,
+    },
+    Unmapped(&'static str),
+}
+
+fn parse_field<T: std::str::FromStr>(value: Option<&str>, line_no: usize) -> Result<T> {
+    value.and_then(|v| v.parse::<T>().ok()).ok_or_else(|| {
+        DataFusionError::Execution(format!("Invalid chain file record at line {}", line_no))
+    })
+}
+
+impl ChainIndex {
+    /// Reads a UCSC chain file (optionally gzip-compressed).
+    pub fn from_path(path: &str) -> Result<Self> {
+        let file = File::open(path)?;
+        let reader: Box<dyn Read> = if path.ends_with(".gz") {
+            Box::new(MultiGzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+        let index = Self::from_reader(BufReader::new(reader))?;
+        info!(
+            "Loaded {} chains with {} blocks from: {}",
+            index.chains.len(),
+            index.blocks.len(),
+            path
+        );
+        Ok(index)
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let mut chains = Vec::new();
+        let mut blocks = Vec::new();
+        let mut nodes = FnvHashMap::<String, Vec<Interval<u32>>>::default();
+        // (target contig, current target position, current query position)
+        let mut current: Option<(String, i64, i64)> = None;
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            if line.starts_with("chain") {
+                fields.next();
+                let _score = fields.next();
+                let target_name = fields.next().unwrap_or_default().to_string();
+                let _target_size = parse_field::<i64>(fields.next(), line_no)?;
+                let _target_strand = fields.next();
+                let target_start = parse_field::<i64>(fields.next(), line_no)?;
+                let _target_end = parse_field::<i64>(fields.next(), line_no)?;
+                let query_name = fields.next().unwrap_or_default().to_string();
+                let query_size = parse_field::<i64>(fields.next(), line_no)?;
+                let query_reverse = fields.next() == Some("-");
+                let query_start = parse_field::<i64>(fields.next(), line_no)?;
+                chains.push(Chain {
+                    query_name,
+                    query_size,
+                    query_reverse,
+                });
+                current = Some((target_name, target_start, query_start));
+                continue;
+            }
+            let Some((target_name, target_pos, query_pos)) = current.as_mut() else {
+                return Err(DataFusionError::Execution(format!(
+                    "Chain file block without a chain header at line {}",
+                    line_no
+                )));
+            };
+            let size = parse_field::<i64>(fields.next(), line_no)?;
+            let block_id = blocks.len() as u32;
+            blocks.push(ChainBlock {
+                chain: chains.len() - 1,
+                target_start: *target_pos,
+                query_start: *query_pos,
+                size,
+            });
+            nodes
+                .entry(target_name.clone())
+                .or_default()
+                .push(Interval::new(
+                    *target_pos as i32,
+                    (*target_pos + size - 1) as i32,
+                    block_id,
+                ));
+            match (fields.next(), fields.next()) {
+                (Some(dt), Some(dq)) => {
+                    *target_pos += size + parse_field::<i64>(Some(dt), line_no)?;
+                    *query_pos += size + parse_field::<i64>(Some(dq), line_no)?;
+                },
+                // the last block of a chain has no gap sizes
+                _ => current = None,
+            }
+        }
+
+        let trees = nodes
+            .into_iter()
+            .map(|(contig, nodes)| (contig, COITree::new(&nodes)))
+            .collect();
+        Ok(ChainIndex {
+            chains,
+            blocks,
+            trees,
+        })
+    }
+
+    pub fn num_chains(&self) -> usize {
+        self.chains.len()
+    }
+
+    /// Lifts a 0-based half-open interval. The interval is mapped when exactly one
+    /// chain covers at least `min_match` of its bases. Otherwise it is reported as
+    /// unmapped with one of the reasons: `no_chain`, `no_overlap`,
+    /// `partially_deleted`, `split` (bases spread over several chains) or
+    /// `duplicated` (several chains map the whole interval).
+    pub fn lift(&self, contig: &str, start: i64, end: i64, min_match: f64) -> LiftoverStatus {
+        let Some(tree) = self.trees.get(contig) else {
+            return LiftoverStatus::Unmapped("no_chain");
+        };
+        // zero-length intervals (insertion points) are looked up as a single base
+        let lookup_end = max(end, start + 1);
+        // chain -> (covered bases, min query position, max query position)
+        let mut hits = FnvHashMap::<usize, (i64, i64, i64)>::default();
+        tree.query(start as i32, (lookup_end - 1) as i32, |node| {
+            let block = &self.blocks[*node.metadata as usize];
+            let overlap_start = max(start, block.target_start);
+            let overlap_end = min(lookup_end, block.target_start + block.size);
+            let query_start = block.query_start + overlap_start - block.target_start;
+            let query_end = block.query_start + overlap_end - block.target_start;
+            let hit = hits.entry(block.chain).or_insert((0, i64::MAX, i64::MIN));
+            hit.0 += overlap_end - overlap_start;
+            hit.1 = min(hit.1, query_start);
+            hit.2 = max(hit.2, query_end);
+        });
+        if hits.is_empty() {
+            return LiftoverStatus::Unmapped("no_overlap");
+        }
+        let length = (lookup_end - start) as f64;
+        let passing = hits
+            .iter()
+            .filter(|(_, hit)| hit.0 as f64 >= min_match * length)
+            .collect::<Vec<_>>();
+        match passing.as_slice() {
+            [(chain, (_, query_start, query_end))] => {
+                let chain = &self.chains[**chain];
+                let (lifted_start, lifted_end) = if chain.query_reverse {
+                    (chain.query_size - query_end, chain.query_size - query_start)
+                } else {
+                    (*query_start, *query_end)
+                };
+                LiftoverStatus::Mapped {
+                    contig: chain.query_name.clone(),
+                    start: lifted_start,
+                    end: if end == start {
+                        lifted_start
+                    } else {
+                        lifted_end
+                    },
+                    reverse: chain.query_reverse,
+                }
+            },
+            [] if hits.len() == 1 => LiftoverStatus::Unmapped("partially_deleted"),
+            [] => LiftoverStatus::Unmapped("split"),
+            _ => LiftoverStatus::Unmapped("duplicated"),
+        }
+    }
+}
+
+fn complement(base: u8) -> u8 {
+    match base {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        b'a' => b't',
+        b'c' => b'g',
+        b'g' => b'c',
+        b't' => b'a',
+        other => other,
+    }
+}
+
+/// Reverse complements each allele of a (possibly multi-allelic) VCF allele string.
+/// Symbolic and breakend alleles are left untouched. The padding base of indels
+/// is not moved, so reverse complemented indels end with it instead of starting
+/// with it and still span the lifted interval; re-anchoring them needs the
+/// target reference sequence.
+fn reverse_complement_alleles(alleles: &str) -> String {
+    let mut result = String::with_capacity(alleles.len());
+    for (i, allele) in alleles.split(['|', ',']).enumerate() {
+        if i > 0 {
+            // alleles keep their lengths, so the separator is at the same offset
+            result.push(alleles.as_bytes()[result.len()] as char);
+        }
+        if allele.starts_with('<') || allele.contains(['[', ']']) {
+            result.push_str(allele);
+        } else {
+            result.extend(allele.bytes().rev().map(|b| complement(b) as char));
+        }
+    }
+    result
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiftoverOutput {
+    Mapped,
+    Unmapped,
+}
+
+pub struct LiftoverProvider {
+    session: Arc<SessionContext>,
+    chains: Arc<ChainIndex>,
+    input_table: String,
+    columns: (String, String, String),
+    filter_op: FilterOp,
+    min_match: f64,
+    output: LiftoverOutput,
+    schema: SchemaRef,
+}
+
+impl LiftoverProvider {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session: Arc<SessionContext>,
+        chains: Arc<ChainIndex>,
+        input_table: String,
+        input_schema: Schema,
+        columns: Vec<String>,
+        filter_op: FilterOp,
+        min_match: f64,
+        output: LiftoverOutput,
+    ) -> Self {
+        let fields = match output {
+            LiftoverOutput::Mapped => {
+                let mut fields = input_schema
+                    .fields()
+                    .iter()
+                    .map(|f| {
+                        if f.name() == &columns[0] {
+                            FieldRef::new(f.as_ref().clone().with_data_type(DataType::Utf8))
+                        } else {
+                            f.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                fields.push(FieldRef::new(Field::new(
+                    LIFTOVER_STRAND_COLUMN,
+                    DataType::Utf8,
+                    false,
+                )));
+                fields
+            },
+            LiftoverOutput::Unmapped => {
+                let mut fields = input_schema.fields().to_vec();
+                fields.push(FieldRef::new(Field::new(
+                    UNMAPPED_REASON_COLUMN,
+                    DataType::Utf8,
+                    false,
+                )));
+                fields
+            },
+        };
+        Self {
+            session,
+            chains,
+            input_table,
+            columns: (columns[0].clone(), columns[1].clone(), columns[2].clone()),
+            filter_op,
+            min_match,
+            output,
+            schema: Arc::new(Schema::new(fields)),
+        }
+    }
+}
+
+impl Debug for LiftoverProvider {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TableProvider for LiftoverProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        _projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let target_partitions = self
+            .session
+            .state()
+            .config()
+            .options()
+            .execution
+            .target_partitions;
+        Ok(Arc::new(LiftoverExec {
+            schema: self.schema.clone(),
+            session: Arc::clone(&self.session),
+            chains: Arc::clone(&self.chains),
+            input_table: self.input_table.clone(),
+            columns: self.columns.clone(),
+            filter_op: self.filter_op.clone(),
+            min_match: self.min_match,
+            output: self.output.clone(),
+            cache: PlanProperties::new(
+                EquivalenceProperties::new(self.schema.clone()),
+                Partitioning::UnknownPartitioning(target_partitions),
+                ExecutionMode::Bounded,
+            ),
+        }))
+    }
+}
+
+struct LiftoverExec {
+    schema: SchemaRef,
+    session: Arc<SessionContext>,
+    chains: Arc<ChainIndex>,
+    input_table: String,
+    columns: (String, String, String),
+    filter_op: FilterOp,
+    min_match: f64,
+    output: LiftoverOutput,
+    cache: PlanProperties,
+}
+
+impl Debug for LiftoverExec {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl DisplayAs for LiftoverExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "LiftoverExec: output={:?}", self.output)
+    }
+}
+
+impl ExecutionPlan for LiftoverExec {
+    fn name(&self) -> &str {
+        "LiftoverExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.cache
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let fut = get_stream(
+            Arc::clone(&self.session),
+            Arc::clone(&self.chains),
+            self.input_table.clone(),
+            self.columns.clone(),
+            self.filter_op.clone(),
+            self.min_match,
+            self.output.clone(),
+            self.schema.clone(),
+            self.cache.partitioning.partition_count(),
+            partition,
+            context,
+        );
+        let stream = futures::stream::once(fut).try_flatten();
+        let schema = self.schema.clone();
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, stream)))
+    }
+}
+
+fn lift_batch(
+    rb: &RecordBatch,
+    chains: &ChainIndex,
+    columns: &(String, String, String),
+    filter_op: &FilterOp,
+    min_match: f64,
+    output: &LiftoverOutput,
+    new_schema: &SchemaRef,
+) -> Result<RecordBatch> {
+    let (contig, pos_start, pos_end) = get_join_col_arrays(rb, columns.clone());
+    // chain files use 0-based half-open coordinates
+    let shift = if *filter_op == FilterOp::Weak { 1 } else { 0 };
+
+    let mut indices = Vec::new();
+    let mut contigs = Vec::new();
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+    let mut strands = Vec::new();
+    let mut reasons = Vec::new();
+    for i in 0..rb.num_rows() {
+        let start = pos_start.value(i) as i64 - shift;
+        let end = pos_end.value(i) as i64;
+        match (chains.lift(contig.value(i), start, end, min_match), output) {
+            (
+                LiftoverStatus::Mapped {
+                    contig,
+                    start,
+                    end,
+                    reverse,
+                },
+                LiftoverOutput::Mapped,
+            ) => {
+                indices.push(i as u32);
+                contigs.push(contig);
+                starts.push(start + shift);
+                ends.push(end);
+                strands.push(if reverse { "-" } else { "+" });
+            },
+            (LiftoverStatus::Unmapped(reason), LiftoverOutput::Unmapped) => {
+                indices.push(i as u32);
+                reasons.push(reason);
+            },
+            _ => {},
+        }
+    }
+
+    let indices = UInt32Array::from(indices);
+    let mut arrays = Vec::with_capacity(new_schema.fields().len());
+    for (field, array) in rb.schema().fields().iter().zip(rb.columns()) {
+        let array = take(array.as_ref(), &indices, None)?;
+        let array: ArrayRef = match output {
+            LiftoverOutput::Unmapped => array,
+            LiftoverOutput::Mapped => {
+                let name = field.name();
+                if name == &columns.0 {
+                    Arc::new(StringArray::from(contigs.clone()))
+                } else if name == &columns.1 {
+                    cast(&Int64Array::from(starts.clone()), field.data_type())?
+                } else if name == &columns.2 {
+                    cast(&Int64Array::from(ends.clone()), field.data_type())?
+                } else if (name == "ref" || name == "alt")
+                    && matches!(
+                        field.data_type(),
+                        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+                    )
+                    && strands.contains(&"-")
+                {
+                    let alleles = cast(&array, &DataType::Utf8)?;
+                    let alleles = alleles.as_any().downcast_ref::<StringArray>().unwrap();
+                    let alleles = alleles
+                        .iter()
+                        .zip(strands.iter())
+                        .map(|(allele, strand)| match (allele, *strand) {
+                            (Some(allele), "-") => Some(reverse_complement_alleles(allele)),
+                            (allele, _) => allele.map(|a| a.to_string()),
+                        })
+                        .collect::<StringArray>();
+                    cast(&alleles, field.data_type())?
+                } else {
+                    array
+                }
+            },
+        };
+        arrays.push(array);
+    }
+    match output {
+        LiftoverOutput::Mapped => arrays.push(Arc::new(StringArray::from(strands))),
+        LiftoverOutput::Unmapped => arrays.push(Arc::new(StringArray::from(reasons))),
+    }
+    Ok(RecordBatch::try_new(new_schema.clone(), arrays)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn get_stream(
+    session: Arc<SessionContext>,
+    chains: Arc<ChainIndex>,
+    input_table: String,
+    columns: (String, String, String),
+    filter_op: FilterOp,
+    min_match: f64,
+    output: LiftoverOutput,
+    new_schema: SchemaRef,
+    target_partitions: usize,
+    partition: usize,
+    context: Arc<TaskContext>,
+) -> Result<SendableRecordBatchStream> {
+    let plan = session
+        .table(input_table)
+        .await?
+        .create_physical_plan()
+        .await?;
+    let repartition_stream =
+        RepartitionExec::try_new(plan, Partitioning::RoundRobinBatch(target_partitions))?;
+    let partition_stream = repartition_stream.execute(partition, context)?;
+    let new_schema_out = new_schema.clone();
+
+    let iter = partition_stream.map(move |rb| {
+        rb.and_then(|rb| {
+            lift_batch(
+                &rb,
+                &chains,
+                &columns,
+                &filter_op,
+                min_match,
+                &output,
+                &new_schema,
+            )
+        })
+    });
+
+    let adapted_stream =
+        RecordBatchStreamAdapter::new(new_schema_out, Box::pin(iter) as BoxStream<_>);
+    Ok(Box::pin(adapted_stream))
+}
@@ -1,16 +1,23 @@
 use std::sync::Arc;
 
 use datafusion::catalog_common::TableReference;
+use datafusion::dataframe::DataFrameWriteOptions;
+use datafusion::error::DataFusionError;
 use exon::ExonSession;
 use log::{debug, info};
 use sequila_core::session_context::{Algorithm, SequilaConfig};
 use tokio::runtime::Runtime;
 
-use crate::context::set_option_internal;
-use crate::option::{FilterOp, RangeOp, RangeOptions};
+use crate::context::{set_option_internal, PyBioSessionContext};
+use crate::interval_index::{IndexedRangeProvider, IntervalIndex};
+use crate::liftover::{ChainIndex, LiftoverOutput, LiftoverProvider};
+use crate::option::{FilterOp, IntervalIndexInfo, RangeOp, RangeOptions, SvMatchOptions};
 use crate::query::{nearest_query, overlap_query};
+use crate::sv_match::match_structural_variants;
+use crate::sweep_line::SweepLineProvider;
 use crate::udtf::CountOverlapsProvider;
 use crate::utils::default_cols_to_string;
+use crate::vcf_intervals::register_vcf_intervals;
 use crate::DEFAULT_COLUMN_NAMES;
 
 pub(crate) struct QueryParams {
@@ -29,7 +36,7 @@ pub(crate) fn do_range_operation(
     range_options: RangeOptions,
     left_table: String,
     right_table: String,
-) -> datafusion::dataframe::DataFrame {
+) -> datafusion::error::Result<datafusion::dataframe::DataFrame> {
     // defaults
     match &range_options.overlap_alg {
         Some(alg) if alg == "coitreesnearest" => {
@@ -46,10 +53,20 @@ pub(crate) fn do_range_operation(
             );
         },
     }
+    let filter_op = range_options.filter_op.clone().unwrap();
+    let left_table = rt.block_on(register_vcf_intervals(ctx, left_table, &filter_op))?;
+    let right_table = rt.block_on(register_vcf_intervals(ctx, right_table, &filter_op))?;
     let streaming = range_options.streaming.unwrap_or(false);
     if streaming {
         info!("Running in streaming mode...");
     }
+    if range_options.sorted_input.unwrap_or(false) {
+        info!(
+            "Running {} operation with sweep-line algorithm on sorted inputs...",
+            range_options.range_op
+        );
+        return rt.block_on(do_sweep_line(ctx, range_options, left_table, right_table));
+    }
     info!(
         "Running {} operation with algorithm {} and {} thread(s)...",
         range_options.range_op,
@@ -68,7 +85,7 @@ pub(crate) fn do_range_operation(
             .execution
             .target_partitions
     );
-    match range_options.range_op {
+    Ok(match range_options.range_op {
         RangeOp::Overlap => rt.block_on(do_overlap(ctx, range_options, left_table, right_table)),
         RangeOp::Nearest => {
             set_option_internal(ctx, "sequila.interval_join_algorithm", "coitreesnearest");
@@ -90,7 +107,7 @@ pub(crate) fn do_range_operation(
         )),
 
         _ => panic!("Unsupported operation"),
-    }
+    })
 }
 
 async fn do_nearest(
@@ -166,6 +183,249 @@ async fn do_count_overlaps_coverage_naive(
     ctx.sql(&query).await.unwrap()
 }
 
+async fn do_sweep_line(
+    ctx: &ExonSession,
+    range_opts: RangeOptions,
+    left_table: String,
+    right_table: String,
+) -> datafusion::error::Result<datafusion::dataframe::DataFrame> {
+    let columns_1 = match range_opts.columns_1 {
+        Some(cols) => cols,
+        _ => default_cols_to_string(&DEFAULT_COLUMN_NAMES),
+    };
+    let columns_2 = match range_opts.columns_2 {
+        Some(cols) => cols,
+        _ => default_cols_to_string(&DEFAULT_COLUMN_NAMES),
+    };
+    let suffixes = match range_opts.suffixes {
+        Some((s1, s2)) => (s1, s2),
+        _ => ("_1".to_string(), "_2".to_string()),
+    };
+    // nearest looks up the closest right interval for each left one,
+    // the other operations stream the right table against the left one
+    let ((query_table, query_columns), (reference_table, reference_columns)) =
+        match range_opts.range_op {
+            RangeOp::Nearest => ((left_table, columns_1), (right_table, columns_2)),
+            RangeOp::Overlap | RangeOp::Coverage | RangeOp::CountOverlapsNaive => {
+                ((right_table, columns_2), (left_table, columns_1))
+            },
+            op => {
+                return Err(DataFusionError::Plan(format!(
+                    "Operation {} is not supported for sorted inputs",
+                    op
+                )))
+            },
+        };
+    let session = &ctx.session;
+    let query_schema = session
+        .table(TableReference::from(query_table.clone()))
+        .await?
+        .schema()
+        .as_arrow()
+        .clone();
+    let reference_schema = session
+        .table(TableReference::from(reference_table.clone()))
+        .await?
+        .schema()
+        .as_arrow()
+        .clone();
+    let sweep_line_provider = SweepLineProvider::new(
+        Arc::new(session.clone()),
+        query_table,
+        query_schema,
+        query_columns,
+        reference_table,
+        reference_schema,
+        reference_columns,
+        range_opts.range_op,
+        range_opts.filter_op.unwrap(),
+        suffixes,
+    );
+    let table_name = "sweep_line".to_string();
+    session.deregister_table(table_name.clone())?;
+    session.register_table(table_name.clone(), Arc::new(sweep_line_provider))?;
+    let query = format!("SELECT * FROM {}", table_name);
+    debug!("Query: {}", query);
+    session.sql(&query).await
+}
+
+pub(crate) fn build_interval_index(
+    py_ctx: &PyBioSessionContext,
+    rt: &Runtime,
+    table_name: String,
+    name: String,
+    columns: Vec<String>,
+    persist: bool,
+) -> datafusion::error::Result<IntervalIndexInfo> {
+    let ctx = &py_ctx.ctx;
+    let df = rt.block_on(ctx.session.table(&table_name))?;
+    let schema = Arc::new(df.schema().as_arrow().clone());
+    let batches = rt.block_on(df.collect())?;
+    let index = IntervalIndex::new(schema, batches, columns.clone());
+    let path = if persist {
+        let path = format!("{}/{}_index.parquet", py_ctx.catalog_dir, name);
+        let df = ctx.session.read_batches(index.batches().to_vec())?;
+        rt.block_on(df.write_parquet(&path, DataFrameWriteOptions::new(), None))?;
+        info!("Interval index {} saved to: {}", name, path);
+        Some(path)
+    } else {
+        None
+    };
+    info!(
+        "Interval index {} built for {} intervals",
+        name,
+        index.num_rows()
+    );
+    let index_info = IntervalIndexInfo {
+        name: name.clone(),
+        columns,
+        num_rows: index.num_rows(),
+        path,
+    };
+    py_ctx
+        .interval_indexes
+        .lock()
+        .unwrap()
+        .insert(name, Arc::new(index));
+    Ok(index_info)
+}
+
+pub(crate) fn do_indexed_range_operation(
+    ctx: &ExonSession,
+    rt: &Runtime,
+    index: Arc<IntervalIndex>,
+    range_options: RangeOptions,
+    query_table: String,
+) -> datafusion::error::Result<datafusion::dataframe::DataFrame> {
+    info!(
+        "Running {} operation against interval index with {} thread(s)...",
+        range_options.range_op,
+        ctx.session
+            .state()
+            .config()
+            .options()
+            .execution
+            .target_partitions
+    );
+    let columns = match range_options.columns_1 {
+        Some(cols) => cols,
+        _ => default_cols_to_string(&DEFAULT_COLUMN_NAMES),
+    };
+    let suffixes = match range_options.suffixes {
+        Some((s1, s2)) => (s1, s2),
+        _ => ("_1".to_string(), "_2".to_string()),
+    };
+    rt.block_on(async {
+        let query_table =
+            register_vcf_intervals(ctx, query_table, range_options.filter_op.as_ref().unwrap())
+                .await?;
+        let session = &ctx.session;
+        let query_schema = session
+            .table(TableReference::from(query_table.clone()))
+            .await?
+            .schema()
+            .as_arrow()
+            .clone();
+        let indexed_range_provider = IndexedRangeProvider::new(
+            Arc::new(session.clone()),
+            index,
+            query_table,
+            query_schema,
+            columns,
+            range_options.range_op,
+            range_options.filter_op.unwrap(),
+            suffixes,
+        );
+        let table_name = "indexed_range".to_string();
+        session.deregister_table(table_name.clone())?;
+        session.register_table(table_name.clone(), Arc::new(indexed_range_provider))?;
+        let query = format!("SELECT * FROM {}", table_name);
+        debug!("Query: {}", query);
+        session.sql(&query).await
+    })
+}
+
+pub(crate) fn do_liftover(
+    ctx: &ExonSession,
+    rt: &Runtime,
+    input_table: String,
+    chain_path: String,
+    columns: Vec<String>,
+    filter_op: FilterOp,
+    min_match: f64,
+) -> datafusion::error::Result<(
+    datafusion::dataframe::DataFrame,
+    datafusion::dataframe::DataFrame,
+)> {
+    let chains = Arc::new(ChainIndex::from_path(&chain_path)?);
+    info!(
+        "Running liftover with {} chains and min_match {}",
+        chains.num_chains(),
+        min_match
+    );
+    rt.block_on(async {
+        let input_table = register_vcf_intervals(ctx, input_table, &filter_op).await?;
+        let session = &ctx.session;
+        let input_schema = session
+            .table(TableReference::from(input_table.clone()))
+            .await?
+            .schema()
+            .as_arrow()
+            .clone();
+        let mut outputs = Vec::with_capacity(2);
+        for (table_name, output) in [
+            ("liftover_mapped", LiftoverOutput::Mapped),
+            ("liftover_unmapped", LiftoverOutput::Unmapped),
+        ] {
+            let liftover_provider = LiftoverProvider::new(
+                Arc::new(session.clone()),
+                Arc::clone(&chains),
+                input_table.clone(),
+                input_schema.clone(),
+                columns.clone(),
+                filter_op.clone(),
+                min_match,
+                output,
+            );
+            session.deregister_table(table_name)?;
+            session.register_table(table_name, Arc::new(liftover_provider))?;
+            let query = format!("SELECT * FROM {}", table_name);
+            debug!("Query: {}", query);
+            outputs.push(session.sql(&query).await?);
+        }
+        let unmapped = outputs.pop().unwrap();
+        let mapped = outputs.pop().unwrap();
+        Ok((mapped, unmapped))
+    })
+}
+
+pub(crate) fn do_sv_match(
+    ctx: &ExonSession,
+    rt: &Runtime,
+    options: SvMatchOptions,
+    calls_table: String,
+    base_table: String,
+) -> datafusion::error::Result<datafusion::dataframe::DataFrame> {
+    info!(
+        "Running SV matching with max distance {} and min reciprocal overlap {}",
+        options.max_distance, options.min_reciprocal_overlap
+    );
+    let (calls, base) = rt.block_on(async {
+        let mut tables = Vec::with_capacity(2);
+        for table in [calls_table, base_table] {
+            let table = register_vcf_intervals(ctx, table, &FilterOp::Weak).await?;
+            let df = ctx.session.table(TableReference::from(table)).await?;
+            let schema = Arc::new(df.schema().as_arrow().clone());
+            tables.push((schema, df.collect().await?));
+        }
+        let base = tables.pop().unwrap();
+        let calls = tables.pop().unwrap();
+        Ok::<_, datafusion::error::DataFusionError>((calls, base))
+    })?;
+    let batch = match_structural_variants(calls.0, calls.1, base.0, base.1, &options)?;
+    ctx.session.read_batch(batch)
+}
+
 async fn get_non_join_columns(
     table_name: String,
     join_columns: Vec<String>,
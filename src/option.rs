@@ -22,13 +22,15 @@ pub struct RangeOptions {
     pub overlap_alg: Option<String>,
     #[pyo3(get, set)]
     pub streaming: Option<bool>,
+    #[pyo3(get, set)]
+    pub sorted_input: Option<bool>,
 }
 
 #[pymethods]
 impl RangeOptions {
     #[allow(clippy::too_many_arguments)]
     #[new]
-    #[pyo3(signature = (range_op, filter_op=None, suffixes=None, columns_1=None, columns_2=None, on_cols=None, overlap_alg=None, streaming=None))]
+    #[pyo3(signature = (range_op, filter_op=None, suffixes=None, columns_1=None, columns_2=None, on_cols=None, overlap_alg=None, streaming=None, sorted_input=None))]
     pub fn new(
         range_op: RangeOp,
         filter_op: Option<FilterOp>,
@@ -38,6 +40,7 @@ impl RangeOptions {
         on_cols: Option<Vec<String>>,
         overlap_alg: Option<String>,
         streaming: Option<bool>,
+        sorted_input: Option<bool>,
     ) -> Self {
         RangeOptions {
             range_op,
@@ -48,6 +51,7 @@ impl RangeOptions {
             on_cols,
             overlap_alg,
             streaming,
+            sorted_input,
         }
     }
 }
@@ -57,6 +61,42 @@ impl std::fmt::Display for RangeOptions {
     }
 }
 
+#[pyclass(name = "SvMatchOptions")]
+#[derive(Clone, Debug)]
+pub struct SvMatchOptions {
+    #[pyo3(get, set)]
+    pub max_distance: i64,
+    #[pyo3(get, set)]
+    pub min_reciprocal_overlap: f64,
+    #[pyo3(get, set)]
+    pub match_type: bool,
+    #[pyo3(get, set)]
+    pub use_confidence_intervals: bool,
+    #[pyo3(get, set)]
+    pub suffixes: Option<(String, String)>,
+}
+
+#[pymethods]
+impl SvMatchOptions {
+    #[new]
+    #[pyo3(signature = (max_distance=500, min_reciprocal_overlap=0.0, match_type=true, use_confidence_intervals=true, suffixes=None))]
+    pub fn new(
+        max_distance: i64,
+        min_reciprocal_overlap: f64,
+        match_type: bool,
+        use_confidence_intervals: bool,
+        suffixes: Option<(String, String)>,
+    ) -> Self {
+        SvMatchOptions {
+            max_distance,
+            min_reciprocal_overlap,
+            match_type,
+            use_confidence_intervals,
+            suffixes,
+        }
+    }
+}
+
 #[pyclass(eq, eq_int)]
 #[derive(Clone, PartialEq, Debug)]
 pub enum FilterOp {
@@ -113,6 +153,15 @@ pub struct BioTable {
     pub path: String,
 }
 
+#[pyclass(eq, get_all)]
+#[derive(Clone, PartialEq, Debug)]
+pub struct IntervalIndexInfo {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub num_rows: usize,
+    pub path: Option<String>,
+}
+
 impl fmt::Display for InputFormat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let text = match self {
@@ -232,21 +281,31 @@ pub struct FastqReadOptions {
     #[pyo3(get, set)]
     pub thread_num: Option<usize>,
     pub object_storage_options: Option<ObjectStorageOptions>,
+    /// Path of the R2 file, read in lockstep with the R1 path.
+    #[pyo3(get, set)]
+    pub paired_path: Option<String>,
+    /// Whether mates are consecutive records of a single file.
+    #[pyo3(get, set)]
+    pub interleaved: bool,
 }
 
 #[pymethods]
 impl FastqReadOptions {
     #[new]
-    #[pyo3(signature = (thread_num=None, object_storage_options=None))]
+    #[pyo3(signature = (thread_num=None, object_storage_options=None, paired_path=None, interleaved=false))]
     pub fn new(
         thread_num: Option<usize>,
         object_storage_options: Option<PyObjectStorageOptions>,
+        paired_path: Option<String>,
+        interleaved: bool,
     ) -> Self {
         FastqReadOptions {
             thread_num,
             object_storage_options: pyobject_storage_options_to_object_storage_options(
                 object_storage_options,
             ),
+            paired_path,
+            interleaved,
         }
     }
     #[staticmethod]
@@ -262,6 +321,8 @@ impl FastqReadOptions {
                 timeout: Some(300), // 300 seconds
                 compression_type: Some(CompressionType::AUTO),
             }),
+            paired_path: None,
+            interleaved: false,
         }
     }
 }
@@ -0,0 +1,294 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use arrow::array::RecordBatch;
+use arrow::compute::{concat_batches, take};
+use arrow_array::{ArrayRef, StringArray, UInt32Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use datafusion::datasource::streaming::StreamingTable;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::PartitionStream;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion_bio_format_fastq::table_provider::FastqTableProvider;
+use exon::ExonSession;
+use futures_util::StreamExt;
+use log::info;
+
+use crate::option::FastqReadOptions;
+use crate::quality_control::string_array;
+
+fn paired_fastq_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("name", DataType::Utf8, true),
+        Field::new("seq1", DataType::Utf8, true),
+        Field::new("qual1", DataType::Utf8, true),
+        Field::new("seq2", DataType::Utf8, true),
+        Field::new("qual2", DataType::Utf8, true),
+    ]))
+}
+
+/// Read name without the `/1` or `/2` mate suffix of older Illumina reads.
+fn pair_name(name: &str) -> &str {
+    name.strip_suffix("/1")
+        .or_else(|| name.strip_suffix("/2"))
+        .unwrap_or(name)
+}
+
+/// Reads the batches of a FASTQ stream a number of rows at a time.
+struct ReadCursor {
+    stream: SendableRecordBatchStream,
+    batch: Option<RecordBatch>,
+    offset: usize,
+}
+
+impl ReadCursor {
+    fn new(stream: SendableRecordBatchStream) -> Self {
+        Self {
+            stream,
+            batch: None,
+            offset: 0,
+        }
+    }
+
+    fn available(&self) -> usize {
+        self.batch
+            .as_ref()
+            .map_or(0, |batch| batch.num_rows() - self.offset)
+    }
+
+    /// Pulls batches until there are rows to take, returning false at the
+    /// end of the stream.
+    async fn fill(&mut self) -> Result<bool> {
+        while self.available() == 0 {
+            match self.stream.next().await {
+                Some(batch) => {
+                    self.batch = Some(batch?);
+                    self.offset = 0;
+                },
+                None => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    fn take(&mut self, rows: usize) -> RecordBatch {
+        let batch = self.batch.as_ref().unwrap().slice(self.offset, rows);
+        self.offset += rows;
+        batch
+    }
+}
+
+/// Pairs the records of R1 and R2 streams, or consecutive records of an
+/// interleaved stream.
+struct PairedReads {
+    first: ReadCursor,
+    second: Option<ReadCursor>,
+    /// The last record of an interleaved batch whose mate is in the next one.
+    carry: Option<RecordBatch>,
+    pairs: usize,
+}
+
+impl PairedReads {
+    async fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        match self.second.as_mut() {
+            Some(second) => {
+                let (has_first, has_second) = (self.first.fill().await?, second.fill().await?);
+                match (has_first, has_second) {
+                    (false, false) => Ok(None),
+                    (true, false) | (false, true) => Err(DataFusionError::Execution(format!(
+                        "Paired FASTQ files have a different number of records, {} ends after {} pairs",
+                        if has_first { "R2" } else { "R1" },
+                        self.pairs
+                    ))),
+                    (true, true) => {
+                        let rows = self.first.available().min(second.available());
+                        let (r1, r2) = (self.first.take(rows), second.take(rows));
+                        self.pair_batch(&r1, &r2).map(Some)
+                    },
+                }
+            },
+            None => loop {
+                if !self.first.fill().await? {
+                    return match self.carry {
+                        Some(_) => Err(DataFusionError::Execution(format!(
+                            "Interleaved FASTQ file has an odd number of records, the mate of the record after {} pairs is missing",
+                            self.pairs
+                        ))),
+                        None => Ok(None),
+                    };
+                }
+                let batch = self.first.take(self.first.available());
+                let batch = match self.carry.take() {
+                    Some(carry) => concat_batches(&batch.schema(), [&carry, &batch])?,
+                    None => batch,
+                };
+                let rows = batch.num_rows();
+                if rows % 2 == 1 {
+                    self.carry = Some(batch.slice(rows - 1, 1));
+                }
+                if rows < 2 {
+                    continue;
+                }
+                let mates = |first: u32| {
+                    let indices =
+                        UInt32Array::from_iter_values((first..(rows / 2 * 2) as u32).step_by(2));
+                    batch
+                        .columns()
+                        .iter()
+                        .map(|column| take(column, &indices, None))
+                        .collect::<std::result::Result<Vec<ArrayRef>, _>>()
+                        .and_then(|columns| RecordBatch::try_new(batch.schema(), columns))
+                };
+                let (r1, r2) = (mates(0)?, mates(1)?);
+                return self.pair_batch(&r1, &r2).map(Some);
+            },
+        }
+    }
+
+    /// Checks that mates have the same names and combines them into rows of
+    /// the paired schema.
+    fn pair_batch(&mut self, r1: &RecordBatch, r2: &RecordBatch) -> Result<RecordBatch> {
+        let column = |batch: &RecordBatch, name: &str| -> Result<StringArray> {
+            let index = batch.schema().index_of(name)?;
+            string_array(batch.column(index))
+        };
+        let (names1, names2) = (column(r1, "name")?, column(r2, "name")?);
+        let mut names = Vec::with_capacity(r1.num_rows());
+        for (i, (name1, name2)) in names1.iter().zip(names2.iter()).enumerate() {
+            let (name1, name2) = (name1.unwrap_or_default(), name2.unwrap_or_default());
+            if pair_name(name1) != pair_name(name2) {
+                return Err(DataFusionError::Execution(format!(
+                    "Paired FASTQ records are out of sync at pair {}: {} and {}",
+                    self.pairs + i + 1,
+                    name1,
+                    name2
+                )));
+            }
+            names.push(pair_name(name1).to_string());
+        }
+        self.pairs += r1.num_rows();
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(names)),
+            Arc::new(column(r1, "sequence")?),
+            Arc::new(column(r1, "quality_scores")?),
+            Arc::new(column(r2, "sequence")?),
+            Arc::new(column(r2, "quality_scores")?),
+        ];
+        RecordBatch::try_new(paired_fastq_schema(), columns).map_err(DataFusionError::from)
+    }
+}
+
+/// Checks that a FASTQ scan has a single partition, as records of several
+/// partitions would be interleaved in no particular order.
+fn check_single_partition(plan: &Arc<dyn ExecutionPlan>) -> Result<()> {
+    match plan.output_partitioning().partition_count() {
+        1 => Ok(()),
+        partitions => Err(DataFusionError::Plan(format!(
+            "Paired FASTQ scans must have a single partition to keep mates in order, got {}",
+            partitions
+        ))),
+    }
+}
+
+/// A partition streaming read pairs from the physical plans of one or two
+/// FASTQ scans.
+struct PairedFastqPartition {
+    schema: SchemaRef,
+    plans: Vec<Arc<dyn ExecutionPlan>>,
+}
+
+impl Debug for PairedFastqPartition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PairedFastqPartition")
+            .field("files", &self.plans.len())
+            .finish()
+    }
+}
+
+impl PartitionStream for PairedFastqPartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let streams = self
+            .plans
+            .iter()
+            .map(|plan| {
+                check_single_partition(plan)?;
+                plan.execute(0, ctx.clone())
+            })
+            .collect::<Result<Vec<SendableRecordBatchStream>>>();
+        let reads = streams.map(|mut streams| {
+            let second = (streams.len() > 1).then(|| ReadCursor::new(streams.remove(1)));
+            PairedReads {
+                first: ReadCursor::new(streams.remove(0)),
+                second,
+                carry: None,
+                pairs: 0,
+            }
+        });
+        let stream = futures::stream::unfold(Some(reads), |state| async move {
+            let mut reads = match state? {
+                Ok(reads) => reads,
+                Err(e) => return Some((Err(e), None)),
+            };
+            match reads.next_batch().await {
+                Ok(Some(batch)) => Some((Ok(batch), Some(Ok(reads)))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        });
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), stream))
+    }
+}
+
+/// Registers R1 and R2 FASTQ files, or an interleaved FASTQ file, as a table
+/// of read pairs with `name`, `seq1`, `qual1`, `seq2` and `qual2` columns.
+/// Mates are read in lockstep and scanning fails if their names (ignoring
+/// `/1` and `/2` suffixes) get out of sync.
+pub(crate) async fn register_paired_fastq(
+    ctx: &ExonSession,
+    path: &str,
+    table_name: &str,
+    fastq_read_options: &FastqReadOptions,
+) -> Result<()> {
+    let mut paths = vec![path.to_string()];
+    if let Some(paired_path) = &fastq_read_options.paired_path {
+        paths.push(paired_path.clone());
+    } else if !fastq_read_options.interleaved {
+        return Err(DataFusionError::Plan(
+            "Paired FASTQ tables need an R2 path or an interleaved file".to_string(),
+        ));
+    }
+    info!(
+        "Registering paired FASTQ table {} for {:?}",
+        table_name, paths
+    );
+    let mut plans = Vec::with_capacity(paths.len());
+    for path in paths {
+        let table_provider = FastqTableProvider::new(
+            path,
+            fastq_read_options.thread_num,
+            fastq_read_options.object_storage_options.clone(),
+        )?;
+        let plan = ctx
+            .session
+            .read_table(Arc::new(table_provider))?
+            .create_physical_plan()
+            .await?;
+        check_single_partition(&plan)?;
+        plans.push(plan);
+    }
+    let schema = paired_fastq_schema();
+    let partition = PairedFastqPartition {
+        schema: schema.clone(),
+        plans,
+    };
+    let table_provider = StreamingTable::try_new(schema, vec![Arc::new(partition)])?;
+    ctx.session
+        .register_table(table_name, Arc::new(table_provider))?;
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::array::StringBuilder;
+use arrow_schema::DataType;
+use datafusion::catalog_common::TableReference;
+use datafusion::dataframe::DataFrame;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::scalar::ScalarValue;
+use exon::ExonSession;
+use log::{debug, info};
+
+use crate::quality_control::string_array;
+
+/// Lowest quality character of Solexa encoded reads (Solexa score -5 + 64).
+const SOLEXA_MIN_CHAR: u8 = b';';
+/// Highest quality character expected in Phred+33 encoded reads.
+const PHRED33_MAX_CHAR: u8 = b'K';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PhredEncoding {
+    /// Sanger and Illumina 1.8+, Phred+33.
+    Phred33,
+    /// Illumina 1.3-1.7, Phred+64.
+    Phred64,
+    /// Solexa and Illumina 1.0, Solexa scores +64.
+    Solexa,
+}
+
+impl PhredEncoding {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            PhredEncoding::Phred33 => "phred33",
+            PhredEncoding::Phred64 => "phred64",
+            PhredEncoding::Solexa => "solexa",
+        }
+    }
+
+    pub(crate) fn offset(&self) -> u8 {
+        match self {
+            PhredEncoding::Phred33 => 33,
+            PhredEncoding::Phred64 | PhredEncoding::Solexa => 64,
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "phred33" | "sanger" => Ok(PhredEncoding::Phred33),
+            "phred64" => Ok(PhredEncoding::Phred64),
+            "solexa" => Ok(PhredEncoding::Solexa),
+            _ => Err(DataFusionError::Plan(format!(
+                "Unknown quality encoding {}, expected one of phred33, phred64, solexa",
+                name
+            ))),
+        }
+    }
+
+    /// Guesses the encoding from the lowest and highest quality characters.
+    /// Ranges fitting both Phred+33 and Phred+64 are taken as Phred+33.
+    fn from_range(min: u8, max: u8) -> Result<Self> {
+        if min < 33 {
+            return Err(DataFusionError::Execution(format!(
+                "Quality character {:?} is below the lowest Phred+33 value",
+                min as char
+            )));
+        }
+        if min < SOLEXA_MIN_CHAR || max <= PHRED33_MAX_CHAR {
+            Ok(PhredEncoding::Phred33)
+        } else if min < 64 {
+            Ok(PhredEncoding::Solexa)
+        } else {
+            Ok(PhredEncoding::Phred64)
+        }
+    }
+
+    /// Score of a quality character, converting Solexa scores to Phred.
+    fn phred_score(&self, c: u8) -> f64 {
+        let score = c as f64 - self.offset() as f64;
+        match self {
+            PhredEncoding::Solexa => 10.0 * (10f64.powf(score / 10.0) + 1.0).log10(),
+            _ => score,
+        }
+    }
+
+    fn encode(&self, phred: f64) -> u8 {
+        let score = match self {
+            PhredEncoding::Solexa => 10.0 * (10f64.powf(phred / 10.0) - 1.0).log10(),
+            _ => phred,
+        };
+        let min = match self {
+            PhredEncoding::Solexa => -5.0,
+            _ => 0.0,
+        };
+        (score.max(min).round() + self.offset() as f64).min(126.0) as u8
+    }
+}
+
+/// Detects the quality encoding of `column` from the first `sample_size` rows.
+/// Returns the encoding with the lowest and highest quality characters seen.
+pub(crate) async fn detect_phred_encoding(
+    ctx: &ExonSession,
+    table_name: String,
+    column: String,
+    sample_size: usize,
+) -> Result<(PhredEncoding, char, char)> {
+    let query = format!(
+        "SELECT \"{}\" FROM {} LIMIT {}",
+        column, table_name, sample_size
+    );
+    debug!("Query: {}", query);
+    let batches = ctx.session.sql(&query).await?.collect().await?;
+    let (mut min, mut max) = (u8::MAX, u8::MIN);
+    for batch in batches.iter() {
+        let qualities = string_array(batch.column(0))?;
+        for quality in qualities.iter().flatten() {
+            for c in quality.bytes() {
+                min = min.min(c);
+                max = max.max(c);
+            }
+        }
+    }
+    if min > max {
+        return Err(DataFusionError::Execution(
+            "No quality scores to detect the encoding from".to_string(),
+        ));
+    }
+    let encoding = PhredEncoding::from_range(min, max)?;
+    info!(
+        "Detected {} quality encoding from characters {:?} to {:?}",
+        encoding.name(),
+        min as char,
+        max as char
+    );
+    Ok((encoding, min as char, max as char))
+}
+
+fn encoding_argument(value: &ColumnarValue) -> Result<PhredEncoding> {
+    match value {
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(name))) => PhredEncoding::from_name(name),
+        _ => Err(DataFusionError::Plan(
+            "Quality encodings must be string literals".to_string(),
+        )),
+    }
+}
+
+/// `reencode_quality(quality, source, target)` converts quality strings
+/// between the `phred33`, `phred64` and `solexa` encodings.
+#[derive(Debug)]
+struct ReencodeQualityUdf {
+    signature: Signature,
+}
+
+impl ScalarUDFImpl for ReencodeQualityUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "reencode_quality"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let source = encoding_argument(&args[1])?;
+        let target = encoding_argument(&args[2])?;
+        let qualities = string_array(&args[0].clone().into_array(1)?)?;
+        let mut table = [0u8; 256];
+        for (c, encoded) in table.iter_mut().enumerate() {
+            *encoded = target.encode(source.phred_score(c as u8));
+        }
+        let mut builder = StringBuilder::new();
+        let mut buffer = Vec::new();
+        for quality in qualities.iter() {
+            match quality {
+                Some(quality) => {
+                    buffer.clear();
+                    buffer.extend(quality.bytes().map(|c| table[c as usize]));
+                    builder.append_value(String::from_utf8_lossy(&buffer));
+                },
+                None => builder.append_null(),
+            }
+        }
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+pub(crate) fn register_phred_udfs(ctx: &ExonSession) {
+    ctx.session
+        .register_udf(ScalarUDF::from(ReencodeQualityUdf {
+            signature: Signature::exact(
+                vec![DataType::Utf8, DataType::Utf8, DataType::Utf8],
+                Volatility::Immutable,
+            ),
+        }));
+}
+
+/// Returns the table with `column` re-encoded from `source` to `target`.
+pub(crate) async fn do_reencode_quality(
+    ctx: &ExonSession,
+    table_name: String,
+    column: String,
+    source: String,
+    target: String,
+) -> Result<DataFrame> {
+    PhredEncoding::from_name(&source)?;
+    PhredEncoding::from_name(&target)?;
+    let schema = ctx
+        .session
+        .table(TableReference::from(table_name.clone()))
+        .await?
+        .schema()
+        .as_arrow()
+        .clone();
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|f| {
+            if *f.name() == column {
+                format!(
+                    "reencode_quality(\"{}\", '{}', '{}') AS \"{}\"",
+                    column, source, target, column
+                )
+            } else {
+                format!("\"{}\"", f.name())
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    let query = format!("SELECT {} FROM {}", columns, table_name);
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
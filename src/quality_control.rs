@@ -1,6 +1,11 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
 
-use arrow::array::{ArrayBuilder, ListBuilder, StructBuilder, UInt64Builder};
+use arrow::array::{
+    ArrayBuilder, Float64Builder, ListBuilder, StringBuilder, StructBuilder, UInt64Builder,
+};
+use arrow::compute::cast;
 use arrow::datatypes::UInt64Type;
 use arrow_array::{Array, ArrayRef, ListArray, StringArray, UInt32Array, UInt64Array};
 use arrow_schema::{DataType, Field};
@@ -10,147 +15,104 @@ use datafusion::logical_expr::{create_udaf, Volatility};
 use datafusion::physical_plan::Accumulator;
 use datafusion::scalar::ScalarValue;
 use exon::ExonSession;
+use fnv::FnvHashMap;
+use log::debug;
 
-use std::fs::OpenOptions;
-use std::io::Write;
+/// Base categories counted by `base_sequence_content`, in output order.
+const BASE_CATEGORIES: [&str; 6] = ["a", "c", "g", "t", "n", "other"];
+const OTHER_BASE: u8 = 5;
 
+/// Maps each byte to its index in `BASE_CATEGORIES`. Bases are matched case
+/// insensitively; IUPAC ambiguity codes and any other byte count as other.
+const BASE_LOOKUP: [u8; 256] = {
+    let mut table = [OTHER_BASE; 256];
+    table[b'A' as usize] = 0;
+    table[b'a' as usize] = 0;
+    table[b'C' as usize] = 1;
+    table[b'c' as usize] = 1;
+    table[b'G' as usize] = 2;
+    table[b'g' as usize] = 2;
+    table[b'T' as usize] = 3;
+    table[b't' as usize] = 3;
+    table[b'N' as usize] = 4;
+    table[b'n' as usize] = 4;
+    table
+};
+
+/// Per-position base counts, flattened with one counter per category and
+/// position.
 #[derive(Debug)]
 struct BaseSequenceContent {
-    a_counts: Vec<Option<u64>>,
-    c_counts: Vec<Option<u64>>,
-    t_counts: Vec<Option<u64>>,
-    g_counts: Vec<Option<u64>>,
-    n_counts: Vec<Option<u64>>,
+    counts: Vec<u64>,
     max_position_seen: usize,
 }
 
 impl BaseSequenceContent {
     pub fn new() -> Self {
         BaseSequenceContent {
-            a_counts: Vec::new(),
-            c_counts: Vec::new(),
-            t_counts: Vec::new(),
-            g_counts: Vec::new(),
-            n_counts: Vec::new(),
+            counts: Vec::new(),
             max_position_seen: 0,
         }
     }
 
     fn ensure_capacity(&mut self, desired_len: usize) {
         if desired_len > self.max_position_seen {
-            self.a_counts.resize(desired_len, Some(0));
-            self.c_counts.resize(desired_len, Some(0));
-            self.g_counts.resize(desired_len, Some(0));
-            self.t_counts.resize(desired_len, Some(0));
-            self.n_counts.resize(desired_len, Some(0));
+            self.counts.resize(desired_len * BASE_CATEGORIES.len(), 0);
             self.max_position_seen = desired_len;
         }
     }
 }
 
+fn base_sequence_content_fields() -> Vec<Field> {
+    BASE_CATEGORIES
+        .iter()
+        .map(|b| Field::new(format!("{}_count", b), DataType::UInt64, false))
+        .chain(std::iter::once(Field::new(
+            "position",
+            DataType::UInt64,
+            false,
+        )))
+        .collect()
+}
+
 impl Accumulator for BaseSequenceContent {
     fn state(&mut self) -> Result<Vec<ScalarValue>> {
-        let a_counts_list = ScalarValue::List(Arc::new(
-            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
-                self.a_counts.clone(),
-            )]),
-        ));
-        let c_counts_list = ScalarValue::List(Arc::new(
-            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
-                self.c_counts.clone(),
-            )]),
-        ));
-        let g_counts_list = ScalarValue::List(Arc::new(
-            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
-                self.g_counts.clone(),
-            )]),
-        ));
-        let t_counts_list = ScalarValue::List(Arc::new(
-            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
-                self.t_counts.clone(),
-            )]),
-        ));
-        let n_counts_list = ScalarValue::List(Arc::new(
+        let counts_list = ScalarValue::List(Arc::new(
             ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
-                self.n_counts.clone(),
+                self.counts.iter().map(|c| Some(*c)).collect::<Vec<_>>(),
             )]),
         ));
-        let max_position_seen = ScalarValue::from(self.max_position_seen as u64);
-
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open("/home/sh4dqw/dev/studies/Polars-UDF/rust_logs.txt") {
-            writeln!(file, "[STATE] Returning serialized state (A): {}", a_counts_list.data_type());
-            writeln!(file, "[STATE] Returning serialized state (C): {}", c_counts_list.data_type());
-            writeln!(file, "[STATE] Returning serialized state (G): {}", g_counts_list.data_type());
-            writeln!(file, "[STATE] Returning serialized state (T): {}", t_counts_list.data_type());
-            writeln!(file, "[STATE] Returning serialized state (N): {}", n_counts_list.data_type());
-            writeln!(file, "[STATE] Returning serialized state (max_position_seen): {}", max_position_seen.data_type());
-        }
-        
-        let state = vec![
-            a_counts_list,
-            c_counts_list,
-            g_counts_list,
-            t_counts_list,
-            n_counts_list,
-            max_position_seen
-        ];
-        
-        Ok(state)
+        Ok(vec![
+            counts_list,
+            ScalarValue::from(self.max_position_seen as u64),
+        ])
     }
 
     fn evaluate(&mut self) -> Result<ScalarValue> {
-        let struct_fields = vec![
-            Arc::new(Field::new("a_count", DataType::UInt64, false)),
-            Arc::new(Field::new("c_count", DataType::UInt64, false)),
-            Arc::new(Field::new("g_count", DataType::UInt64, false)),
-            Arc::new(Field::new("t_count", DataType::UInt64, false)),
-            Arc::new(Field::new("n_count", DataType::UInt64, false)),
-        ];
-        let struct_builders: Vec<Box<dyn ArrayBuilder>> = vec![
-            Box::new(UInt64Builder::new()),
-            Box::new(UInt64Builder::new()),
-            Box::new(UInt64Builder::new()),
-            Box::new(UInt64Builder::new()),
-            Box::new(UInt64Builder::new()),
-        ];
-        let struct_builder = StructBuilder::new(struct_fields, struct_builders);
-        let mut list_builder = ListBuilder::new(struct_builder);
+        let struct_fields = base_sequence_content_fields()
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+        let struct_builders: Vec<Box<dyn ArrayBuilder>> = (0..struct_fields.len())
+            .map(|_| Box::new(UInt64Builder::new()) as Box<dyn ArrayBuilder>)
+            .collect();
+        let mut list_builder = ListBuilder::new(StructBuilder::new(struct_fields, struct_builders));
 
-        for i in 0..self.max_position_seen {
-            let a_count = self.a_counts.get(i).copied().unwrap().unwrap_or(0);
-            let c_count = self.c_counts.get(i).copied().unwrap().unwrap_or(0);
-            let g_count = self.g_counts.get(i).copied().unwrap().unwrap_or(0);
-            let t_count = self.t_counts.get(i).copied().unwrap().unwrap_or(0);
-            let n_count = self.n_counts.get(i).copied().unwrap().unwrap_or(0);
-
-            let struct_builder_ref = list_builder
-                .values()
-                .as_any_mut()
-                .downcast_mut::<StructBuilder>()
-                .unwrap();
-            struct_builder_ref
-                .field_builder::<UInt64Builder>(0)
-                .unwrap()
-                .append_value(a_count);
-            struct_builder_ref
-                .field_builder::<UInt64Builder>(1)
-                .unwrap()
-                .append_value(c_count);
-            struct_builder_ref
-                .field_builder::<UInt64Builder>(2)
-                .unwrap()
-                .append_value(g_count);
-            struct_builder_ref
-                .field_builder::<UInt64Builder>(3)
-                .unwrap()
-                .append_value(t_count);
+        let struct_builder_ref = list_builder.values();
+        for (pos, counts) in self.counts.chunks_exact(BASE_CATEGORIES.len()).enumerate() {
+            for (i, count) in counts.iter().enumerate() {
+                struct_builder_ref
+                    .field_builder::<UInt64Builder>(i)
+                    .unwrap()
+                    .append_value(*count);
+            }
             struct_builder_ref
-                .field_builder::<UInt64Builder>(4)
+                .field_builder::<UInt64Builder>(BASE_CATEGORIES.len())
                 .unwrap()
-                .append_value(n_count);
+                .append_value(pos as u64 + 1);
             struct_builder_ref.append(true);
-            list_builder.append(true);
         }
+        list_builder.append(true);
 
         Ok(ScalarValue::List(Arc::new(list_builder.finish())))
     }
@@ -159,59 +121,23 @@ impl Accumulator for BaseSequenceContent {
         if values.is_empty() {
             return Ok(());
         }
-
-        let sequences = values[0]
-            .as_any()
-            .downcast_ref::<StringArray>()
-            .ok_or_else(|| {
-                DataFusionError::Internal("Argument must be string array".to_string())
-            })?;
-
-        
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open("/home/sh4dqw/dev/studies/Polars-UDF/rust_logs.txt") {
-            writeln!(file, "[UPDATE_BATCH] Start processing batch. Num values: {}. Current max_pos: {}", values[0].len(), self.max_position_seen);
-        }
-
-        for i in 0..sequences.len() {
-            if !sequences.is_null(i) {
-                let seq = sequences.value(i);
-                let current_seq_len = seq.len();
-                self.ensure_capacity(current_seq_len);
-
-                for (pos, base_char) in seq.chars().enumerate() {
-                    if pos < self.max_position_seen {
-                        match base_char.to_ascii_uppercase() {
-                            'A' => {
-                                self.a_counts[pos] =
-                                    self.a_counts[pos].map_or(Some(1), |val| Some(val + 1))
-                            },
-                            'C' => {
-                                self.c_counts[pos] =
-                                    self.c_counts[pos].map_or(Some(1), |val| Some(val + 1))
-                            },
-                            'G' => {
-                                self.g_counts[pos] =
-                                    self.g_counts[pos].map_or(Some(1), |val| Some(val + 1))
-                            },
-                            'T' => {
-                                self.t_counts[pos] =
-                                    self.t_counts[pos].map_or(Some(1), |val| Some(val + 1))
-                            },
-                            'N' => {
-                                self.n_counts[pos] =
-                                    self.n_counts[pos].map_or(Some(1), |val| Some(val + 1))
-                            },
-                            _ => {},
-                        }
-                    }
-                }
+        let sequences = string_array(&values[0])?;
+        debug!(
+            "base_sequence_content: updating with {} sequences, max position {}",
+            sequences.len(),
+            self.max_position_seen
+        );
+        for sequence in sequences.iter().flatten() {
+            let bytes = sequence.as_bytes();
+            self.ensure_capacity(bytes.len());
+            for (counts, base) in self
+                .counts
+                .chunks_exact_mut(BASE_CATEGORIES.len())
+                .zip(bytes)
+            {
+                counts[BASE_LOOKUP[*base as usize] as usize] += 1;
             }
         }
-
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open("/home/sh4dqw/dev/studies/Polars-UDF/rust_logs.txt") {
-            writeln!(file, "[UPDATE_BATCH] Finished processing batch. Num values: {}. Current max_pos: {}", values[0].len(), self.max_position_seen);
-        }
-
         Ok(())
     }
 
@@ -219,144 +145,325 @@ impl Accumulator for BaseSequenceContent {
         if states.is_empty() {
             return Ok(());
         }
-
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open("/home/sh4dqw/dev/studies/Polars-UDF/rust_logs.txt") {
-            writeln!(file, "[MERGE_BATCH] Started merging batch. Num A counts: {}. Current max_pos: {}", states[0].len(), self.max_position_seen);
-            writeln!(file, "[MERGE_BATCH] Started merging batch. Num C counts: {}. Current max_pos: {}", states[1].len(), self.max_position_seen);
-            writeln!(file, "[MERGE_BATCH] Started merging batch. Num G counts: {}. Current max_pos: {}", states[2].len(), self.max_position_seen);
-            writeln!(file, "[MERGE_BATCH] Started merging batch. Num T counts: {}. Current max_pos: {}", states[3].len(), self.max_position_seen);
-            writeln!(file, "[MERGE_BATCH] Started merging batch. Num N counts: {}. Current max_pos: {}", states[4].len(), self.max_position_seen);
-        }
-        
-        let a_counts_array = states[0]
+        let counts_array = states[0]
             .as_any()
             .downcast_ref::<ListArray>()
             .ok_or_else(|| {
-                DataFusionError::Internal("First column must be list array of A counts".to_string())
+                DataFusionError::Internal("First column must be list array of counts".to_string())
             })?;
-        let c_counts_array = states[1]
+        let max_pos_array = states[1]
             .as_any()
-            .downcast_ref::<ListArray>()
+            .downcast_ref::<UInt64Array>()
             .ok_or_else(|| {
                 DataFusionError::Internal(
-                    "Second column must be list array of C counts".to_string(),
+                    "Second column must be u64 array of max positions".to_string(),
                 )
             })?;
-        let g_counts_array = states[2]
-            .as_any()
-            .downcast_ref::<ListArray>()
-            .ok_or_else(|| {
-                DataFusionError::Internal("Third column must be list array of G counts".to_string())
-            })?;
-        let t_counts_array = states[3]
+        debug!(
+            "base_sequence_content: merging {} states, max position {}",
+            counts_array.len(),
+            self.max_position_seen
+        );
+
+        for i in 0..counts_array.len() {
+            let counts_array_ref = counts_array.value(i);
+            let other_counts = counts_array_ref
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap();
+            self.ensure_capacity(max_pos_array.value(i) as usize);
+            for (count, other) in self.counts.iter_mut().zip(other_counts.values().iter()) {
+                *count += other;
+            }
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.counts.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+pub(crate) fn register_base_sequence_content(ctx: &ExonSession) {
+    let udaf = create_udaf(
+        "base_sequence_content",
+        vec![DataType::Utf8],
+        Arc::new(DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::Struct(base_sequence_content_fields().into()),
+            true,
+        )))),
+        Volatility::Immutable,
+        Arc::new(|_| Ok(Box::new(BaseSequenceContent::new()))),
+        Arc::new(vec![
+            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
+            DataType::UInt64,
+        ]),
+    );
+    ctx.session.register_udaf(udaf);
+}
+
+/// Per-position base counts of `sequence_column`, computed separately for each
+/// combination of the `group_by` columns, optionally with the percentage of
+/// each category. Grouped results also report the (1-based) position.
+pub(crate) async fn do_base_sequence_content(
+    ctx: &ExonSession,
+    table_name: String,
+    sequence_column: String,
+    group_by: Vec<String>,
+    percentages: bool,
+) -> DataFrame {
+    let groups = group_by
+        .iter()
+        .map(|c| format!("\"{}\"", c))
+        .collect::<Vec<_>>();
+    let (group_select, group_clause, order) = if groups.is_empty() {
+        (String::new(), String::new(), String::new())
+    } else {
+        let columns = groups.join(", ");
+        (
+            format!("{}, ", columns),
+            format!("GROUP BY {}", columns),
+            format!("ORDER BY {}, position", columns),
+        )
+    };
+    let mut columns = BASE_CATEGORIES
+        .iter()
+        .map(|b| format!("my_struct.{b}_count AS {b}_count"))
+        .collect::<Vec<_>>();
+    if percentages {
+        let total = BASE_CATEGORIES
+            .iter()
+            .map(|b| format!("my_struct.{b}_count"))
+            .collect::<Vec<_>>()
+            .join(" + ");
+        columns.extend(BASE_CATEGORIES.iter().map(|b| {
+            format!("CAST(my_struct.{b}_count AS DOUBLE) * 100.0 / ({total}) AS {b}_percent")
+        }));
+    }
+    if !groups.is_empty() {
+        columns.push("my_struct.position AS position".to_string());
+    }
+    let query = format!(
+        r#"
+        WITH content AS (
+            SELECT {group_select}base_sequence_content("{sequence_column}") AS positions
+            FROM {table_name}
+            {group_clause}
+        ),
+        struct_column AS (
+            SELECT {group_select}unnest(positions) AS my_struct
+            FROM content
+        )
+        SELECT
+            {group_select}{columns}
+        FROM struct_column
+        {order};
+        "#,
+        columns = columns.join(",\n            "),
+    );
+    debug!("Query: {}", query);
+
+    ctx.sql(&query).await.unwrap()
+}
+
+/// Number of Phred score bins, i.e. the printable ASCII range above the Phred+33 offset.
+const PHRED_BINS: usize = 94;
+
+pub(crate) fn string_array(array: &ArrayRef) -> Result<StringArray> {
+    let array = cast(array, &DataType::Utf8)?;
+    array
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .cloned()
+        .ok_or_else(|| DataFusionError::Internal("Argument must be string array".to_string()))
+}
+
+/// Value at the given percentile (nearest-rank method) of a Phred score histogram.
+fn histogram_percentile(histogram: &[u64], total: u64, percentile: f64) -> f64 {
+    let rank = ((percentile * total as f64).ceil() as u64).max(1);
+    let mut cumulative = 0;
+    for (score, count) in histogram.iter().enumerate() {
+        cumulative += count;
+        if cumulative >= rank {
+            return score as f64;
+        }
+    }
+    0.0
+}
+
+/// Per-position Phred score histograms, flattened with `PHRED_BINS` bins per position.
+#[derive(Debug)]
+struct BaseSequenceQuality {
+    histograms: Vec<u64>,
+    max_position_seen: usize,
+    phred_offset: u8,
+}
+
+impl BaseSequenceQuality {
+    pub fn new(phred_offset: u8) -> Self {
+        BaseSequenceQuality {
+            histograms: Vec::new(),
+            max_position_seen: 0,
+            phred_offset,
+        }
+    }
+
+    fn ensure_capacity(&mut self, desired_len: usize) {
+        if desired_len > self.max_position_seen {
+            self.histograms.resize(desired_len * PHRED_BINS, 0);
+            self.max_position_seen = desired_len;
+        }
+    }
+}
+
+fn base_sequence_quality_fields() -> Vec<Field> {
+    vec![
+        Field::new("position", DataType::UInt64, false),
+        Field::new("mean", DataType::Float64, false),
+        Field::new("median", DataType::Float64, false),
+        Field::new("lower_quartile", DataType::Float64, false),
+        Field::new("upper_quartile", DataType::Float64, false),
+        Field::new("percentile_10", DataType::Float64, false),
+        Field::new("percentile_90", DataType::Float64, false),
+    ]
+}
+
+impl Accumulator for BaseSequenceQuality {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        let histograms_list = ScalarValue::List(Arc::new(
+            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
+                self.histograms.iter().map(|c| Some(*c)).collect::<Vec<_>>(),
+            )]),
+        ));
+        let max_position_seen = ScalarValue::from(self.max_position_seen as u64);
+        Ok(vec![histograms_list, max_position_seen])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let struct_fields = base_sequence_quality_fields()
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+        let mut struct_builders: Vec<Box<dyn ArrayBuilder>> =
+            vec![Box::new(UInt64Builder::new())];
+        for _ in 1..struct_fields.len() {
+            struct_builders.push(Box::new(Float64Builder::new()));
+        }
+        let struct_builder = StructBuilder::new(struct_fields, struct_builders);
+        let mut list_builder = ListBuilder::new(struct_builder);
+
+        let struct_builder_ref = list_builder.values();
+        for pos in 0..self.max_position_seen {
+            let histogram = &self.histograms[pos * PHRED_BINS..(pos + 1) * PHRED_BINS];
+            let total: u64 = histogram.iter().sum();
+            let sum: u64 = histogram
+                .iter()
+                .enumerate()
+                .map(|(score, count)| score as u64 * count)
+                .sum();
+            let mean = if total > 0 {
+                sum as f64 / total as f64
+            } else {
+                0.0
+            };
+            struct_builder_ref
+                .field_builder::<UInt64Builder>(0)
+                .unwrap()
+                .append_value(pos as u64 + 1);
+            let values = [
+                mean,
+                histogram_percentile(histogram, total, 0.5),
+                histogram_percentile(histogram, total, 0.25),
+                histogram_percentile(histogram, total, 0.75),
+                histogram_percentile(histogram, total, 0.1),
+                histogram_percentile(histogram, total, 0.9),
+            ];
+            for (i, value) in values.into_iter().enumerate() {
+                struct_builder_ref
+                    .field_builder::<Float64Builder>(i + 1)
+                    .unwrap()
+                    .append_value(value);
+            }
+            struct_builder_ref.append(true);
+        }
+        list_builder.append(true);
+
+        Ok(ScalarValue::List(Arc::new(list_builder.finish())))
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        let qualities = string_array(&values[0])?;
+        for quality in qualities.iter().flatten() {
+            self.ensure_capacity(quality.len());
+            for (pos, score) in quality.bytes().enumerate() {
+                let score = score.saturating_sub(self.phred_offset) as usize;
+                self.histograms[pos * PHRED_BINS + score.min(PHRED_BINS - 1)] += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        if states.is_empty() {
+            return Ok(());
+        }
+        let histograms_array = states[0]
             .as_any()
             .downcast_ref::<ListArray>()
             .ok_or_else(|| {
                 DataFusionError::Internal(
-                    "Fourth column must be list array of T counts".to_string(),
+                    "First column must be list array of histograms".to_string(),
                 )
             })?;
-        let n_counts_array = states[4]
-            .as_any()
-            .downcast_ref::<ListArray>()
-            .ok_or_else(|| {
-                DataFusionError::Internal("Fifth column must be list array of N counts".to_string())
-            })?;
-        let max_pos_array = states[5]
+        let max_pos_array = states[1]
             .as_any()
             .downcast_ref::<UInt64Array>()
             .ok_or_else(|| {
                 DataFusionError::Internal(
-                    "Sixth column must be u64 array of max positions".to_string(),
+                    "Second column must be u64 array of max positions".to_string(),
                 )
             })?;
 
-        for i in 0..a_counts_array.len() {
-            let a_counts_array_ref = a_counts_array.value(i);
-            let other_a_counts = a_counts_array_ref
-                .as_any()
-                .downcast_ref::<UInt64Array>()
-                .unwrap();
-            let c_counts_array_ref = c_counts_array.value(i);
-            let other_c_counts = c_counts_array_ref
-                .as_any()
-                .downcast_ref::<UInt64Array>()
-                .unwrap();
-            let g_counts_array_ref = g_counts_array.value(i);
-            let other_g_counts = g_counts_array_ref
-                .as_any()
-                .downcast_ref::<UInt64Array>()
-                .unwrap();
-            let t_counts_array_ref = t_counts_array.value(i);
-            let other_t_counts = t_counts_array_ref
-                .as_any()
-                .downcast_ref::<UInt64Array>()
-                .unwrap();
-            let n_counts_array_ref = n_counts_array.value(i);
-            let other_n_counts = n_counts_array_ref
+        for i in 0..histograms_array.len() {
+            let histograms_array_ref = histograms_array.value(i);
+            let other_histograms = histograms_array_ref
                 .as_any()
                 .downcast_ref::<UInt64Array>()
                 .unwrap();
             let other_max_pos = max_pos_array.value(i) as usize;
-
             self.ensure_capacity(other_max_pos);
-
-            for pos in 0..other_max_pos {
-                self.a_counts[pos] =
-                    self.a_counts[pos].map_or(Some(1), |val| Some(val + other_a_counts.value(pos)));
-                self.c_counts[pos] =
-                    self.c_counts[pos].map_or(Some(1), |val| Some(val + other_c_counts.value(pos)));
-                self.g_counts[pos] =
-                    self.g_counts[pos].map_or(Some(1), |val| Some(val + other_g_counts.value(pos)));
-                self.t_counts[pos] =
-                    self.t_counts[pos].map_or(Some(1), |val| Some(val + other_t_counts.value(pos)));
-                self.n_counts[pos] =
-                    self.n_counts[pos].map_or(Some(1), |val| Some(val + other_n_counts.value(pos)));
+            for (count, other) in self
+                .histograms
+                .iter_mut()
+                .zip(other_histograms.values().iter())
+            {
+                *count += other;
             }
         }
-
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open("/home/sh4dqw/dev/studies/Polars-UDF/rust_logs.txt") {
-            writeln!(file, "[MERGE_BATCH] Stopped merging batch. Num values: {}. Current max_pos: {}", states[0].len(), self.max_position_seen);
-        }
-        
         Ok(())
     }
 
     fn size(&self) -> usize {
-        std::mem::size_of_val(self)
-            + self.a_counts.capacity() * std::mem::size_of::<u64>()
-            + self.c_counts.capacity() * std::mem::size_of::<u64>()
-            + self.g_counts.capacity() * std::mem::size_of::<u64>()
-            + self.t_counts.capacity() * std::mem::size_of::<u64>()
-            + self.n_counts.capacity() * std::mem::size_of::<u64>()
+        std::mem::size_of_val(self) + self.histograms.capacity() * std::mem::size_of::<u64>()
     }
 }
 
-pub(crate) fn register_base_sequence_content(ctx: &ExonSession) {
+pub(crate) fn register_base_sequence_quality(ctx: &ExonSession, phred_offset: u8) {
     let udaf = create_udaf(
-        "base_sequence_content",
+        "base_sequence_quality",
         vec![DataType::Utf8],
         Arc::new(DataType::List(Arc::new(Field::new(
             "item",
-            DataType::Struct(
-                vec![
-                    Field::new("a_count", DataType::UInt64, false),
-                    Field::new("c_count", DataType::UInt64, false),
-                    Field::new("g_count", DataType::UInt64, false),
-                    Field::new("t_count", DataType::UInt64, false),
-                    Field::new("n_count", DataType::UInt64, false),
-                ]
-                .into(),
-            ),
+            DataType::Struct(base_sequence_quality_fields().into()),
             true,
         )))),
         Volatility::Immutable,
-        Arc::new(|_| Ok(Box::new(BaseSequenceContent::new()))),
+        Arc::new(move |_| Ok(Box::new(BaseSequenceQuality::new(phred_offset)))),
         Arc::new(vec![
-            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
-            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
-            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
-            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
             DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
             DataType::UInt64,
         ]),
@@ -364,24 +471,1080 @@ pub(crate) fn register_base_sequence_content(ctx: &ExonSession) {
     ctx.session.register_udaf(udaf);
 }
 
-pub(crate) async fn do_base_sequence_content(ctx: &ExonSession, table_name: String) -> DataFrame {
+pub(crate) async fn do_base_sequence_quality(ctx: &ExonSession, table_name: String) -> DataFrame {
     let query = format!(
         r#"
-        WITH struct_column AS (
-            SELECT 
-                array_any_value(base_sequence_content(sequence)) AS my_struct
+        WITH stats AS (
+            SELECT base_sequence_quality(quality_scores) AS positions
             FROM {}
+        ),
+        struct_column AS (
+            SELECT unnest(positions) AS my_struct
+            FROM stats
         )
         SELECT
-            my_struct.a_count AS A_count,
-            my_struct.c_count AS C_count,
-            my_struct.g_count AS G_count,
-            my_struct.t_count AS T_count,
-            my_struct.n_count AS N_count
-        FROM struct_column;
+            my_struct['position'] AS position,
+            my_struct['mean'] AS mean,
+            my_struct['median'] AS median,
+            my_struct['lower_quartile'] AS lower_quartile,
+            my_struct['upper_quartile'] AS upper_quartile,
+            my_struct['percentile_10'] AS percentile_10,
+            my_struct['percentile_90'] AS percentile_90
+        FROM struct_column
+        ORDER BY position;
         "#,
         table_name
     );
 
     ctx.sql(&query).await.unwrap()
 }
+
+type ValueFn = Arc<dyn Fn(&str) -> Option<u64> + Send + Sync>;
+
+/// Number of rows per integer value computed from a single string column,
+/// e.g. the mean quality of each read. Merged as (value, count) lists.
+struct ValueHistogram {
+    counts: BTreeMap<u64, u64>,
+    value: ValueFn,
+    value_field: &'static str,
+}
+
+impl Debug for ValueHistogram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValueHistogram")
+            .field("value_field", &self.value_field)
+            .field("counts", &self.counts)
+            .finish()
+    }
+}
+
+fn value_histogram_fields(value_field: &str) -> Vec<Field> {
+    vec![
+        Field::new(value_field, DataType::UInt64, false),
+        Field::new("count", DataType::UInt64, false),
+    ]
+}
+
+impl Accumulator for ValueHistogram {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        let values_list = ScalarValue::List(Arc::new(
+            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
+                self.counts.keys().map(|v| Some(*v)).collect::<Vec<_>>(),
+            )]),
+        ));
+        let counts_list = ScalarValue::List(Arc::new(
+            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
+                self.counts.values().map(|c| Some(*c)).collect::<Vec<_>>(),
+            )]),
+        ));
+        Ok(vec![values_list, counts_list])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let struct_fields = value_histogram_fields(self.value_field)
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+        let struct_builders: Vec<Box<dyn ArrayBuilder>> = vec![
+            Box::new(UInt64Builder::new()),
+            Box::new(UInt64Builder::new()),
+        ];
+        let struct_builder = StructBuilder::new(struct_fields, struct_builders);
+        let mut list_builder = ListBuilder::new(struct_builder);
+
+        let struct_builder_ref = list_builder.values();
+        for (value, count) in self.counts.iter() {
+            struct_builder_ref
+                .field_builder::<UInt64Builder>(0)
+                .unwrap()
+                .append_value(*value);
+            struct_builder_ref
+                .field_builder::<UInt64Builder>(1)
+                .unwrap()
+                .append_value(*count);
+            struct_builder_ref.append(true);
+        }
+        list_builder.append(true);
+
+        Ok(ScalarValue::List(Arc::new(list_builder.finish())))
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        let column = string_array(&values[0])?;
+        for value in column.iter().flatten().filter_map(|v| (self.value)(v)) {
+            *self.counts.entry(value).or_insert(0) += 1;
+        }
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        if states.is_empty() {
+            return Ok(());
+        }
+        let values_array = states[0]
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("First column must be list array of values".to_string())
+            })?;
+        let counts_array = states[1]
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("Second column must be list array of counts".to_string())
+            })?;
+
+        for i in 0..values_array.len() {
+            let values_array_ref = values_array.value(i);
+            let other_values = values_array_ref
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap();
+            let counts_array_ref = counts_array.value(i);
+            let other_counts = counts_array_ref
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap();
+            for (value, count) in other_values.values().iter().zip(other_counts.values()) {
+                *self.counts.entry(*value).or_insert(0) += count;
+            }
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.counts.len() * 2 * std::mem::size_of::<u64>()
+    }
+}
+
+fn register_value_histogram(
+    ctx: &ExonSession,
+    name: &str,
+    value_field: &'static str,
+    value: ValueFn,
+) {
+    let udaf = create_udaf(
+        name,
+        vec![DataType::Utf8],
+        Arc::new(DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::Struct(value_histogram_fields(value_field).into()),
+            true,
+        )))),
+        Volatility::Immutable,
+        Arc::new(move |_| {
+            Ok(Box::new(ValueHistogram {
+                counts: BTreeMap::new(),
+                value: Arc::clone(&value),
+                value_field,
+            }))
+        }),
+        Arc::new(vec![
+            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
+            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
+        ]),
+    );
+    ctx.session.register_udaf(udaf);
+}
+
+/// Mean Phred score of a read, rounded down as in FastQC.
+fn mean_quality(quality: &str, phred_offset: u8) -> Option<u64> {
+    if quality.is_empty() {
+        return None;
+    }
+    let total: u64 = quality
+        .bytes()
+        .map(|q| q.saturating_sub(phred_offset) as u64)
+        .sum();
+    Some(total / quality.len() as u64)
+}
+
+pub(crate) fn register_per_sequence_quality(ctx: &ExonSession, phred_offset: u8) {
+    register_value_histogram(
+        ctx,
+        "per_sequence_quality",
+        "quality",
+        Arc::new(move |quality: &str| mean_quality(quality, phred_offset)),
+    );
+}
+
+pub(crate) async fn do_per_sequence_quality(ctx: &ExonSession, table_name: String) -> DataFrame {
+    let query = format!(
+        r#"
+        WITH histogram AS (
+            SELECT per_sequence_quality(quality_scores) AS counts
+            FROM {}
+        ),
+        struct_column AS (
+            SELECT unnest(counts) AS my_struct
+            FROM histogram
+        )
+        SELECT
+            my_struct['quality'] AS quality,
+            my_struct['count'] AS count
+        FROM struct_column
+        ORDER BY quality;
+        "#,
+        table_name
+    );
+
+    ctx.sql(&query).await.unwrap()
+}
+
+/// GC percentage of a read, rounded to the nearest integer. Bases other than
+/// A, C, G and T are not counted.
+fn gc_percent(sequence: &str) -> Option<u64> {
+    let (gc, acgt) = sequence.bytes().fold((0u64, 0u64), |(gc, acgt), b| {
+        match b.to_ascii_uppercase() {
+            b'G' | b'C' => (gc + 1, acgt + 1),
+            b'A' | b'T' => (gc, acgt + 1),
+            _ => (gc, acgt),
+        }
+    });
+    if acgt == 0 {
+        return None;
+    }
+    Some((gc * 200 + acgt) / (2 * acgt))
+}
+
+pub(crate) fn register_per_sequence_gc_content(ctx: &ExonSession) {
+    register_value_histogram(
+        ctx,
+        "per_sequence_gc_content",
+        "gc_content",
+        Arc::new(gc_percent),
+    );
+}
+
+/// Query fitting the normal distribution of FastQC to a GC histogram. `ctes`
+/// must define an `observed` CTE with `gc_content` and `count` columns; the
+/// mode is the mean of the GC values whose count is within 90% of the
+/// maximum, and the standard deviation is measured around the mode.
+fn gc_content_fit_query(ctes: &str) -> String {
+    format!(
+        r#"
+        WITH {ctes},
+        mode AS (
+            SELECT AVG(CAST(gc_content AS DOUBLE)) AS mode
+            FROM observed
+            WHERE count >= 0.9 * (SELECT MAX(count) FROM observed)
+        ),
+        fit AS (
+            SELECT
+                mode.mode AS mode,
+                CAST(SUM(count) AS DOUBLE) AS total,
+                sqrt(
+                    SUM(count * power(gc_content - mode.mode, 2))
+                    / GREATEST(CAST(SUM(count) AS DOUBLE) - 1, 1)
+                ) AS sd
+            FROM observed CROSS JOIN mode
+            GROUP BY mode.mode
+        ),
+        gc_values AS (
+            SELECT CAST(unnest(range(0, 101)) AS BIGINT UNSIGNED) AS gc_content
+        )
+        SELECT
+            gc_values.gc_content AS gc_content,
+            CAST(COALESCE(observed.count, 0) AS BIGINT UNSIGNED) AS count,
+            CASE
+                WHEN fit.sd > 0 THEN fit.total
+                    * exp(-power(gc_values.gc_content - fit.mode, 2) / (2 * power(fit.sd, 2)))
+                    / (fit.sd * sqrt(2 * pi()))
+                WHEN gc_values.gc_content = round(fit.mode) THEN fit.total
+                ELSE 0.0
+            END AS theoretical_count
+        FROM gc_values
+        CROSS JOIN fit
+        LEFT JOIN observed ON gc_values.gc_content = observed.gc_content
+        ORDER BY gc_content;
+        "#,
+    )
+}
+
+/// Distribution of per-read GC percentage over 0..=100 with the normal
+/// distribution fitted as in FastQC, see `gc_content_fit_query`.
+pub(crate) async fn do_per_sequence_gc_content(ctx: &ExonSession, table_name: String) -> DataFrame {
+    let query = gc_content_fit_query(&format!(
+        r#"
+        histogram AS (
+            SELECT per_sequence_gc_content(sequence) AS counts
+            FROM {}
+        ),
+        struct_column AS (
+            SELECT unnest(counts) AS my_struct
+            FROM histogram
+        ),
+        observed AS (
+            SELECT
+                my_struct['gc_content'] AS gc_content,
+                my_struct['count'] AS count
+            FROM struct_column
+        )"#,
+        table_name
+    ));
+
+    ctx.sql(&query).await.unwrap()
+}
+
+/// Fits the normal distribution of `do_per_sequence_gc_content` to an
+/// already computed GC histogram with `gc_content` and `count` columns, e.g.
+/// the one of the QC report.
+pub(crate) async fn do_gc_content_fit(ctx: &ExonSession, table_name: String) -> Result<DataFrame> {
+    let query = gc_content_fit_query(&format!(
+        r#"
+        observed AS (
+            SELECT
+                CAST(gc_content AS BIGINT UNSIGNED) AS gc_content,
+                CAST(count AS BIGINT UNSIGNED) AS count
+            FROM {}
+        )"#,
+        table_name
+    ));
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
+
+/// Registers `sequence_length_distribution`, counting reads per length or,
+/// for long reads, per bin of `bin_size` lengths keyed by the bin start.
+pub(crate) fn register_sequence_length_distribution(ctx: &ExonSession, bin_size: u64) {
+    let bin_size = bin_size.max(1);
+    register_value_histogram(
+        ctx,
+        "sequence_length_distribution",
+        "length",
+        Arc::new(move |sequence: &str| Some(sequence.len() as u64 / bin_size * bin_size)),
+    );
+}
+
+pub(crate) async fn do_sequence_length_distribution(
+    ctx: &ExonSession,
+    table_name: String,
+    bin_size: u64,
+) -> DataFrame {
+    let query = format!(
+        r#"
+        WITH histogram AS (
+            SELECT sequence_length_distribution(sequence) AS counts
+            FROM {}
+        ),
+        struct_column AS (
+            SELECT unnest(counts) AS my_struct
+            FROM histogram
+        )
+        SELECT
+            my_struct['length'] AS length,
+            my_struct['length'] + {} AS length_end,
+            my_struct['count'] AS count
+        FROM struct_column
+        ORDER BY length;
+        "#,
+        table_name,
+        bin_size.max(1) - 1
+    );
+
+    ctx.sql(&query).await.unwrap()
+}
+
+/// Reads longer than this are truncated to `DUPLICATION_TRUNCATED_LENGTH`
+/// before being tracked, as in FastQC.
+const DUPLICATION_TRUNCATE_ABOVE: usize = 75;
+const DUPLICATION_TRUNCATED_LENGTH: usize = 50;
+const DUPLICATION_LEVEL_LABELS: [&str; 16] = [
+    "1", "2", "3", "4", "5", "6", "7", "8", "9", ">10", ">50", ">100", ">500", ">1k", ">5k",
+    ">10k+",
+];
+
+#[derive(Debug, Clone, Copy)]
+enum SequenceDuplicationOutput {
+    Levels,
+    Overrepresented { min_percentage: f64 },
+}
+
+/// Sequence counts with FastQC's bounded-memory strategy: only the distinct
+/// sequences seen before `max_unique` of them were collected are tracked and
+/// `count_at_limit` records how many reads had been seen at that point, which
+/// is used to extrapolate duplication levels to the whole input. Partial
+/// states are merged by summing the counts of shared sequences and adding
+/// new ones while there is room.
+#[derive(Debug)]
+struct SequenceDuplication {
+    counts: FnvHashMap<String, u64>,
+    max_unique: usize,
+    count_at_limit: u64,
+    total: u64,
+    output: SequenceDuplicationOutput,
+}
+
+impl SequenceDuplication {
+    fn frozen(&self) -> bool {
+        self.counts.len() >= self.max_unique
+    }
+
+    fn add(&mut self, sequence: &str) {
+        self.total += 1;
+        let sequence = if sequence.len() > DUPLICATION_TRUNCATE_ABOVE {
+            sequence.get(..DUPLICATION_TRUNCATED_LENGTH).unwrap_or(sequence)
+        } else {
+            sequence
+        };
+        if let Some(count) = self.counts.get_mut(sequence) {
+            *count += 1;
+            if !self.frozen() {
+                self.count_at_limit = self.total;
+            }
+        } else if !self.frozen() {
+            self.counts.insert(sequence.to_string(), 1);
+            self.count_at_limit = self.total;
+        }
+    }
+
+    /// Estimated number of distinct sequences with `duplication_level` in the
+    /// whole input, given `observations` of them among the tracked ones.
+    fn corrected_count(&self, duplication_level: u64, observations: u64) -> f64 {
+        let observations = observations as f64;
+        if self.count_at_limit == self.total
+            || self.total.saturating_sub(observations as u64) < self.count_at_limit
+        {
+            return observations;
+        }
+        let mut p_not_seeing = 1.0;
+        let limit_of_caring = 1.0 - (observations / (observations + 0.01));
+        for i in 0..self.count_at_limit {
+            let remaining = (self.total - i) as f64;
+            p_not_seeing *= (remaining - duplication_level as f64) / remaining;
+            if p_not_seeing < limit_of_caring {
+                p_not_seeing = 0.0;
+                break;
+            }
+        }
+        observations / (1.0 - p_not_seeing)
+    }
+
+    fn duplication_levels(&self) -> (Vec<f64>, Vec<f64>, f64) {
+        let mut collated: BTreeMap<u64, u64> = BTreeMap::new();
+        for count in self.counts.values() {
+            *collated.entry(*count).or_insert(0) += 1;
+        }
+        let mut deduplicated = vec![0.0; DUPLICATION_LEVEL_LABELS.len()];
+        let mut total = vec![0.0; DUPLICATION_LEVEL_LABELS.len()];
+        let mut deduplicated_sum = 0.0;
+        let mut total_sum = 0.0;
+        for (level, observations) in collated {
+            let corrected = self.corrected_count(level, observations);
+            deduplicated_sum += corrected;
+            total_sum += corrected * level as f64;
+            let slot = match level {
+                10000.. => 15,
+                5000.. => 14,
+                1000.. => 13,
+                500.. => 12,
+                100.. => 11,
+                50.. => 10,
+                10.. => 9,
+                _ => level as usize - 1,
+            };
+            deduplicated[slot] += corrected;
+            total[slot] += corrected * level as f64;
+        }
+        if total_sum == 0.0 {
+            return (deduplicated, total, 0.0);
+        }
+        deduplicated
+            .iter_mut()
+            .for_each(|v| *v = *v / deduplicated_sum * 100.0);
+        total.iter_mut().for_each(|v| *v = *v / total_sum * 100.0);
+        (deduplicated, total, deduplicated_sum / total_sum * 100.0)
+    }
+
+    fn evaluate_levels(&self) -> ScalarValue {
+        let (deduplicated, total, remaining) = self.duplication_levels();
+        let struct_fields = duplication_level_fields()
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+        let struct_builders: Vec<Box<dyn ArrayBuilder>> = vec![
+            Box::new(StringBuilder::new()),
+            Box::new(Float64Builder::new()),
+            Box::new(Float64Builder::new()),
+            Box::new(Float64Builder::new()),
+        ];
+        let mut list_builder = ListBuilder::new(StructBuilder::new(struct_fields, struct_builders));
+        let struct_builder_ref = list_builder.values();
+        for (i, label) in DUPLICATION_LEVEL_LABELS.iter().enumerate() {
+            struct_builder_ref
+                .field_builder::<StringBuilder>(0)
+                .unwrap()
+                .append_value(label);
+            struct_builder_ref
+                .field_builder::<Float64Builder>(1)
+                .unwrap()
+                .append_value(deduplicated[i]);
+            struct_builder_ref
+                .field_builder::<Float64Builder>(2)
+                .unwrap()
+                .append_value(total[i]);
+            struct_builder_ref
+                .field_builder::<Float64Builder>(3)
+                .unwrap()
+                .append_value(remaining);
+            struct_builder_ref.append(true);
+        }
+        list_builder.append(true);
+        ScalarValue::List(Arc::new(list_builder.finish()))
+    }
+
+    fn evaluate_overrepresented(&self, min_percentage: f64) -> ScalarValue {
+        let mut overrepresented = self
+            .counts
+            .iter()
+            .map(|(sequence, count)| {
+                (
+                    sequence,
+                    *count,
+                    *count as f64 / self.total.max(1) as f64 * 100.0,
+                )
+            })
+            .filter(|(_, _, percentage)| *percentage > min_percentage)
+            .collect::<Vec<_>>();
+        overrepresented.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+        let struct_fields = overrepresented_fields()
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+        let struct_builders: Vec<Box<dyn ArrayBuilder>> = vec![
+            Box::new(StringBuilder::new()),
+            Box::new(UInt64Builder::new()),
+            Box::new(Float64Builder::new()),
+        ];
+        let mut list_builder = ListBuilder::new(StructBuilder::new(struct_fields, struct_builders));
+        let struct_builder_ref = list_builder.values();
+        for (sequence, count, percentage) in overrepresented {
+            struct_builder_ref
+                .field_builder::<StringBuilder>(0)
+                .unwrap()
+                .append_value(sequence);
+            struct_builder_ref
+                .field_builder::<UInt64Builder>(1)
+                .unwrap()
+                .append_value(count);
+            struct_builder_ref
+                .field_builder::<Float64Builder>(2)
+                .unwrap()
+                .append_value(percentage);
+            struct_builder_ref.append(true);
+        }
+        list_builder.append(true);
+        ScalarValue::List(Arc::new(list_builder.finish()))
+    }
+}
+
+fn duplication_level_fields() -> Vec<Field> {
+    vec![
+        Field::new("duplication_level", DataType::Utf8, false),
+        Field::new("percent_deduplicated", DataType::Float64, false),
+        Field::new("percent_total", DataType::Float64, false),
+        Field::new("percent_remaining", DataType::Float64, false),
+    ]
+}
+
+fn overrepresented_fields() -> Vec<Field> {
+    vec![
+        Field::new("sequence", DataType::Utf8, false),
+        Field::new("count", DataType::UInt64, false),
+        Field::new("percentage", DataType::Float64, false),
+    ]
+}
+
+impl Accumulator for SequenceDuplication {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        let mut sequences_builder = ListBuilder::new(StringBuilder::new());
+        let mut counts = Vec::with_capacity(self.counts.len());
+        for (sequence, count) in self.counts.iter() {
+            sequences_builder.values().append_value(sequence);
+            counts.push(Some(*count));
+        }
+        sequences_builder.append(true);
+        let counts_list = ScalarValue::List(Arc::new(
+            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(counts)]),
+        ));
+        Ok(vec![
+            ScalarValue::List(Arc::new(sequences_builder.finish())),
+            counts_list,
+            ScalarValue::UInt64(Some(self.count_at_limit)),
+            ScalarValue::UInt64(Some(self.total)),
+        ])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        Ok(match self.output {
+            SequenceDuplicationOutput::Levels => self.evaluate_levels(),
+            SequenceDuplicationOutput::Overrepresented { min_percentage } => {
+                self.evaluate_overrepresented(min_percentage)
+            },
+        })
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        let sequences = string_array(&values[0])?;
+        for sequence in sequences.iter().flatten() {
+            self.add(sequence);
+        }
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        if states.is_empty() {
+            return Ok(());
+        }
+        let sequences_array = states[0]
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("First column must be list array of sequences".to_string())
+            })?;
+        let counts_array = states[1]
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("Second column must be list array of counts".to_string())
+            })?;
+        let count_at_limit_array = states[2]
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("Third column must be UInt64Array".to_string())
+            })?;
+        let total_array = states[3]
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("Fourth column must be UInt64Array".to_string())
+            })?;
+
+        for i in 0..sequences_array.len() {
+            let sequences_array_ref = sequences_array.value(i);
+            let other_sequences = string_array(&sequences_array_ref)?;
+            let counts_array_ref = counts_array.value(i);
+            let other_counts = counts_array_ref
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap();
+            let (mut new_sequences, mut inserted) = (0u64, 0u64);
+            for (sequence, count) in other_sequences.iter().zip(other_counts.values()) {
+                let Some(sequence) = sequence else { continue };
+                if let Some(existing) = self.counts.get_mut(sequence) {
+                    *existing += count;
+                } else {
+                    new_sequences += 1;
+                    if !self.frozen() {
+                        self.counts.insert(sequence.to_string(), *count);
+                        inserted += 1;
+                    }
+                }
+            }
+            // When the merged sequences reach the limit, only the share of new
+            // sequences that fit counts towards the reads seen before it, as
+            // if the partitions had been read one after another
+            let other_count_at_limit = count_at_limit_array.value(i);
+            self.count_at_limit += if inserted == new_sequences {
+                other_count_at_limit
+            } else {
+                (other_count_at_limit as f64 * inserted as f64 / new_sequences as f64) as u64
+            };
+            self.total += total_array.value(i);
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self
+                .counts
+                .keys()
+                .map(|k| k.capacity() + std::mem::size_of::<String>() + std::mem::size_of::<u64>())
+                .sum::<usize>()
+    }
+}
+
+fn register_sequence_duplication(
+    ctx: &ExonSession,
+    name: &str,
+    fields: Vec<Field>,
+    max_unique: usize,
+    output: SequenceDuplicationOutput,
+) {
+    let udaf = create_udaf(
+        name,
+        vec![DataType::Utf8],
+        Arc::new(DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::Struct(fields.into()),
+            true,
+        )))),
+        Volatility::Immutable,
+        Arc::new(move |_| {
+            Ok(Box::new(SequenceDuplication {
+                counts: FnvHashMap::default(),
+                max_unique: max_unique.max(1),
+                count_at_limit: 0,
+                total: 0,
+                output,
+            }))
+        }),
+        Arc::new(vec![
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
+            DataType::UInt64,
+            DataType::UInt64,
+        ]),
+    );
+    ctx.session.register_udaf(udaf);
+}
+
+pub(crate) fn register_sequence_duplication_levels(ctx: &ExonSession, max_unique: usize) {
+    register_sequence_duplication(
+        ctx,
+        "sequence_duplication_levels",
+        duplication_level_fields(),
+        max_unique,
+        SequenceDuplicationOutput::Levels,
+    );
+}
+
+pub(crate) fn register_overrepresented_sequences(
+    ctx: &ExonSession,
+    max_unique: usize,
+    min_percentage: f64,
+) {
+    register_sequence_duplication(
+        ctx,
+        "overrepresented_sequences",
+        overrepresented_fields(),
+        max_unique,
+        SequenceDuplicationOutput::Overrepresented { min_percentage },
+    );
+}
+
+pub(crate) async fn do_sequence_duplication_levels(
+    ctx: &ExonSession,
+    table_name: String,
+) -> DataFrame {
+    let query = format!(
+        r#"
+        WITH levels AS (
+            SELECT sequence_duplication_levels(sequence) AS levels
+            FROM {}
+        ),
+        struct_column AS (
+            SELECT unnest(levels) AS my_struct
+            FROM levels
+        )
+        SELECT
+            my_struct['duplication_level'] AS duplication_level,
+            my_struct['percent_deduplicated'] AS percent_deduplicated,
+            my_struct['percent_total'] AS percent_total,
+            my_struct['percent_remaining'] AS percent_remaining
+        FROM struct_column;
+        "#,
+        table_name
+    );
+
+    ctx.sql(&query).await.unwrap()
+}
+
+pub(crate) async fn do_overrepresented_sequences(
+    ctx: &ExonSession,
+    table_name: String,
+) -> DataFrame {
+    let query = format!(
+        r#"
+        WITH overrepresented AS (
+            SELECT overrepresented_sequences(sequence) AS sequences
+            FROM {}
+        ),
+        struct_column AS (
+            SELECT unnest(sequences) AS my_struct
+            FROM overrepresented
+        )
+        SELECT
+            my_struct['sequence'] AS sequence,
+            my_struct['count'] AS count,
+            my_struct['percentage'] AS percentage
+        FROM struct_column
+        ORDER BY count DESC, sequence;
+        "#,
+        table_name
+    );
+
+    ctx.sql(&query).await.unwrap()
+}
+
+/// Adapters searched for by default, as in FastQC.
+pub(crate) const DEFAULT_ADAPTERS: [(&str, &str); 5] = [
+    ("Illumina Universal Adapter", "AGATCGGAAGAG"),
+    ("Illumina Small RNA 3' Adapter", "TGGAATTCTCGG"),
+    ("Illumina Small RNA 5' Adapter", "GATCGTCGGACT"),
+    ("Nextera Transposase Sequence", "CTGTCTCTTATA"),
+    ("SOLID Small RNA Adapter", "CGCCTTGGCCGT"),
+];
+
+/// Number of reads in which each adapter is first found at each position.
+/// The cumulative percentages are computed on evaluation.
+#[derive(Debug)]
+struct AdapterContent {
+    adapters: Arc<Vec<(String, String)>>,
+    /// Flattened `adapters.len() x max_position_seen` hit counts.
+    hits: Vec<u64>,
+    max_position_seen: usize,
+    total: u64,
+}
+
+impl AdapterContent {
+    fn ensure_capacity(&mut self, length: usize) {
+        if length <= self.max_position_seen {
+            return;
+        }
+        let mut hits = vec![0; self.adapters.len() * length];
+        for a in 0..self.adapters.len() {
+            let old = &self.hits[a * self.max_position_seen..(a + 1) * self.max_position_seen];
+            hits[a * length..a * length + self.max_position_seen].copy_from_slice(old);
+        }
+        self.hits = hits;
+        self.max_position_seen = length;
+    }
+}
+
+fn adapter_content_fields() -> Vec<Field> {
+    vec![
+        Field::new("position", DataType::UInt64, false),
+        Field::new("adapter", DataType::Utf8, false),
+        Field::new("percentage", DataType::Float64, false),
+    ]
+}
+
+impl Accumulator for AdapterContent {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        let hits_list = ScalarValue::List(Arc::new(
+            ListArray::from_iter_primitive::<UInt64Type, _, _>(vec![Some(
+                self.hits.iter().map(|h| Some(*h)).collect::<Vec<_>>(),
+            )]),
+        ));
+        Ok(vec![
+            hits_list,
+            ScalarValue::UInt64(Some(self.max_position_seen as u64)),
+            ScalarValue::UInt64(Some(self.total)),
+        ])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let struct_fields = adapter_content_fields()
+            .into_iter()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+        let struct_builders: Vec<Box<dyn ArrayBuilder>> = vec![
+            Box::new(UInt64Builder::new()),
+            Box::new(StringBuilder::new()),
+            Box::new(Float64Builder::new()),
+        ];
+        let mut list_builder = ListBuilder::new(StructBuilder::new(struct_fields, struct_builders));
+        let struct_builder_ref = list_builder.values();
+        for (a, (name, _)) in self.adapters.iter().enumerate() {
+            let mut cumulative = 0;
+            for position in 0..self.max_position_seen {
+                cumulative += self.hits[a * self.max_position_seen + position];
+                struct_builder_ref
+                    .field_builder::<UInt64Builder>(0)
+                    .unwrap()
+                    .append_value(position as u64 + 1);
+                struct_builder_ref
+                    .field_builder::<StringBuilder>(1)
+                    .unwrap()
+                    .append_value(name);
+                struct_builder_ref
+                    .field_builder::<Float64Builder>(2)
+                    .unwrap()
+                    .append_value(cumulative as f64 / self.total.max(1) as f64 * 100.0);
+                struct_builder_ref.append(true);
+            }
+        }
+        list_builder.append(true);
+        Ok(ScalarValue::List(Arc::new(list_builder.finish())))
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        let sequences = string_array(&values[0])?;
+        for sequence in sequences.iter().flatten() {
+            self.total += 1;
+            self.ensure_capacity(sequence.len());
+            // adapters are uppercase, so soft-masked bases match too
+            let sequence = sequence.to_ascii_uppercase();
+            for (a, (_, adapter)) in self.adapters.iter().enumerate() {
+                if let Some(index) = sequence.find(adapter.as_str()) {
+                    self.hits[a * self.max_position_seen + index] += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        if states.is_empty() {
+            return Ok(());
+        }
+        let hits_array = states[0]
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("First column must be list array of hits".to_string())
+            })?;
+        let max_position_array = states[1]
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("Second column must be UInt64Array".to_string())
+            })?;
+        let total_array = states[2]
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("Third column must be UInt64Array".to_string())
+            })?;
+
+        for i in 0..hits_array.len() {
+            let other_max_position = max_position_array.value(i) as usize;
+            self.ensure_capacity(other_max_position);
+            let hits_array_ref = hits_array.value(i);
+            let other_hits = hits_array_ref
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap();
+            for (j, hits) in other_hits.values().iter().enumerate() {
+                let a = j / other_max_position;
+                let position = j % other_max_position;
+                self.hits[a * self.max_position_seen + position] += hits;
+            }
+            self.total += total_array.value(i);
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.hits.len() * std::mem::size_of::<u64>()
+    }
+}
+
+/// Registers `adapter_content` searching for the given (name, sequence)
+/// adapters, or the FastQC ones when none are given.
+pub(crate) fn register_adapter_content(ctx: &ExonSession, adapters: Option<Vec<(String, String)>>) {
+    let adapters = Arc::new(
+        adapters
+            .unwrap_or_else(|| {
+                DEFAULT_ADAPTERS
+                    .iter()
+                    .map(|(name, sequence)| (name.to_string(), sequence.to_string()))
+                    .collect()
+            })
+            .into_iter()
+            .map(|(name, sequence)| (name, sequence.to_ascii_uppercase()))
+            .filter(|(_, sequence)| !sequence.is_empty())
+            .collect::<Vec<_>>(),
+    );
+    let udaf = create_udaf(
+        "adapter_content",
+        vec![DataType::Utf8],
+        Arc::new(DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::Struct(adapter_content_fields().into()),
+            true,
+        )))),
+        Volatility::Immutable,
+        Arc::new(move |_| {
+            Ok(Box::new(AdapterContent {
+                adapters: Arc::clone(&adapters),
+                hits: vec![],
+                max_position_seen: 0,
+                total: 0,
+            }))
+        }),
+        Arc::new(vec![
+            DataType::List(Arc::new(Field::new("item", DataType::UInt64, true))),
+            DataType::UInt64,
+            DataType::UInt64,
+        ]),
+    );
+    ctx.session.register_udaf(udaf);
+}
+
+pub(crate) async fn do_adapter_content(ctx: &ExonSession, table_name: String) -> Result<DataFrame> {
+    let query = format!(
+        r#"
+        WITH content AS (
+            SELECT adapter_content(sequence) AS positions
+            FROM {}
+        ),
+        struct_column AS (
+            SELECT unnest(positions) AS my_struct
+            FROM content
+        )
+        SELECT
+            my_struct['position'] AS position,
+            my_struct['adapter'] AS adapter,
+            my_struct['percentage'] AS percentage
+        FROM struct_column;
+        "#,
+        table_name
+    );
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
+
+/// Computes all FastQC modules in a single pass over the table. Returns one
+/// row with the basic statistics and the raw output of each aggregate.
+pub(crate) async fn do_fastq_qc_report(
+    ctx: &ExonSession,
+    table_name: String,
+    phred_offset: u8,
+    max_unique: usize,
+    adapters: Option<Vec<(String, String)>>,
+    min_overrepresented_percentage: f64,
+) -> Result<DataFrame> {
+    register_base_sequence_quality(ctx, phred_offset);
+    register_per_sequence_quality(ctx, phred_offset);
+    register_base_sequence_content(ctx);
+    register_per_sequence_gc_content(ctx);
+    register_sequence_length_distribution(ctx, 1);
+    register_sequence_duplication_levels(ctx, max_unique);
+    register_overrepresented_sequences(ctx, max_unique, min_overrepresented_percentage);
+    register_adapter_content(ctx, adapters);
+    let query = format!(
+        r#"
+        SELECT
+            CAST(count(sequence) AS BIGINT UNSIGNED) AS total_sequences,
+            CAST(COALESCE(sum(octet_length(sequence)), 0) AS BIGINT UNSIGNED) AS total_bases,
+            CAST(min(octet_length(sequence)) AS BIGINT UNSIGNED) AS min_length,
+            CAST(max(octet_length(sequence)) AS BIGINT UNSIGNED) AS max_length,
+            base_sequence_quality(quality_scores) AS base_sequence_quality,
+            per_sequence_quality(quality_scores) AS per_sequence_quality,
+            base_sequence_content(sequence) AS base_sequence_content,
+            per_sequence_gc_content(sequence) AS per_sequence_gc_content,
+            sequence_length_distribution(sequence) AS sequence_length_distribution,
+            sequence_duplication_levels(sequence) AS sequence_duplication_levels,
+            overrepresented_sequences(sequence) AS overrepresented_sequences,
+            adapter_content(sequence) AS adapter_content
+        FROM {};
+        "#,
+        table_name
+    );
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
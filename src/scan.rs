@@ -1,11 +1,19 @@
-use std::sync::Arc;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
 
 use arrow::array::RecordBatch;
 use arrow::error::ArrowError;
 use arrow::ffi_stream::ArrowArrayStreamReader;
 use arrow::pyarrow::PyArrowType;
+use arrow::record_batch::RecordBatchReader;
+use arrow_schema::SchemaRef;
 use datafusion::dataframe::DataFrameWriteOptions;
+use datafusion::datasource::streaming::StreamingTable;
 use datafusion::datasource::MemTable;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::PartitionStream;
 use datafusion::prelude::{CsvReadOptions, ParquetReadOptions};
 use datafusion_bio_format_bam::table_provider::BamTableProvider;
 use datafusion_bio_format_bed::table_provider::{BEDFields, BedTableProvider};
@@ -22,6 +30,7 @@ use crate::option::{
     BamReadOptions, BedReadOptions, FastqReadOptions, GffReadOptions, InputFormat, ReadOptions,
     VcfReadOptions,
 };
+use crate::paired_fastq::register_paired_fastq;
 
 const MAX_IN_MEMORY_ROWS: usize = 1024 * 1024;
 
@@ -29,36 +38,89 @@ pub(crate) fn register_frame(
     py_ctx: &PyBioSessionContext,
     df: PyArrowType<ArrowArrayStreamReader>,
     table_name: String,
-) {
-    let batches =
-        df.0.collect::<Result<Vec<RecordBatch>, ArrowError>>()
-            .unwrap();
+) -> Result<()> {
+    let batches = df.0.collect::<Result<Vec<RecordBatch>, ArrowError>>()?;
     let schema = batches[0].schema();
     let ctx = &py_ctx.ctx;
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    let table_source = MemTable::try_new(schema, vec![batches]).unwrap();
-    ctx.session.deregister_table(&table_name).unwrap();
+    let rt = tokio::runtime::Runtime::new()?;
+    let table_source = MemTable::try_new(schema, vec![batches])?;
+    ctx.session.deregister_table(&table_name)?;
     ctx.session
-        .register_table(&table_name, Arc::new(table_source))
-        .unwrap();
-    let df = rt.block_on(ctx.session.table(&table_name)).unwrap();
-    let table_size = rt.block_on(df.clone().count()).unwrap();
+        .register_table(&table_name, Arc::new(table_source))?;
+    let df = rt.block_on(ctx.session.table(&table_name))?;
+    let table_size = rt.block_on(df.clone().count())?;
     if table_size > MAX_IN_MEMORY_ROWS {
         let path = format!("{}/{}.parquet", py_ctx.catalog_dir, table_name);
-        ctx.session.deregister_table(&table_name).unwrap();
-        rt.block_on(df.write_parquet(&path, DataFrameWriteOptions::new(), None))
-            .unwrap();
-        ctx.session.deregister_table(&table_name).unwrap();
+        ctx.session.deregister_table(&table_name)?;
+        rt.block_on(df.write_parquet(&path, DataFrameWriteOptions::new(), None))?;
+        ctx.session.deregister_table(&table_name)?;
         rt.block_on(register_table(
             ctx,
             &path,
             &table_name,
             InputFormat::Parquet,
             None,
-        ));
+        ))?;
+    }
+    Ok(())
+}
+
+/// A single partition reading the batches of an Arrow stream as they are
+/// pulled, so that a frame can be scanned once without being collected.
+struct FrameStreamPartition {
+    schema: SchemaRef,
+    reader: Mutex<Option<ArrowArrayStreamReader>>,
+}
+
+impl Debug for FrameStreamPartition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameStreamPartition")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl PartitionStream for FrameStreamPartition {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let batches: Box<dyn Iterator<Item = Result<RecordBatch>> + Send> =
+            match self.reader.lock().unwrap().take() {
+                Some(reader) => Box::new(reader.map(|batch| batch.map_err(DataFusionError::from))),
+                None => Box::new(std::iter::once(Err(DataFusionError::Execution(
+                    "A frame stream can only be scanned once".to_string(),
+                )))),
+            };
+        Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            futures::stream::iter(batches),
+        ))
     }
 }
 
+/// Registers an Arrow stream as a table that reads the stream while being
+/// scanned instead of collecting it into memory first like `register_frame`.
+/// The table can only be scanned once.
+pub(crate) fn register_frame_stream(
+    py_ctx: &PyBioSessionContext,
+    df: PyArrowType<ArrowArrayStreamReader>,
+    table_name: String,
+) {
+    let schema = df.0.schema();
+    let partition = FrameStreamPartition {
+        schema: schema.clone(),
+        reader: Mutex::new(Some(df.0)),
+    };
+    let table_source = StreamingTable::try_new(schema, vec![Arc::new(partition)]).unwrap();
+    let ctx = &py_ctx.ctx;
+    ctx.session.deregister_table(&table_name).unwrap();
+    ctx.session
+        .register_table(&table_name, Arc::new(table_source))
+        .unwrap();
+}
+
 pub(crate) fn get_input_format(path: &str) -> InputFormat {
     let path = path.to_lowercase();
     if path.ends_with(".parquet") {
@@ -71,6 +133,8 @@ pub(crate) fn get_input_format(path: &str) -> InputFormat {
         InputFormat::Vcf
     } else if path.ends_with(".gff") || path.ends_with(".gff.gz") || path.ends_with(".gff.bgz") {
         InputFormat::Gff
+    } else if path.ends_with(".bam") {
+        InputFormat::Bam
     } else {
         panic!("Unsupported format")
     }
@@ -82,7 +146,7 @@ pub(crate) async fn register_table(
     table_name: &str,
     format: InputFormat,
     read_options: Option<ReadOptions>,
-) -> String {
+) -> Result<String> {
     ctx.session.deregister_table(table_name).unwrap();
     match format {
         InputFormat::Parquet => ctx
@@ -111,15 +175,19 @@ pub(crate) async fn register_table(
                 "Registering FASTQ table {} with options: {:?}",
                 table_name, fastq_read_options
             );
-            let table_provider = FastqTableProvider::new(
-                path.to_string(),
-                fastq_read_options.thread_num,
-                fastq_read_options.object_storage_options.clone(),
-            )
-            .unwrap();
-            ctx.session
-                .register_table(table_name, Arc::new(table_provider))
-                .expect("Failed to register FASTQ table");
+            if fastq_read_options.paired_path.is_some() || fastq_read_options.interleaved {
+                register_paired_fastq(ctx, path, table_name, &fastq_read_options).await?;
+            } else {
+                let table_provider = FastqTableProvider::new(
+                    path.to_string(),
+                    fastq_read_options.thread_num,
+                    fastq_read_options.object_storage_options.clone(),
+                )
+                .unwrap();
+                ctx.session
+                    .register_table(table_name, Arc::new(table_provider))
+                    .expect("Failed to register FASTQ table");
+            }
         },
         InputFormat::Vcf => {
             let vcf_read_options = match &read_options {
@@ -222,7 +290,7 @@ pub(crate) async fn register_table(
             todo!("Indexed formats are not supported")
         },
     };
-    table_name.to_string()
+    Ok(table_name.to_string())
 }
 
 pub(crate) fn maybe_register_table(
@@ -231,11 +299,11 @@ pub(crate) fn maybe_register_table(
     read_options: Option<ReadOptions>,
     ctx: &ExonSession,
     rt: &Runtime,
-) -> String {
+) -> Result<String> {
     let ext: Vec<&str> = df_path_or_table.split('.').collect();
     debug!("ext: {:?}", ext);
     if ext.len() == 1 {
-        return df_path_or_table;
+        return Ok(df_path_or_table);
     }
     match ext.last() {
         Some(_ext) => {
@@ -245,10 +313,9 @@ pub(crate) fn maybe_register_table(
                 default_table,
                 get_input_format(&df_path_or_table),
                 read_options,
-            ));
-            default_table.to_string()
+            ))?;
+            Ok(default_table.to_string())
         },
-        _ => df_path_or_table,
+        _ => Ok(df_path_or_table),
     }
-    .to_string()
 }
@@ -1,8 +1,11 @@
 use std::sync::{Arc, Mutex};
 
+use datafusion::dataframe::DataFrame;
 use datafusion::execution::SendableRecordBatchStream;
 use futures_util::StreamExt;
+use log::debug;
 use polars::prelude::PolarsResult;
+use polars_lazy::prelude::{LazyFrame, ScanArgsAnonymous};
 use polars_plan::plans::{AnonymousScan, AnonymousScanArgs};
 use tokio::runtime::Runtime;
 
@@ -43,3 +46,22 @@ impl AnonymousScan for RangeOperationScan {
         false //TODO: implement
     }
 }
+
+/// Returns a LazyFrame pulling the batches of a DataFusion DataFrame as it is
+/// collected.
+pub(crate) fn dataframe_to_lazy_frame(rt: &Runtime, df: DataFrame) -> PolarsResult<LazyFrame> {
+    let schema = df.schema().as_arrow();
+    let polars_schema = convert_arrow_rb_schema_to_polars_df_schema(schema)?;
+    debug!("Schema: {:?}", polars_schema);
+    let args = ScanArgsAnonymous {
+        schema: Some(Arc::new(polars_schema)),
+        name: "SCAN polars-bio",
+        ..ScanArgsAnonymous::default()
+    };
+    let stream = rt.block_on(df.execute_stream()).unwrap();
+    let scan = RangeOperationScan {
+        df_iter: Arc::new(Mutex::new(stream)),
+        rt: Runtime::new().unwrap(),
+    };
+    LazyFrame::anonymous_scan(Arc::new(scan), args)
+}
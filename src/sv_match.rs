@@ -0,0 +1,407 @@
+use std::cmp::{max, Ordering};
+use std::sync::Arc;
+
+use arrow::compute::{cast, interleave};
+use arrow_array::{Array, ArrayRef, Float64Array, Int64Array, ListArray, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, FieldRef, Schema, SchemaRef};
+use coitrees::{COITree, Interval, IntervalTree};
+use datafusion::common::{DataFusionError, Result};
+use fnv::FnvHashMap;
+
+use crate::option::SvMatchOptions;
+use crate::utils::with_suffix;
+use crate::vcf_intervals::{info_column, is_vcf_schema};
+
+/// Structural variant call extracted from a VCF-derived table. Positions are
+/// 1-based and inclusive, as in the VCF.
+#[derive(Clone, Debug)]
+struct SvCall {
+    row: (usize, usize),
+    contig: String,
+    start: i64,
+    end: i64,
+    svtype: String,
+    svlen: Option<i64>,
+    cipos: (i64, i64),
+    ciend: (i64, i64),
+    // breakend mate (contig, position) parsed from the ALT allele
+    mate: Option<(String, i64)>,
+}
+
+#[derive(Clone, Debug)]
+struct SvMatch {
+    call: usize,
+    base: usize,
+    start_distance: i64,
+    end_distance: i64,
+    reciprocal_overlap: Option<f64>,
+    size_similarity: Option<f64>,
+    score: f64,
+}
+
+fn string_values(batch: &RecordBatch, name: &str) -> Result<Option<Vec<Option<String>>>> {
+    let Some(column) = batch.column_by_name(name) else {
+        return Ok(None);
+    };
+    let column = cast(column, &DataType::Utf8)?;
+    let column = column.as_any().downcast_ref::<StringArray>().unwrap();
+    Ok(Some(
+        column.iter().map(|v| v.map(|v| v.to_string())).collect(),
+    ))
+}
+
+/// Reads an integer INFO field. For list fields (e.g. `Number=A` or `Number=2`)
+/// the first `n` elements of every row are returned.
+fn int_values(batch: &RecordBatch, name: &str, n: usize) -> Result<Option<Vec<Vec<i64>>>> {
+    let Some(column) = batch.column_by_name(name) else {
+        return Ok(None);
+    };
+    let values = match column.data_type() {
+        DataType::List(_) | DataType::LargeList(_) => {
+            let item = Arc::new(Field::new("item", DataType::Int64, true));
+            let column = cast(column, &DataType::List(item))?;
+            let column = column.as_any().downcast_ref::<ListArray>().unwrap();
+            (0..column.len())
+                .map(|i| {
+                    if column.is_null(i) {
+                        return vec![];
+                    }
+                    let values = column.value(i);
+                    let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+                    values.iter().take(n).flatten().collect()
+                })
+                .collect()
+        },
+        _ => {
+            let column = cast(column, &DataType::Int64)?;
+            let column = column.as_any().downcast_ref::<Int64Array>().unwrap();
+            column.iter().map(|v| v.into_iter().collect()).collect()
+        },
+    };
+    Ok(Some(values))
+}
+
+/// Parses the mate position of a breakend ALT allele, e.g. `G]chr17:198982]`.
+fn breakend_mate(alt: &str) -> Option<(String, i64)> {
+    let start = alt.find(['[', ']'])?;
+    let end = alt[start + 1..].find(['[', ']'])? + start + 1;
+    let (contig, pos) = alt[start + 1..end].rsplit_once(':')?;
+    Some((contig.to_string(), pos.parse().ok()?))
+}
+
+/// SV type from the `SVTYPE` INFO field or, if missing, from the alleles.
+/// Subtypes are dropped, e.g. `DEL:ME` and `DUP:TANDEM` become `DEL` and `DUP`.
+fn sv_type(svtype: Option<&str>, reference: &str, alt: &str) -> String {
+    let svtype = match svtype {
+        Some(svtype) => svtype.to_string(),
+        None if alt.starts_with('<') => alt.trim_matches(['<', '>']).to_string(),
+        None if alt.contains(['[', ']']) => "BND".to_string(),
+        None if reference.len() > alt.len() => "DEL".to_string(),
+        None if reference.len() < alt.len() => "INS".to_string(),
+        None => "SNV".to_string(),
+    };
+    svtype
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+}
+
+fn sv_calls(batches: &[RecordBatch]) -> Result<Vec<SvCall>> {
+    let mut calls = Vec::new();
+    for (b, batch) in batches.iter().enumerate() {
+        let schema = batch.schema();
+        if !is_vcf_schema(&schema) {
+            return Err(DataFusionError::Execution(
+                "SV matching requires VCF-derived tables".to_string(),
+            ));
+        }
+        let info_name = |key: &str| info_column(&schema, key).map(|f| f.name().clone());
+        let contigs = string_values(batch, "chrom")?.unwrap();
+        let starts = int_values(batch, "start", 1)?.unwrap();
+        let ends = int_values(batch, "end", 1)?.unwrap();
+        let refs = string_values(batch, "ref")?.unwrap();
+        let alts = string_values(batch, "alt")?.unwrap();
+        let svtypes = match info_name("SVTYPE") {
+            Some(name) => string_values(batch, &name)?,
+            None => None,
+        };
+        let info_ints = |key: &str, n: usize| -> Result<Option<Vec<Vec<i64>>>> {
+            match info_name(key) {
+                Some(name) => int_values(batch, &name, n),
+                None => Ok(None),
+            }
+        };
+        let svlens = info_ints("SVLEN", 1)?;
+        let ciposes = info_ints("CIPOS", 2)?;
+        let ciends = info_ints("CIEND", 2)?;
+        let interval = |values: &Option<Vec<Vec<i64>>>, i: usize| match values {
+            Some(values) if values[i].len() == 2 => (values[i][0], values[i][1]),
+            _ => (0, 0),
+        };
+
+        for i in 0..batch.num_rows() {
+            let reference = refs[i].as_deref().unwrap_or_default();
+            let alt = alts[i].as_deref().unwrap_or_default();
+            let svtype = sv_type(
+                svtypes.as_ref().and_then(|v| v[i].as_deref()),
+                reference,
+                alt,
+            );
+            let start = starts[i].first().copied().unwrap_or_default();
+            let end = ends[i].first().copied().unwrap_or(start);
+            let svlen = match svlens.as_ref().and_then(|v| v[i].first()) {
+                Some(svlen) => Some(svlen.abs()),
+                None if svtype == "BND" => None,
+                None if !alt.starts_with('<') && reference.len() != alt.len() => {
+                    Some((reference.len() as i64 - alt.len() as i64).abs())
+                },
+                None if end > start => Some(end - start),
+                None => None,
+            };
+            calls.push(SvCall {
+                row: (b, i),
+                contig: contigs[i].clone().unwrap_or_default(),
+                start,
+                end,
+                mate: if svtype == "BND" {
+                    breakend_mate(alt)
+                } else {
+                    None
+                },
+                svtype,
+                svlen,
+                cipos: interval(&ciposes, i),
+                ciend: interval(&ciends, i),
+            });
+        }
+    }
+    Ok(calls)
+}
+
+/// Distance between two breakpoints widened by their confidence intervals.
+fn breakpoint_distance(a: i64, a_ci: (i64, i64), b: i64, b_ci: (i64, i64)) -> i64 {
+    max(
+        0,
+        max((a + a_ci.0) - (b + b_ci.1), (b + b_ci.0) - (a + a_ci.1)),
+    )
+}
+
+fn compare(call: &SvCall, base: &SvCall, options: &SvMatchOptions) -> Option<SvMatch> {
+    if options.match_type && call.svtype != base.svtype {
+        return None;
+    }
+    let (call_cipos, call_ciend, base_cipos, base_ciend) = if options.use_confidence_intervals {
+        (call.cipos, call.ciend, base.cipos, base.ciend)
+    } else {
+        ((0, 0), (0, 0), (0, 0), (0, 0))
+    };
+    let start_distance = breakpoint_distance(call.start, call_cipos, base.start, base_cipos);
+    let end_distance = match (&call.mate, &base.mate) {
+        (Some((call_contig, call_pos)), Some((base_contig, base_pos))) => {
+            if call_contig != base_contig {
+                return None;
+            }
+            breakpoint_distance(*call_pos, call_ciend, *base_pos, base_ciend)
+        },
+        (None, None) => breakpoint_distance(call.end, call_ciend, base.end, base_ciend),
+        _ => return None,
+    };
+    if start_distance > options.max_distance || end_distance > options.max_distance {
+        return None;
+    }
+    let is_span = |c: &SvCall| c.svtype != "INS" && c.svtype != "BND" && c.end > c.start;
+    let reciprocal_overlap = if is_span(call) && is_span(base) {
+        let overlap = (call.end.min(base.end) - call.start.max(base.start) + 1).max(0);
+        let length = (call.end - call.start + 1).max(base.end - base.start + 1);
+        Some(overlap as f64 / length as f64)
+    } else {
+        None
+    };
+    if reciprocal_overlap.is_some_and(|o| o < options.min_reciprocal_overlap) {
+        return None;
+    }
+    let size_similarity = match (call.svlen, base.svlen) {
+        (Some(a), Some(b)) if a.max(b) > 0 => Some(a.min(b) as f64 / a.max(b) as f64),
+        _ => None,
+    };
+    let distance_score = if options.max_distance > 0 {
+        1.0 - (start_distance + end_distance) as f64 / (2 * options.max_distance) as f64
+    } else {
+        1.0
+    };
+    let metrics = [reciprocal_overlap, size_similarity, Some(distance_score)]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<f64>>();
+    Some(SvMatch {
+        call: 0,
+        base: 0,
+        start_distance,
+        end_distance,
+        reciprocal_overlap,
+        size_similarity,
+        score: metrics.iter().sum::<f64>() / metrics.len() as f64,
+    })
+}
+
+/// Matches SV calls against base (e.g. truth set) calls and returns the best
+/// one-to-one pairs. Candidates are found with interval trees over the base start
+/// breakpoints and assigned greedily by decreasing score, as Truvari does by default.
+fn best_matches(calls: &[SvCall], base: &[SvCall], options: &SvMatchOptions) -> Vec<SvMatch> {
+    let mut nodes = FnvHashMap::<String, Vec<Interval<u32>>>::default();
+    for (i, b) in base.iter().enumerate() {
+        let (lo, hi) = if options.use_confidence_intervals {
+            b.cipos
+        } else {
+            (0, 0)
+        };
+        nodes
+            .entry(b.contig.clone())
+            .or_default()
+            .push(Interval::new(
+                (b.start + lo) as i32,
+                (b.start + hi) as i32,
+                i as u32,
+            ));
+    }
+    let trees = nodes
+        .into_iter()
+        .map(|(contig, nodes)| (contig, COITree::<u32, u32>::new(&nodes)))
+        .collect::<FnvHashMap<_, _>>();
+
+    let mut candidates = Vec::new();
+    for (i, call) in calls.iter().enumerate() {
+        let Some(tree) = trees.get(&call.contig) else {
+            continue;
+        };
+        let (lo, hi) = if options.use_confidence_intervals {
+            call.cipos
+        } else {
+            (0, 0)
+        };
+        tree.query(
+            (call.start + lo - options.max_distance) as i32,
+            (call.start + hi + options.max_distance) as i32,
+            |node| {
+                let b = *node.metadata as usize;
+                if let Some(mut m) = compare(call, &base[b], options) {
+                    m.call = i;
+                    m.base = b;
+                    candidates.push(m);
+                }
+            },
+        );
+    }
+    candidates.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then((a.start_distance + a.end_distance).cmp(&(b.start_distance + b.end_distance)))
+            .then(a.call.cmp(&b.call))
+            .then(a.base.cmp(&b.base))
+    });
+    let mut call_used = vec![false; calls.len()];
+    let mut base_used = vec![false; base.len()];
+    let mut matches = Vec::new();
+    for m in candidates {
+        if call_used[m.call] || base_used[m.base] {
+            continue;
+        }
+        call_used[m.call] = true;
+        base_used[m.base] = true;
+        matches.push(m);
+    }
+    matches.sort_by_key(|m| m.call);
+    matches
+}
+
+fn take_rows(
+    schema: &Schema,
+    batches: &[RecordBatch],
+    rows: &[(usize, usize)],
+) -> Result<Vec<ArrayRef>> {
+    (0..schema.fields().len())
+        .map(|c| {
+            let arrays: Vec<&dyn Array> = batches.iter().map(|b| b.column(c).as_ref()).collect();
+            Ok(interleave(&arrays, rows)?)
+        })
+        .collect()
+}
+
+/// Runs SV matching of `calls` against `base` and returns a single batch with the
+/// columns of both tables (with suffixes) followed by the match metrics.
+pub(crate) fn match_structural_variants(
+    calls_schema: SchemaRef,
+    calls_batches: Vec<RecordBatch>,
+    base_schema: SchemaRef,
+    base_batches: Vec<RecordBatch>,
+    options: &SvMatchOptions,
+) -> Result<RecordBatch> {
+    let suffixes = match &options.suffixes {
+        Some((s1, s2)) => (s1.clone(), s2.clone()),
+        _ => ("_1".to_string(), "_2".to_string()),
+    };
+    let calls = sv_calls(&calls_batches)?;
+    let base = sv_calls(&base_batches)?;
+    let matches = best_matches(&calls, &base, options);
+
+    let mut fields = [
+        with_suffix(&calls_schema, &suffixes.0),
+        with_suffix(&base_schema, &suffixes.1),
+    ]
+    .concat();
+    fields.extend([
+        FieldRef::new(Field::new(
+            format!("svtype{}", suffixes.0),
+            DataType::Utf8,
+            false,
+        )),
+        FieldRef::new(Field::new(
+            format!("svtype{}", suffixes.1),
+            DataType::Utf8,
+            false,
+        )),
+        FieldRef::new(Field::new("start_distance", DataType::Int64, false)),
+        FieldRef::new(Field::new("end_distance", DataType::Int64, false)),
+        FieldRef::new(Field::new("reciprocal_overlap", DataType::Float64, true)),
+        FieldRef::new(Field::new("size_similarity", DataType::Float64, true)),
+        FieldRef::new(Field::new("score", DataType::Float64, false)),
+    ]);
+    let schema = Arc::new(Schema::new(fields));
+    if matches.is_empty() {
+        return Ok(RecordBatch::new_empty(schema));
+    }
+
+    let call_rows = matches
+        .iter()
+        .map(|m| calls[m.call].row)
+        .collect::<Vec<_>>();
+    let base_rows = matches.iter().map(|m| base[m.base].row).collect::<Vec<_>>();
+    let mut columns = take_rows(&calls_schema, &calls_batches, &call_rows)?;
+    columns.extend(take_rows(&base_schema, &base_batches, &base_rows)?);
+    columns.extend([
+        Arc::new(StringArray::from_iter_values(
+            matches.iter().map(|m| calls[m.call].svtype.as_str()),
+        )) as ArrayRef,
+        Arc::new(StringArray::from_iter_values(
+            matches.iter().map(|m| base[m.base].svtype.as_str()),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            matches.iter().map(|m| m.start_distance),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            matches.iter().map(|m| m.end_distance),
+        )),
+        Arc::new(Float64Array::from_iter(
+            matches.iter().map(|m| m.reciprocal_overlap),
+        )),
+        Arc::new(Float64Array::from_iter(
+            matches.iter().map(|m| m.size_similarity),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            matches.iter().map(|m| m.score),
+        )),
+    ]);
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
@@ -0,0 +1,696 @@
+use std::any::Any;
+use std::cmp::{max, min};
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use arrow::compute::{interleave, take};
+use arrow_array::{new_empty_array, Array, ArrayRef, Int64Array, RecordBatch, UInt32Array};
+use arrow_schema::{DataType, Field, FieldRef, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::datasource::TableType;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::physical_expr::{EquivalenceProperties, Partitioning};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionMode, ExecutionPlan, PlanProperties,
+};
+use datafusion::prelude::{Expr, SessionContext};
+use fnv::{FnvHashMap, FnvHashSet};
+use futures_util::{StreamExt, TryStreamExt};
+
+use crate::option::{FilterOp, RangeOp};
+use crate::udtf::{get_join_col_arrays, node_coverage};
+use crate::utils::with_suffix;
+
+/// Sort-merge (sweep-line) implementation of overlap, nearest, count overlaps and coverage
+/// for inputs sorted by contig and start position. Both tables are streamed once, so memory
+/// is bounded by the number of reference intervals active at the current query locus.
+pub struct SweepLineProvider {
+    session: Arc<SessionContext>,
+    query_table: String,
+    reference_table: String,
+    query_columns: (String, String, String),
+    reference_columns: (String, String, String),
+    reference_schema: SchemaRef,
+    range_op: RangeOp,
+    filter_op: FilterOp,
+    schema: SchemaRef,
+}
+
+impl SweepLineProvider {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        session: Arc<SessionContext>,
+        query_table: String,
+        query_schema: Schema,
+        query_columns: Vec<String>,
+        reference_table: String,
+        reference_schema: Schema,
+        reference_columns: Vec<String>,
+        range_op: RangeOp,
+        filter_op: FilterOp,
+        suffixes: (String, String),
+    ) -> Self {
+        Self {
+            session,
+            query_table,
+            reference_table,
+            schema: output_schema(&range_op, &query_schema, &reference_schema, &suffixes),
+            query_columns: (
+                query_columns[0].clone(),
+                query_columns[1].clone(),
+                query_columns[2].clone(),
+            ),
+            reference_columns: (
+                reference_columns[0].clone(),
+                reference_columns[1].clone(),
+                reference_columns[2].clone(),
+            ),
+            reference_schema: Arc::new(reference_schema),
+            range_op,
+            filter_op,
+        }
+    }
+}
+
+fn output_schema(
+    range_op: &RangeOp,
+    query_schema: &Schema,
+    reference_schema: &Schema,
+    suffixes: &(String, String),
+) -> SchemaRef {
+    let fields = match range_op {
+        RangeOp::Overlap => [
+            with_suffix(reference_schema, &suffixes.0),
+            with_suffix(query_schema, &suffixes.1),
+        ]
+        .concat(),
+        RangeOp::Nearest => {
+            let mut fields = [
+                with_suffix(query_schema, &suffixes.0),
+                with_suffix(reference_schema, &suffixes.1),
+            ]
+            .concat();
+            fields.push(FieldRef::new(Field::new(
+                "distance",
+                DataType::Int64,
+                false,
+            )));
+            fields
+        },
+        _ => {
+            let mut fields = query_schema.fields().to_vec();
+            let name = if *range_op == RangeOp::Coverage {
+                "coverage"
+            } else {
+                "count"
+            };
+            fields.push(FieldRef::new(Field::new(name, DataType::Int64, false)));
+            fields
+        },
+    };
+    Arc::new(Schema::new(fields))
+}
+
+impl Debug for SweepLineProvider {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TableProvider for SweepLineProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        _projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(SweepLineExec {
+            schema: self.schema.clone(),
+            session: Arc::clone(&self.session),
+            query_table: self.query_table.clone(),
+            reference_table: self.reference_table.clone(),
+            query_columns: self.query_columns.clone(),
+            reference_columns: self.reference_columns.clone(),
+            reference_schema: self.reference_schema.clone(),
+            range_op: self.range_op.clone(),
+            filter_op: self.filter_op.clone(),
+            // a single partition keeps both inputs in their sorted order
+            cache: PlanProperties::new(
+                EquivalenceProperties::new(self.schema.clone()),
+                Partitioning::UnknownPartitioning(1),
+                ExecutionMode::Bounded,
+            ),
+        }))
+    }
+}
+
+struct SweepLineExec {
+    schema: SchemaRef,
+    session: Arc<SessionContext>,
+    query_table: String,
+    reference_table: String,
+    query_columns: (String, String, String),
+    reference_columns: (String, String, String),
+    reference_schema: SchemaRef,
+    range_op: RangeOp,
+    filter_op: FilterOp,
+    cache: PlanProperties,
+}
+
+impl Debug for SweepLineExec {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl DisplayAs for SweepLineExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "SweepLineExec: op={}", self.range_op)
+    }
+}
+
+impl ExecutionPlan for SweepLineExec {
+    fn name(&self) -> &str {
+        "SweepLineExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.cache
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let fut = get_stream(
+            Arc::clone(&self.session),
+            self.query_table.clone(),
+            self.reference_table.clone(),
+            self.query_columns.clone(),
+            self.reference_columns.clone(),
+            self.reference_schema.clone(),
+            self.range_op.clone(),
+            self.filter_op.clone(),
+            self.schema.clone(),
+        );
+        let stream = futures::stream::once(fut).try_flatten();
+        let schema = self.schema.clone();
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, stream)))
+    }
+}
+
+fn not_sorted(side: &str, contig: &str, start: i32) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "Sweep-line requires inputs sorted by contig and start, but the {} table is not sorted at {}:{}",
+        side, contig, start
+    ))
+}
+
+/// Reads only the join columns of the reference table and assigns each contig its rank
+/// in the order of first appearance. Query contigs are synced against this order.
+async fn reference_contig_ranks(
+    session: &SessionContext,
+    reference_table: &str,
+    columns: &(String, String, String),
+) -> Result<FnvHashMap<String, usize>> {
+    let mut stream = session
+        .table(reference_table)
+        .await?
+        .select_columns(&[columns.0.as_str(), columns.1.as_str(), columns.2.as_str()])?
+        .execute_stream()
+        .await?;
+    let mut ranks = FnvHashMap::<String, usize>::default();
+    let mut last_contig: Option<String> = None;
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        let (contig_arr, start_arr, _) = get_join_col_arrays(&batch, columns.clone());
+        for i in 0..batch.num_rows() {
+            let contig = contig_arr.value(i);
+            if last_contig.as_deref() == Some(contig) {
+                continue;
+            }
+            if ranks.contains_key(contig) {
+                return Err(not_sorted("reference", contig, start_arr.value(i)));
+            }
+            ranks.insert(contig.to_string(), ranks.len());
+            last_contig = Some(contig.to_string());
+        }
+    }
+    Ok(ranks)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn get_stream(
+    session: Arc<SessionContext>,
+    query_table: String,
+    reference_table: String,
+    query_columns: (String, String, String),
+    reference_columns: (String, String, String),
+    reference_schema: SchemaRef,
+    range_op: RangeOp,
+    filter_op: FilterOp,
+    new_schema: SchemaRef,
+) -> Result<SendableRecordBatchStream> {
+    let reference_contigs =
+        reference_contig_ranks(&session, &reference_table, &reference_columns).await?;
+    let query = session.table(query_table).await?.execute_stream().await?;
+    let reference = session
+        .table(reference_table)
+        .await?
+        .execute_stream()
+        .await?;
+
+    let state = SweepState {
+        query,
+        reference,
+        query_columns,
+        reference_columns,
+        reference_schema,
+        range_op,
+        strict: filter_op == FilterOp::Strict,
+        schema: new_schema.clone(),
+        reference_contigs,
+        reference_batches: VecDeque::new(),
+        first_batch: 0,
+        cursor: (0, 0),
+        reference_done: false,
+        last_reference: None,
+        rank: None,
+        active: Vec::new(),
+        last_ended: None,
+        last_query: None,
+        seen_query_contigs: FnvHashSet::default(),
+    };
+    let stream = futures::stream::try_unfold(state, |mut state| async move {
+        while let Some(batch) = state.query.next().await {
+            let output = state.process_batch(batch?).await?;
+            if output.num_rows() > 0 {
+                return Ok(Some((output, state)));
+            }
+        }
+        Ok::<_, DataFusionError>(None)
+    });
+    Ok(Box::pin(RecordBatchStreamAdapter::new(new_schema, stream)))
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RefInterval {
+    start: i32,
+    end: i32,
+    batch: usize,
+    row: usize,
+}
+
+struct RefBatch {
+    batch: RecordBatch,
+    ranks: Vec<usize>,
+    starts: Vec<i32>,
+    ends: Vec<i32>,
+}
+
+struct SweepState {
+    query: SendableRecordBatchStream,
+    reference: SendableRecordBatchStream,
+    query_columns: (String, String, String),
+    reference_columns: (String, String, String),
+    reference_schema: SchemaRef,
+    range_op: RangeOp,
+    strict: bool,
+    schema: SchemaRef,
+    reference_contigs: FnvHashMap<String, usize>,
+    /// Reference batches still referenced by the active set, `first_batch` is the id of the front one
+    reference_batches: VecDeque<RefBatch>,
+    first_batch: usize,
+    /// (batch id, row) of the next unread reference interval
+    cursor: (usize, usize),
+    reference_done: bool,
+    last_reference: Option<(usize, i32)>,
+    rank: Option<usize>,
+    active: Vec<RefInterval>,
+    /// Evicted reference interval with the greatest end - the left candidate for nearest
+    last_ended: Option<RefInterval>,
+    last_query: Option<(String, i32)>,
+    seen_query_contigs: FnvHashSet<String>,
+}
+
+impl SweepState {
+    fn starts_before(&self, reference_start: i32, query_end: i32) -> bool {
+        if self.strict {
+            reference_start < query_end
+        } else {
+            reference_start <= query_end
+        }
+    }
+
+    fn ends_after(&self, reference_end: i32, query_start: i32) -> bool {
+        if self.strict {
+            reference_end > query_start
+        } else {
+            reference_end >= query_start
+        }
+    }
+
+    fn load_reference_batch(&mut self, batch: RecordBatch) -> Result<RefBatch> {
+        let num_rows = batch.num_rows();
+        let mut ranks = Vec::with_capacity(num_rows);
+        let mut starts = Vec::with_capacity(num_rows);
+        let mut ends = Vec::with_capacity(num_rows);
+        {
+            let (contig_arr, start_arr, end_arr) =
+                get_join_col_arrays(&batch, self.reference_columns.clone());
+            for i in 0..num_rows {
+                let contig = contig_arr.value(i);
+                let start = start_arr.value(i);
+                let rank = match self.reference_contigs.get(contig) {
+                    Some(rank) => *rank,
+                    None => return Err(not_sorted("reference", contig, start)),
+                };
+                if let Some((last_rank, last_start)) = self.last_reference {
+                    if rank < last_rank || (rank == last_rank && start < last_start) {
+                        return Err(not_sorted("reference", contig, start));
+                    }
+                }
+                self.last_reference = Some((rank, start));
+                ranks.push(rank);
+                starts.push(start);
+                ends.push(end_arr.value(i));
+            }
+        }
+        Ok(RefBatch {
+            batch,
+            ranks,
+            starts,
+            ends,
+        })
+    }
+
+    /// Returns the contig rank and coordinates of the next unread reference interval
+    /// without consuming it, pulling a new batch from the reference stream when needed.
+    async fn peek_reference(&mut self) -> Result<Option<(usize, RefInterval)>> {
+        loop {
+            let (batch_id, row) = self.cursor;
+            if let Some(ref_batch) = self.reference_batches.get(batch_id - self.first_batch) {
+                if row < ref_batch.starts.len() {
+                    return Ok(Some((
+                        ref_batch.ranks[row],
+                        RefInterval {
+                            start: ref_batch.starts[row],
+                            end: ref_batch.ends[row],
+                            batch: batch_id,
+                            row,
+                        },
+                    )));
+                }
+                self.cursor = (batch_id + 1, 0);
+                continue;
+            }
+            if self.reference_done {
+                return Ok(None);
+            }
+            match self.reference.next().await {
+                Some(batch) => {
+                    let ref_batch = self.load_reference_batch(batch?)?;
+                    self.reference_batches.push_back(ref_batch);
+                },
+                None => self.reference_done = true,
+            }
+        }
+    }
+
+    /// Moves the sweep line to the query interval: reference intervals starting before
+    /// its end enter the active set and the ones that can no longer overlap are evicted.
+    async fn advance(&mut self, rank: usize, start: i32, end: i32) -> Result<()> {
+        if self.rank != Some(rank) {
+            self.active.clear();
+            self.last_ended = None;
+            self.rank = Some(rank);
+        }
+        while let Some((ref_rank, interval)) = self.peek_reference().await? {
+            if ref_rank > rank || (ref_rank == rank && !self.starts_before(interval.start, end)) {
+                break;
+            }
+            self.cursor.1 += 1;
+            if ref_rank == rank {
+                self.active.push(interval);
+            }
+        }
+        let strict = self.strict;
+        let mut last_ended = self.last_ended.take();
+        self.active.retain(|r| {
+            let alive = if strict {
+                r.end > start
+            } else {
+                r.end >= start
+            };
+            if !alive && last_ended.map_or(true, |l| r.end >= l.end) {
+                last_ended = Some(*r);
+            }
+            alive
+        });
+        self.last_ended = last_ended;
+        Ok(())
+    }
+
+    fn check_query_order(&mut self, contig: &str, start: i32) -> Result<()> {
+        match &mut self.last_query {
+            Some((last_contig, last_start)) if last_contig == contig => {
+                if start < *last_start {
+                    return Err(not_sorted("query", contig, start));
+                }
+                *last_start = start;
+            },
+            _ => {
+                if !self.seen_query_contigs.insert(contig.to_string()) {
+                    return Err(not_sorted("query", contig, start));
+                }
+                self.last_query = Some((contig.to_string(), start));
+            },
+        }
+        Ok(())
+    }
+
+    /// Coverage with the semantics of `udtf::get_coverage`: overlapping active
+    /// intervals are merged and each merged piece contributes its
+    /// `node_coverage`, with strict queries shrunk by one base on both sides.
+    fn covered_bases(&self, start: i32, end: i32) -> i64 {
+        let (start, end) = if self.strict {
+            (start + 1, end - 1)
+        } else {
+            (start, end)
+        };
+        // active intervals are kept in start order, so merged pieces are too
+        let mut covered = 0i64;
+        let mut current: Option<(i32, i32)> = None;
+        for r in self
+            .active
+            .iter()
+            .filter(|r| r.start <= end && r.end >= start)
+        {
+            current = match current {
+                Some((first, last)) if r.start <= last => Some((first, max(last, r.end))),
+                Some((first, last)) => {
+                    covered += node_coverage(start, end, first, last) as i64;
+                    Some((r.start, r.end))
+                },
+                None => Some((r.start, r.end)),
+            };
+        }
+        if let Some((first, last)) = current {
+            covered += node_coverage(start, end, first, last) as i64;
+        }
+        covered
+    }
+
+    async fn nearest(&mut self, start: i32, end: i32) -> Result<Option<(RefInterval, i64)>> {
+        if let Some(r) = self
+            .active
+            .iter()
+            .find(|r| self.starts_before(r.start, end) && self.ends_after(r.end, start))
+        {
+            return Ok(Some((*r, 0)));
+        }
+        let rank = self.rank;
+        let right = match self
+            .active
+            .iter()
+            .find(|r| !self.starts_before(r.start, end))
+        {
+            Some(r) => Some(*r),
+            None => match self.peek_reference().await? {
+                Some((ref_rank, r)) if Some(ref_rank) == rank => Some(r),
+                _ => None,
+            },
+        };
+        let left = self.last_ended;
+        Ok(match (left, right) {
+            (Some(l), Some(r)) if (r.start - end) < (start - l.end) => {
+                Some((r, (r.start - end) as i64))
+            },
+            (Some(l), _) => Some((l, (start - l.end) as i64)),
+            (None, Some(r)) => Some((r, (r.start - end) as i64)),
+            (None, None) => None,
+        })
+    }
+
+    async fn process_batch(&mut self, batch: RecordBatch) -> Result<RecordBatch> {
+        let num_rows = batch.num_rows();
+        let mut values: Vec<i64> = Vec::with_capacity(num_rows);
+        let mut query_rows: Vec<u32> = Vec::new();
+        let mut reference_rows: Vec<(usize, usize)> = Vec::new();
+        {
+            let (contig_arr, start_arr, end_arr) =
+                get_join_col_arrays(&batch, self.query_columns.clone());
+            for i in 0..num_rows {
+                let contig = contig_arr.value(i);
+                let start = start_arr.value(i);
+                let end = end_arr.value(i);
+                self.check_query_order(contig, start)?;
+                let rank = match self.reference_contigs.get(contig) {
+                    Some(rank) => *rank,
+                    None => {
+                        if matches!(
+                            self.range_op,
+                            RangeOp::Coverage | RangeOp::CountOverlapsNaive
+                        ) {
+                            values.push(0);
+                        }
+                        continue;
+                    },
+                };
+                if self.rank.is_some_and(|current| rank < current) {
+                    return Err(DataFusionError::Execution(format!(
+                        "Sweep-line requires both inputs to use the same contig order, but {} comes earlier in the reference table",
+                        contig
+                    )));
+                }
+                self.advance(rank, start, end).await?;
+                match self.range_op {
+                    RangeOp::Overlap => {
+                        for r in self
+                            .active
+                            .iter()
+                            .filter(|r| self.starts_before(r.start, end))
+                        {
+                            query_rows.push(i as u32);
+                            reference_rows.push((r.batch - self.first_batch, r.row));
+                        }
+                    },
+                    RangeOp::Nearest => {
+                        if let Some((r, distance)) = self.nearest(start, end).await? {
+                            query_rows.push(i as u32);
+                            reference_rows.push((r.batch - self.first_batch, r.row));
+                            values.push(distance);
+                        }
+                    },
+                    RangeOp::Coverage => values.push(self.covered_bases(start, end)),
+                    _ => values.push(
+                        self.active
+                            .iter()
+                            .filter(|r| self.starts_before(r.start, end))
+                            .count() as i64,
+                    ),
+                }
+            }
+        }
+        let output = self.build_output(&batch, values, query_rows, reference_rows)?;
+        self.release_reference_batches();
+        Ok(output)
+    }
+
+    fn take_reference(&self, indices: &[(usize, usize)]) -> Result<Vec<ArrayRef>> {
+        (0..self.reference_schema.fields().len())
+            .map(|c| {
+                if indices.is_empty() {
+                    return Ok(new_empty_array(self.reference_schema.field(c).data_type()));
+                }
+                let arrays: Vec<&dyn Array> = self
+                    .reference_batches
+                    .iter()
+                    .map(|b| b.batch.column(c).as_ref())
+                    .collect();
+                Ok(interleave(&arrays, indices)?)
+            })
+            .collect()
+    }
+
+    fn build_output(
+        &self,
+        batch: &RecordBatch,
+        values: Vec<i64>,
+        query_rows: Vec<u32>,
+        reference_rows: Vec<(usize, usize)>,
+    ) -> Result<RecordBatch> {
+        let columns = match self.range_op {
+            RangeOp::Overlap | RangeOp::Nearest => {
+                let indices = UInt32Array::from(query_rows);
+                let query_columns = batch
+                    .columns()
+                    .iter()
+                    .map(|c| take(c.as_ref(), &indices, None))
+                    .collect::<std::result::Result<Vec<ArrayRef>, _>>()?;
+                let reference_columns = self.take_reference(&reference_rows)?;
+                if self.range_op == RangeOp::Overlap {
+                    [reference_columns, query_columns].concat()
+                } else {
+                    let mut columns = [query_columns, reference_columns].concat();
+                    columns.push(Arc::new(Int64Array::from(values)));
+                    columns
+                }
+            },
+            _ => {
+                let mut columns = batch.columns().to_vec();
+                columns.push(Arc::new(Int64Array::from(values)));
+                columns
+            },
+        };
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+
+    /// Drops reference batches that no active interval (nor the read cursor) points into.
+    fn release_reference_batches(&mut self) {
+        let needed = self
+            .active
+            .iter()
+            .chain(self.last_ended.iter())
+            .map(|r| r.batch)
+            .fold(self.cursor.0, min);
+        while self.first_batch < needed && !self.reference_batches.is_empty() {
+            self.reference_batches.pop_front();
+            self.first_batch += 1;
+        }
+    }
+}
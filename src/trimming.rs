@@ -0,0 +1,621 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::array::{ArrayBuilder, StringBuilder, StructBuilder};
+use arrow_array::{Array, ArrayRef};
+use arrow_schema::{DataType, Field, Fields};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{
+    ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use datafusion::scalar::ScalarValue;
+use exon::ExonSession;
+
+use crate::quality_control::string_array;
+
+const DEFAULT_PHRED_OFFSET: i64 = 33;
+/// Default maximum edits per aligned adapter base, as in cutadapt.
+const DEFAULT_ADAPTER_ERROR_RATE: f64 = 0.1;
+/// Default minimum adapter overlap, as in cutadapt.
+const DEFAULT_ADAPTER_MIN_OVERLAP: usize = 3;
+/// Default minimum overlap of paired reads, as in fastp.
+const DEFAULT_PAIRED_MIN_OVERLAP: usize = 30;
+
+/// Penalty of a base other than the poly-X base, as in cutadapt's poly-A
+/// trimming.
+const POLY_X_MISMATCH_PENALTY: i64 = 2;
+
+fn trimmed_read_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("sequence", DataType::Utf8, true),
+        Field::new("quality_scores", DataType::Utf8, true),
+    ])
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TrimMethod {
+    /// Trimmomatic SLIDINGWINDOW: cuts at the first window with a mean
+    /// quality below the required one.
+    SlidingWindow,
+    /// Trimmomatic LEADING: removes leading bases below a quality.
+    Leading,
+    /// Trimmomatic TRAILING: removes trailing bases below a quality.
+    Trailing,
+    /// The BWA `-q` algorithm, cutting the 3' end at the position
+    /// maximising the sum of `cutoff - quality`.
+    Bwa,
+    /// Removes a poly-X tail of at least a minimum length, tolerating
+    /// occasional other bases.
+    PolyX(u8),
+    /// Removes a fixed number of bases from the head and the tail.
+    Crop,
+}
+
+impl TrimMethod {
+    fn name(&self) -> &'static str {
+        match self {
+            TrimMethod::SlidingWindow => "trim_sliding_window",
+            TrimMethod::Leading => "trim_leading",
+            TrimMethod::Trailing => "trim_trailing",
+            TrimMethod::Bwa => "trim_quality_bwa",
+            TrimMethod::PolyX(b'G') => "trim_poly_g",
+            TrimMethod::PolyX(_) => "trim_poly_a",
+            TrimMethod::Crop => "trim_crop",
+        }
+    }
+
+    /// Number of integer parameters following the sequence and quality.
+    fn parameters(&self) -> usize {
+        match self {
+            TrimMethod::SlidingWindow | TrimMethod::Crop => 2,
+            _ => 1,
+        }
+    }
+
+    /// Whether the method reads quality scores, and so takes an optional
+    /// Phred offset as its last argument.
+    fn uses_quality(&self) -> bool {
+        !matches!(self, TrimMethod::PolyX(_) | TrimMethod::Crop)
+    }
+
+    /// Returns the `start..end` range of the read to keep.
+    fn keep_range(
+        &self,
+        sequence: &[u8],
+        quality: &[u8],
+        params: &[i64],
+        offset: i64,
+    ) -> (usize, usize) {
+        let len = sequence.len();
+        let score = |i: usize| quality[i] as i64 - offset;
+        match self {
+            TrimMethod::SlidingWindow => {
+                let (window, required) = (params[0] as usize, params[1]);
+                let window = window.min(len);
+                if window == 0 {
+                    return (0, len);
+                }
+                let mut total: i64 = (0..window).map(score).sum();
+                let mut start = 0;
+                loop {
+                    if total < required * window as i64 {
+                        let mut end = start;
+                        while end < start + window && score(end) >= required {
+                            end += 1;
+                        }
+                        return (0, end);
+                    }
+                    if start + window >= len {
+                        return (0, len);
+                    }
+                    total += score(start + window) - score(start);
+                    start += 1;
+                }
+            },
+            TrimMethod::Leading => {
+                let start = (0..len).find(|&i| score(i) >= params[0]).unwrap_or(len);
+                (start, len)
+            },
+            TrimMethod::Trailing => {
+                let end = (0..len)
+                    .rev()
+                    .find(|&i| score(i) >= params[0])
+                    .map_or(0, |i| i + 1);
+                (0, end)
+            },
+            TrimMethod::Bwa => {
+                let (mut sum, mut max_sum, mut end) = (0, 0, len);
+                for i in (0..len).rev() {
+                    sum += params[0] - score(i);
+                    if sum < 0 {
+                        break;
+                    }
+                    if sum > max_sum {
+                        max_sum = sum;
+                        end = i;
+                    }
+                }
+                (0, end)
+            },
+            TrimMethod::PolyX(base) => {
+                let (mut score, mut best_score, mut end) = (0, 0, len);
+                for i in (0..len).rev() {
+                    score += if sequence[i].to_ascii_uppercase() == *base {
+                        1
+                    } else {
+                        -POLY_X_MISMATCH_PENALTY
+                    };
+                    if score < 0 {
+                        break;
+                    }
+                    if score > best_score {
+                        best_score = score;
+                        end = i;
+                    }
+                }
+                if ((len - end) as i64) < params[0] {
+                    end = len;
+                }
+                (0, end)
+            },
+            TrimMethod::Crop => {
+                let start = (params[0] as usize).min(len);
+                let end = len.saturating_sub(params[1] as usize).max(start);
+                (start, end)
+            },
+        }
+    }
+}
+
+fn int_argument(value: &ColumnarValue, function: &str) -> Result<i64> {
+    match value {
+        ColumnarValue::Scalar(ScalarValue::Int64(Some(value))) if *value >= 0 => Ok(*value),
+        _ => Err(DataFusionError::Plan(format!(
+            "Integer parameters of {} must be non-negative literals",
+            function
+        ))),
+    }
+}
+
+fn float_argument(value: &ColumnarValue, function: &str) -> Result<f64> {
+    match value {
+        ColumnarValue::Scalar(ScalarValue::Float64(Some(value))) if (0.0..1.0).contains(value) => {
+            Ok(*value)
+        },
+        _ => Err(DataFusionError::Plan(format!(
+            "The error rate of {} must be a literal between 0 and 1",
+            function
+        ))),
+    }
+}
+
+/// Converts the leading string arguments to arrays of the same length.
+fn string_arguments(args: &[ColumnarValue], count: usize) -> Result<Vec<ArrayRef>> {
+    let rows = args
+        .iter()
+        .find_map(|arg| match arg {
+            ColumnarValue::Array(array) => Some(array.len()),
+            ColumnarValue::Scalar(_) => None,
+        })
+        .unwrap_or(1);
+    args[..count]
+        .iter()
+        .map(|arg| arg.clone().into_array(rows))
+        .collect()
+}
+
+pub(crate) fn check_lengths(sequence: &[u8], quality: &[u8]) -> Result<()> {
+    if sequence.len() != quality.len() {
+        return Err(DataFusionError::Execution(format!(
+            "Sequence and quality lengths differ ({} and {})",
+            sequence.len(),
+            quality.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Appends the values of a trimmed read to a struct of string fields, or a
+/// null struct for `None`.
+fn append_read(builder: &mut StructBuilder, values: Option<&[&[u8]]>) {
+    match values {
+        Some(values) => {
+            for (i, value) in values.iter().enumerate() {
+                builder
+                    .field_builder::<StringBuilder>(i)
+                    .unwrap()
+                    .append_value(String::from_utf8_lossy(value));
+            }
+            builder.append(true);
+        },
+        None => {
+            for i in 0..builder.num_fields() {
+                builder
+                    .field_builder::<StringBuilder>(i)
+                    .unwrap()
+                    .append_null();
+            }
+            builder.append(false);
+        },
+    }
+}
+
+fn string_struct_builder(fields: Fields) -> StructBuilder {
+    let struct_builders = fields
+        .iter()
+        .map(|_| Box::new(StringBuilder::new()) as Box<dyn ArrayBuilder>)
+        .collect::<Vec<Box<dyn ArrayBuilder>>>();
+    StructBuilder::new(fields, struct_builders)
+}
+
+/// `trim_*(sequence, quality, params...)` returns a struct of the trimmed
+/// `sequence` and `quality_scores`, so that trimming methods can be chained
+/// in SQL.
+#[derive(Debug)]
+struct TrimUdf {
+    signature: Signature,
+    method: TrimMethod,
+}
+
+impl TrimUdf {
+    fn new(method: TrimMethod) -> Self {
+        let arguments = vec![DataType::Utf8; 2]
+            .into_iter()
+            .chain(vec![DataType::Int64; method.parameters()])
+            .collect::<Vec<DataType>>();
+        let signature = if method.uses_quality() {
+            let mut with_offset = arguments.clone();
+            with_offset.push(DataType::Int64);
+            Signature::one_of(
+                vec![
+                    TypeSignature::Exact(arguments),
+                    TypeSignature::Exact(with_offset),
+                ],
+                Volatility::Immutable,
+            )
+        } else {
+            Signature::exact(arguments, Volatility::Immutable)
+        };
+        Self { signature, method }
+    }
+}
+
+impl ScalarUDFImpl for TrimUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.method.name()
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Struct(trimmed_read_fields()))
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let params = args[2..2 + self.method.parameters()]
+            .iter()
+            .map(|arg| int_argument(arg, self.name()))
+            .collect::<Result<Vec<i64>>>()?;
+        if matches!(self.method, TrimMethod::SlidingWindow) && params[0] == 0 {
+            return Err(DataFusionError::Plan(
+                "The window size of trim_sliding_window must be positive".to_string(),
+            ));
+        }
+        let offset = match args.get(2 + self.method.parameters()) {
+            Some(arg) => int_argument(arg, self.name())?,
+            None => DEFAULT_PHRED_OFFSET,
+        };
+        let arrays = string_arguments(args, 2)?;
+        let sequences = string_array(&arrays[0])?;
+        let qualities = string_array(&arrays[1])?;
+
+        let mut builder = string_struct_builder(trimmed_read_fields());
+        for (sequence, quality) in sequences.iter().zip(qualities.iter()) {
+            let (Some(sequence), Some(quality)) = (sequence, quality) else {
+                append_read(&mut builder, None);
+                continue;
+            };
+            let (sequence, quality) = (sequence.as_bytes(), quality.as_bytes());
+            check_lengths(sequence, quality)?;
+            let (start, end) = self.method.keep_range(sequence, quality, &params, offset);
+            append_read(
+                &mut builder,
+                Some(&[&sequence[start..end], &quality[start..end]]),
+            );
+        }
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+fn bases_match(a: u8, b: u8) -> bool {
+    let (a, b) = (a.to_ascii_uppercase(), b.to_ascii_uppercase());
+    a == b || a == b'N' || b == b'N'
+}
+
+/// Finds the start of a 3' adapter in a read with a semi-global alignment,
+/// as cutadapt does for regular 3' adapters: the alignment starts at the
+/// beginning of the adapter anywhere in the read, and either the whole
+/// adapter is aligned or the read ends within it. Alignments covering at
+/// least `min_overlap` adapter bases with at most `max_error_rate` edits per
+/// aligned adapter base are accepted, preferring the most matches and then
+/// the fewest errors. N matches any base.
+fn find_adapter(
+    read: &[u8],
+    adapter: &[u8],
+    max_error_rate: f64,
+    min_overlap: usize,
+) -> Option<usize> {
+    let (n, m) = (read.len(), adapter.len());
+    // Edit distances and read start positions of the previous and current
+    // adapter rows, for each read position.
+    let mut previous: Vec<(usize, usize)> = (0..=n).map(|j| (0, j)).collect();
+    let mut current = vec![(0, 0); n + 1];
+    // (matches, errors, start) of the best accepted alignment
+    let mut best: Option<(usize, usize, usize)> = None;
+    let mut accept = |length: usize, (errors, start): (usize, usize)| {
+        if length < min_overlap || errors as f64 > max_error_rate * length as f64 {
+            return;
+        }
+        let matches = length.saturating_sub(errors);
+        let better = match best {
+            Some((best_matches, best_errors, _)) => {
+                matches > best_matches || (matches == best_matches && errors < best_errors)
+            },
+            None => true,
+        };
+        if better {
+            best = Some((matches, errors, start));
+        }
+    };
+    for i in 1..=m {
+        current[0] = (i, 0);
+        for j in 1..=n {
+            let cost = usize::from(!bases_match(adapter[i - 1], read[j - 1]));
+            let diagonal = (previous[j - 1].0 + cost, previous[j - 1].1);
+            let deletion = (previous[j].0 + 1, previous[j].1);
+            let insertion = (current[j - 1].0 + 1, current[j - 1].1);
+            current[j] = [deletion, insertion]
+                .into_iter()
+                .fold(
+                    diagonal,
+                    |best, cell| if cell.0 < best.0 { cell } else { best },
+                );
+        }
+        if i < m {
+            accept(i, current[n]);
+        } else {
+            for cell in current.iter().skip(1) {
+                accept(m, *cell);
+            }
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    best.map(|(_, _, start)| start)
+}
+
+/// `trim_adapter(sequence, quality, adapter[, max_error_rate, min_overlap])`
+/// removes a 3' adapter and everything after it from each read.
+#[derive(Debug)]
+struct AdapterTrimUdf {
+    signature: Signature,
+}
+
+impl ScalarUDFImpl for AdapterTrimUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "trim_adapter"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Struct(trimmed_read_fields()))
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let adapter = match &args[2] {
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(adapter))) if !adapter.is_empty() => {
+                adapter.as_bytes()
+            },
+            _ => {
+                return Err(DataFusionError::Plan(
+                    "The adapter of trim_adapter must be a non-empty string literal".to_string(),
+                ))
+            },
+        };
+        let max_error_rate = match args.get(3) {
+            Some(arg) => float_argument(arg, self.name())?,
+            None => DEFAULT_ADAPTER_ERROR_RATE,
+        };
+        let min_overlap = match args.get(4) {
+            Some(arg) => int_argument(arg, self.name())? as usize,
+            None => DEFAULT_ADAPTER_MIN_OVERLAP,
+        };
+        let arrays = string_arguments(args, 2)?;
+        let sequences = string_array(&arrays[0])?;
+        let qualities = string_array(&arrays[1])?;
+
+        let mut builder = string_struct_builder(trimmed_read_fields());
+        for (sequence, quality) in sequences.iter().zip(qualities.iter()) {
+            let (Some(sequence), Some(quality)) = (sequence, quality) else {
+                append_read(&mut builder, None);
+                continue;
+            };
+            let (sequence, quality) = (sequence.as_bytes(), quality.as_bytes());
+            check_lengths(sequence, quality)?;
+            let end = find_adapter(sequence, adapter, max_error_rate, min_overlap)
+                .unwrap_or(sequence.len());
+            append_read(&mut builder, Some(&[&sequence[..end], &quality[..end]]));
+        }
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+fn paired_read_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("seq1", DataType::Utf8, true),
+        Field::new("qual1", DataType::Utf8, true),
+        Field::new("seq2", DataType::Utf8, true),
+        Field::new("qual2", DataType::Utf8, true),
+    ])
+}
+
+fn complement(base: u8) -> u8 {
+    match base.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        other => other,
+    }
+}
+
+/// Finds the insert length of a read pair whose insert is shorter than the
+/// reads, so that both reads run into adapters. The start of read 1 then
+/// overlaps the end of the reverse complement of read 2; the longest
+/// overlap of at least `min_overlap` bases with at most `max_error_rate`
+/// mismatches per base is taken, as in fastp's overlap analysis.
+fn find_insert_length(
+    read1: &[u8],
+    read2: &[u8],
+    max_error_rate: f64,
+    min_overlap: usize,
+) -> Option<usize> {
+    let reverse2 = read2
+        .iter()
+        .rev()
+        .map(|base| complement(*base))
+        .collect::<Vec<u8>>();
+    let longest = read1.len().min(read2.len());
+    (min_overlap.max(1)..=longest).rev().find(|&length| {
+        let max_errors = (max_error_rate * length as f64) as usize;
+        read1[..length]
+            .iter()
+            .zip(&reverse2[reverse2.len() - length..])
+            .filter(|(a, b)| !bases_match(**a, **b))
+            .take(max_errors + 1)
+            .count()
+            <= max_errors
+    })
+}
+
+/// `trim_adapter_paired(seq1, qual1, seq2, qual2[, max_error_rate,
+/// min_overlap])` detects adapter read-through from the overlap of the two
+/// reads of a pair, without knowing the adapter sequences, and trims both
+/// reads to the insert length.
+#[derive(Debug)]
+struct PairedAdapterTrimUdf {
+    signature: Signature,
+}
+
+impl ScalarUDFImpl for PairedAdapterTrimUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "trim_adapter_paired"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Struct(paired_read_fields()))
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let max_error_rate = match args.get(4) {
+            Some(arg) => float_argument(arg, self.name())?,
+            None => DEFAULT_ADAPTER_ERROR_RATE,
+        };
+        let min_overlap = match args.get(5) {
+            Some(arg) => int_argument(arg, self.name())? as usize,
+            None => DEFAULT_PAIRED_MIN_OVERLAP,
+        };
+        let arrays = string_arguments(args, 4)?;
+        let columns = arrays
+            .iter()
+            .map(string_array)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut builder = string_struct_builder(paired_read_fields());
+        for row in 0..columns[0].len() {
+            if columns.iter().any(|column| column.is_null(row)) {
+                append_read(&mut builder, None);
+                continue;
+            }
+            let [seq1, qual1, seq2, qual2] = [0, 1, 2, 3].map(|i| columns[i].value(row).as_bytes());
+            check_lengths(seq1, qual1)?;
+            check_lengths(seq2, qual2)?;
+            let (end1, end2) = match find_insert_length(seq1, seq2, max_error_rate, min_overlap) {
+                Some(length) => (length, length),
+                None => (seq1.len(), seq2.len()),
+            };
+            append_read(
+                &mut builder,
+                Some(&[&seq1[..end1], &qual1[..end1], &seq2[..end2], &qual2[..end2]]),
+            );
+        }
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+pub(crate) fn register_trimming_udfs(ctx: &ExonSession) {
+    for method in [
+        TrimMethod::SlidingWindow,
+        TrimMethod::Leading,
+        TrimMethod::Trailing,
+        TrimMethod::Bwa,
+        TrimMethod::PolyX(b'G'),
+        TrimMethod::PolyX(b'A'),
+        TrimMethod::Crop,
+    ] {
+        ctx.session
+            .register_udf(ScalarUDF::from(TrimUdf::new(method)));
+    }
+    ctx.session.register_udf(ScalarUDF::from(AdapterTrimUdf {
+        signature: Signature::one_of(
+            vec![
+                TypeSignature::Exact(vec![DataType::Utf8; 3]),
+                TypeSignature::Exact(vec![
+                    DataType::Utf8,
+                    DataType::Utf8,
+                    DataType::Utf8,
+                    DataType::Float64,
+                    DataType::Int64,
+                ]),
+            ],
+            Volatility::Immutable,
+        ),
+    }));
+    ctx.session
+        .register_udf(ScalarUDF::from(PairedAdapterTrimUdf {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8; 4]),
+                    TypeSignature::Exact(vec![
+                        DataType::Utf8,
+                        DataType::Utf8,
+                        DataType::Utf8,
+                        DataType::Utf8,
+                        DataType::Float64,
+                        DataType::Int64,
+                    ]),
+                ],
+                Volatility::Immutable,
+            ),
+        }));
+}
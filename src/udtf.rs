@@ -4,7 +4,7 @@ use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
 
 use arrow_array::{
-    Array, GenericStringArray, Int32Array, Int64Array, RecordBatch, StringViewArray,
+    Array, GenericStringArray, Int32Array, Int64Array, RecordBatch, StringViewArray, UInt32Array,
 };
 use arrow_schema::{DataType, Field, FieldRef, Schema, SchemaRef};
 use async_trait::async_trait;
@@ -215,7 +215,7 @@ impl ExecutionPlan for CountOverlapsExec {
 
 type IntervalHashMap = FnvHashMap<String, Vec<Interval<()>>>;
 
-fn merge_intervals(mut intervals: Vec<Interval<()>>) -> Vec<Interval<()>> {
+pub(crate) fn merge_intervals(mut intervals: Vec<Interval<()>>) -> Vec<Interval<()>> {
     // Return early if there are no intervals.
     if intervals.is_empty() {
         return vec![];
@@ -278,14 +278,14 @@ fn build_coitree_from_batches(
     trees
 }
 
-enum ContigArray<'a> {
+pub(crate) enum ContigArray<'a> {
     GenericString(&'a GenericStringArray<i64>),
     Utf8View(&'a StringViewArray),
     Utf8(&'a GenericStringArray<i32>),
 }
 
 impl ContigArray<'_> {
-    fn value(&self, i: usize) -> &str {
+    pub(crate) fn value(&self, i: usize) -> &str {
         match self {
             ContigArray::GenericString(arr) => arr.value(i),
             ContigArray::Utf8View(arr) => arr.value(i),
@@ -294,21 +294,23 @@ impl ContigArray<'_> {
     }
 }
 
-enum PosArray<'a> {
+pub(crate) enum PosArray<'a> {
     Int32(&'a Int32Array),
     Int64(&'a Int64Array),
+    UInt32(&'a UInt32Array),
 }
 
 impl PosArray<'_> {
-    fn value(&self, i: usize) -> i32 {
+    pub(crate) fn value(&self, i: usize) -> i32 {
         match self {
             PosArray::Int32(arr) => arr.value(i),
             PosArray::Int64(arr) => arr.value(i) as i32,
+            PosArray::UInt32(arr) => arr.value(i) as i32,
         }
     }
 }
 
-fn get_join_col_arrays(
+pub(crate) fn get_join_col_arrays(
     batch: &RecordBatch,
     columns: (String, String, String),
 ) -> (ContigArray<'_>, PosArray<'_>, PosArray<'_>) {
@@ -362,6 +364,15 @@ fn get_join_col_arrays(
                 .unwrap();
             PosArray::Int64(start_arr)
         },
+        DataType::UInt32 => {
+            let start_arr = batch
+                .column_by_name(&columns.1)
+                .unwrap()
+                .as_any()
+                .downcast_ref::<UInt32Array>()
+                .unwrap();
+            PosArray::UInt32(start_arr)
+        },
         _ => todo!(),
     };
 
@@ -384,17 +395,30 @@ fn get_join_col_arrays(
                 .unwrap();
             PosArray::Int64(end_arr)
         },
+        DataType::UInt32 => {
+            let end_arr = batch
+                .column_by_name(&columns.2)
+                .unwrap()
+                .as_any()
+                .downcast_ref::<UInt32Array>()
+                .unwrap();
+            PosArray::UInt32(end_arr)
+        },
         _ => todo!(),
     };
 
     (contig_arr, start_arr, end_arr)
 }
 
-fn get_coverage(tree: &COITree<(), u32>, start: i32, end: i32) -> i32 {
+/// Coverage of the `start`..`end` query by a merged interval `first`..`last`.
+pub(crate) fn node_coverage(start: i32, end: i32, first: i32, last: i32) -> i32 {
+    max(1, min(end + 1, last) - max(start - 1, first))
+}
+
+pub(crate) fn get_coverage(tree: &COITree<(), u32>, start: i32, end: i32) -> i32 {
     let mut coverage = 0;
     tree.query(start, end, |node| {
-        let overlap = max(1, min(end + 1, node.last) - max(start - 1, node.first));
-        coverage += overlap;
+        coverage += node_coverage(start, end, node.first, node.last);
     });
     coverage
 }
@@ -0,0 +1,406 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use arrow::array::{ArrayBuilder, StringBuilder, StructBuilder, UInt64Builder};
+use arrow::record_batch::RecordBatch;
+use arrow_array::{Array, ArrayRef, UInt64Array};
+use arrow_schema::{DataType, Field, Fields, Schema};
+use datafusion::catalog_common::TableReference;
+use datafusion::dataframe::DataFrame;
+use datafusion::datasource::MemTable;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{
+    ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use datafusion::scalar::ScalarValue;
+use exon::ExonSession;
+use log::{debug, info};
+
+use crate::quality_control::string_array;
+use crate::trimming::check_lengths;
+
+const UMI_GROUPS_TABLE: &str = "umi_groups";
+const DEFAULT_UMI_SEPARATOR: &str = "_";
+
+fn extracted_read_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("umi", DataType::Utf8, true),
+        Field::new("cell_barcode", DataType::Utf8, true),
+        Field::new("sequence", DataType::Utf8, true),
+        Field::new("quality_scores", DataType::Utf8, true),
+    ])
+}
+
+/// Checks an umi_tools-style string pattern, in which `N` marks UMI bases,
+/// `C` cell barcode bases and `X` bases kept in the read.
+fn check_pattern(pattern: &str) -> Result<()> {
+    if pattern.is_empty() || !pattern.bytes().all(|b| matches!(b, b'N' | b'C' | b'X')) {
+        return Err(DataFusionError::Plan(format!(
+            "UMI pattern {} must only consist of N, C and X",
+            pattern
+        )));
+    }
+    Ok(())
+}
+
+/// `extract_umi(sequence, quality, pattern)` moves the UMI and cell barcode
+/// bases at the start of a read, as marked by an umi_tools-style pattern such
+/// as `NNNNNNXXXX`, out of the read. Reads shorter than the pattern are null
+/// and reads whose sequence and quality lengths differ are an error.
+#[derive(Debug)]
+struct ExtractUmiUdf {
+    signature: Signature,
+}
+
+impl ScalarUDFImpl for ExtractUmiUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "extract_umi"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Struct(extracted_read_fields()))
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let pattern = match &args[2] {
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(pattern))) => pattern,
+            _ => {
+                return Err(DataFusionError::Plan(
+                    "The pattern of extract_umi must be a string literal".to_string(),
+                ))
+            },
+        };
+        check_pattern(pattern)?;
+        let pattern = pattern.as_bytes();
+        let rows = match (&args[0], &args[1]) {
+            (ColumnarValue::Array(array), _) | (_, ColumnarValue::Array(array)) => array.len(),
+            _ => 1,
+        };
+        let sequences = string_array(&args[0].clone().into_array(rows)?)?;
+        let qualities = string_array(&args[1].clone().into_array(rows)?)?;
+
+        let struct_builders: Vec<Box<dyn ArrayBuilder>> = (0..4)
+            .map(|_| Box::new(StringBuilder::new()) as Box<dyn ArrayBuilder>)
+            .collect();
+        let mut builder = StructBuilder::new(extracted_read_fields(), struct_builders);
+        let (mut umi, mut cell, mut sequence_out, mut quality_out) =
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for (sequence, quality) in sequences.iter().zip(qualities.iter()) {
+            let values = match (sequence, quality) {
+                (Some(sequence), Some(quality)) if sequence.len() >= pattern.len() => {
+                    let (sequence, quality) = (sequence.as_bytes(), quality.as_bytes());
+                    check_lengths(sequence, quality)?;
+                    umi.clear();
+                    cell.clear();
+                    sequence_out.clear();
+                    quality_out.clear();
+                    for (i, kind) in pattern.iter().enumerate() {
+                        match kind {
+                            b'N' => umi.push(sequence[i]),
+                            b'C' => cell.push(sequence[i]),
+                            _ => {
+                                sequence_out.push(sequence[i]);
+                                quality_out.push(quality[i]);
+                            },
+                        }
+                    }
+                    sequence_out.extend_from_slice(&sequence[pattern.len()..]);
+                    quality_out.extend_from_slice(&quality[pattern.len()..]);
+                    Some([&umi, &cell, &sequence_out, &quality_out])
+                },
+                _ => None,
+            };
+            for i in 0..4 {
+                let field = builder.field_builder::<StringBuilder>(i).unwrap();
+                match values {
+                    Some(values) => field.append_value(String::from_utf8_lossy(values[i])),
+                    None => field.append_null(),
+                }
+            }
+            builder.append(values.is_some());
+        }
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+/// `umi_from_name(name[, separator])` returns the UMI appended to read names
+/// after the last separator, `_` by default as written by umi_tools, or null
+/// for names without one.
+#[derive(Debug)]
+struct UmiFromNameUdf {
+    signature: Signature,
+}
+
+impl ScalarUDFImpl for UmiFromNameUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "umi_from_name"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let separator = match args.get(1) {
+            Some(ColumnarValue::Scalar(ScalarValue::Utf8(Some(separator))))
+                if !separator.is_empty() =>
+            {
+                separator.as_str()
+            },
+            Some(_) => {
+                return Err(DataFusionError::Plan(
+                    "The separator of umi_from_name must be a non-empty string literal".to_string(),
+                ))
+            },
+            None => DEFAULT_UMI_SEPARATOR,
+        };
+        let names = string_array(&args[0].clone().into_array(1)?)?;
+        let mut builder = StringBuilder::new();
+        for name in names.iter() {
+            match name.and_then(|name| name.rsplit_once(separator)) {
+                Some((_, umi)) if !umi.is_empty() => builder.append_value(umi),
+                _ => builder.append_null(),
+            }
+        }
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+pub(crate) fn register_umi_udfs(ctx: &ExonSession) {
+    ctx.session.register_udf(ScalarUDF::from(ExtractUmiUdf {
+        signature: Signature::exact(vec![DataType::Utf8; 3], Volatility::Immutable),
+    }));
+    ctx.session.register_udf(ScalarUDF::from(UmiFromNameUdf {
+        signature: Signature::one_of(
+            vec![
+                TypeSignature::Exact(vec![DataType::Utf8]),
+                TypeSignature::Exact(vec![DataType::Utf8; 2]),
+            ],
+            Volatility::Immutable,
+        ),
+    }));
+}
+
+/// Returns the table with the UMI (and cell barcode, if the pattern has one)
+/// bases of `pattern` moved from the read into `umi` (and `cell_barcode`)
+/// columns.
+pub(crate) async fn do_extract_umi(
+    ctx: &ExonSession,
+    table_name: String,
+    sequence_column: String,
+    quality_column: String,
+    pattern: String,
+) -> Result<DataFrame> {
+    check_pattern(&pattern)?;
+    let schema = ctx
+        .session
+        .table(TableReference::from(table_name.clone()))
+        .await?
+        .schema()
+        .as_arrow()
+        .clone();
+    let mut columns = schema
+        .fields()
+        .iter()
+        .map(|f| {
+            if *f.name() == sequence_column {
+                format!("r['sequence'] AS \"{}\"", sequence_column)
+            } else if *f.name() == quality_column {
+                format!("r['quality_scores'] AS \"{}\"", quality_column)
+            } else {
+                format!("\"{}\"", f.name())
+            }
+        })
+        .collect::<Vec<String>>();
+    columns.push("r['umi'] AS umi".to_string());
+    if pattern.contains('C') {
+        columns.push("r['cell_barcode'] AS cell_barcode".to_string());
+    }
+    let query = format!(
+        "SELECT {} FROM (SELECT *, extract_umi(\"{}\", \"{}\", '{}') AS r FROM {}) WHERE r IS NOT NULL",
+        columns.join(", "),
+        sequence_column,
+        quality_column,
+        pattern,
+        table_name
+    );
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
+
+fn hamming_distance(a: &[u8], b: &[u8]) -> usize {
+    if a.len() != b.len() {
+        return usize::MAX;
+    }
+    a.iter().zip(b).filter(|(x, y)| x != y).count()
+}
+
+/// Clusters the UMIs of one group with the umi_tools directional method: a
+/// UMI absorbs UMIs within `max_distance` mismatches having at most half
+/// (plus one) of its count, transitively, starting from the most frequent
+/// UMIs. Returns the index of the representative UMI of each UMI.
+fn cluster_directional(umis: &[(String, u64)], max_distance: usize) -> Vec<usize> {
+    let mut order = (0..umis.len()).collect::<Vec<usize>>();
+    order.sort_by(|&a, &b| umis[b].1.cmp(&umis[a].1).then(umis[a].0.cmp(&umis[b].0)));
+    let mut representatives = vec![usize::MAX; umis.len()];
+    for &root in &order {
+        if representatives[root] != usize::MAX {
+            continue;
+        }
+        representatives[root] = root;
+        let mut queue = vec![root];
+        while let Some(node) = queue.pop() {
+            for &other in &order {
+                if representatives[other] == usize::MAX
+                    && umis[node].1 + 1 >= 2 * umis[other].1
+                    && hamming_distance(umis[node].0.as_bytes(), umis[other].0.as_bytes())
+                        <= max_distance
+                {
+                    representatives[other] = root;
+                    queue.push(other);
+                }
+            }
+        }
+    }
+    representatives
+}
+
+/// Groups reads by UMI and sequence (prefix) and clusters the UMIs of each
+/// sequence with the directional method, so that UMIs differing by
+/// sequencing errors fall into one group. Returns the reads with `group_id`
+/// and `final_umi` columns, or with `groups` false only one read per group,
+/// preferring reads carrying the representative UMI. Reads with a null UMI
+/// or sequence are dropped.
+pub(crate) async fn do_umi_dedup(
+    ctx: &ExonSession,
+    table_name: String,
+    umi_column: String,
+    sequence_column: String,
+    prefix_length: Option<usize>,
+    max_distance: usize,
+    groups: bool,
+) -> Result<DataFrame> {
+    let key = |table: &str| match prefix_length {
+        Some(length) => format!("substr({}\"{}\", 1, {})", table, sequence_column, length),
+        None => format!("{}\"{}\"", table, sequence_column),
+    };
+    // Reads without a UMI or sequence are dropped rather than clustered
+    // under an empty key
+    let query = format!(
+        "SELECT {} AS prefix, \"{}\" AS umi, count(*) AS count FROM {} WHERE \"{}\" IS NOT NULL AND \"{}\" IS NOT NULL GROUP BY 1, 2",
+        key(""),
+        umi_column,
+        table_name,
+        umi_column,
+        sequence_column
+    );
+    debug!("Query: {}", query);
+    let batches = ctx.session.sql(&query).await?.collect().await?;
+    let mut umis_by_prefix: BTreeMap<String, Vec<(String, u64)>> = BTreeMap::new();
+    for batch in batches.iter() {
+        let prefixes = string_array(batch.column(0))?;
+        let umis = string_array(batch.column(1))?;
+        let counts = arrow::compute::cast(batch.column(2), &DataType::UInt64)?;
+        let counts = counts
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .ok_or_else(|| DataFusionError::Internal("Expected UInt64Array".to_string()))?;
+        for i in 0..batch.num_rows() {
+            umis_by_prefix
+                .entry(prefixes.value(i).to_string())
+                .or_default()
+                .push((umis.value(i).to_string(), counts.value(i)));
+        }
+    }
+
+    let (mut prefixes, mut umis, mut representatives) = (
+        StringBuilder::new(),
+        StringBuilder::new(),
+        StringBuilder::new(),
+    );
+    let mut group_ids = UInt64Builder::new();
+    let mut next_group = 0u64;
+    for (prefix, mut group) in umis_by_prefix {
+        group.sort();
+        let clusters = cluster_directional(&group, max_distance);
+        let mut cluster_groups = vec![0u64; group.len()];
+        for (i, representative) in clusters.iter().enumerate() {
+            if i == *representative {
+                cluster_groups[i] = next_group;
+                next_group += 1;
+            }
+        }
+        for (i, representative) in clusters.iter().enumerate() {
+            prefixes.append_value(&prefix);
+            umis.append_value(&group[i].0);
+            representatives.append_value(&group[*representative].0);
+            group_ids.append_value(cluster_groups[*representative]);
+        }
+    }
+    info!("Clustered UMIs into {} groups", next_group);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("prefix", DataType::Utf8, false),
+        Field::new("umi", DataType::Utf8, false),
+        Field::new("final_umi", DataType::Utf8, false),
+        Field::new("group_id", DataType::UInt64, false),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(prefixes.finish()),
+        Arc::new(umis.finish()),
+        Arc::new(representatives.finish()),
+        Arc::new(group_ids.finish()),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    ctx.session.deregister_table(UMI_GROUPS_TABLE)?;
+    ctx.session.register_table(
+        UMI_GROUPS_TABLE,
+        Arc::new(MemTable::try_new(schema, vec![vec![batch]])?),
+    )?;
+
+    let grouped = format!(
+        "SELECT t.*, g.group_id, g.final_umi FROM {} t JOIN {} g ON {} = g.prefix AND t.\"{}\" = g.umi",
+        table_name,
+        UMI_GROUPS_TABLE,
+        key("t."),
+        umi_column
+    );
+    let query = if groups {
+        format!("{} ORDER BY g.group_id", grouped)
+    } else {
+        format!(
+            r#"
+            WITH grouped AS ({grouped}),
+            ranked AS (
+                SELECT *, row_number() OVER (
+                    PARTITION BY group_id
+                    ORDER BY "{umi_column}" = final_umi DESC, "{sequence_column}"
+                ) AS umi_rank
+                FROM grouped
+            )
+            SELECT * EXCLUDE (umi_rank)
+            FROM ranked
+            WHERE umi_rank = 1
+            ORDER BY group_id
+            "#,
+        )
+    };
+    debug!("Query: {}", query);
+    ctx.session.sql(&query).await
+}
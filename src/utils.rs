@@ -9,6 +9,25 @@ pub(crate) fn default_cols_to_string(s: &[&str; 3]) -> Vec<String> {
     s.iter().map(|x| x.to_string()).collect()
 }
 
+/// Fields of `schema` with `suffix` appended to their names, for the output
+/// schemas of operations joining two tables.
+pub(crate) fn with_suffix(
+    schema: &arrow_schema::Schema,
+    suffix: &str,
+) -> Vec<arrow_schema::FieldRef> {
+    schema
+        .fields()
+        .iter()
+        .map(|f| {
+            arrow_schema::FieldRef::new(f.as_ref().clone().with_name(format!(
+                "{}{}",
+                f.name(),
+                suffix
+            )))
+        })
+        .collect()
+}
+
 fn convert_arrow_rs_field_to_polars_arrow_field(
     arrow_rs_field: &arrow_schema::Field,
 ) -> Result<polars_arrow::datatypes::Field, &str> {
@@ -0,0 +1,117 @@
+use arrow_schema::{DataType, FieldRef, Schema};
+use datafusion::catalog_common::TableReference;
+use exon::ExonSession;
+use log::{debug, info};
+
+use crate::option::FilterOp;
+
+/// Leading columns of tables produced by `VcfTableProvider`.
+const VCF_COLUMNS: [&str; 8] = [
+    "chrom", "start", "end", "id", "ref", "alt", "qual", "filter",
+];
+
+pub(crate) fn is_vcf_schema(schema: &Schema) -> bool {
+    schema.fields().len() >= VCF_COLUMNS.len()
+        && schema
+            .fields()
+            .iter()
+            .zip(VCF_COLUMNS.iter())
+            .all(|(f, c)| f.name() == c)
+}
+
+/// Finds an INFO column by its key, e.g. `END` or `SVLEN`. The lowercase
+/// `end` column holds the variant end and is never treated as the INFO field.
+pub(crate) fn info_column<'a>(schema: &'a Schema, key: &str) -> Option<&'a FieldRef> {
+    schema
+        .fields()
+        .iter()
+        .skip(VCF_COLUMNS.len())
+        .find(|f| f.name().eq_ignore_ascii_case(key) && f.name() != "end")
+}
+
+fn info_field(schema: &Schema, key: &str) -> Option<String> {
+    info_column(schema, key).map(|f| {
+        let value = format!("\"{}\"", f.name());
+        match f.data_type() {
+            DataType::List(_) | DataType::LargeList(_) => {
+                format!("array_element({}, 1)", value)
+            },
+            _ => value,
+        }
+    })
+}
+
+/// SQL expression for the 1-based, inclusive end of a variant. The span of
+/// sequence-resolved alleles comes from the REF length, symbolic structural
+/// variants use `END` or `SVLEN` when these INFO fields are read.
+fn variant_end_expr(schema: &Schema) -> String {
+    let start = "CAST(\"start\" AS BIGINT)";
+    let mut cases = vec![format!("WHEN \"alt\" LIKE '<INS%' THEN {}", start)];
+    if let Some(end) = info_field(schema, "END") {
+        cases.push(format!(
+            "WHEN \"alt\" LIKE '<%' AND {end} IS NOT NULL THEN CAST({end} AS BIGINT)",
+        ));
+    }
+    if let Some(svlen) = info_field(schema, "SVLEN") {
+        cases.push(format!(
+            "WHEN \"alt\" LIKE '<%' AND {svlen} IS NOT NULL THEN {start} + abs(CAST({svlen} AS BIGINT))",
+        ));
+    }
+    format!(
+        "CASE {} ELSE {} + character_length(\"ref\") - 1 END",
+        cases.join(" "),
+        start
+    )
+}
+
+/// Registers a view over a VCF-derived table with interval bounds derived from
+/// the variant alleles, so that it can be used directly in range operations.
+/// For `FilterOp::Strict` the positions are converted to 0-based, half-open
+/// intervals. Other tables are returned unchanged.
+pub(crate) async fn register_vcf_intervals(
+    ctx: &ExonSession,
+    table_name: String,
+    filter_op: &FilterOp,
+) -> datafusion::error::Result<String> {
+    let table = ctx
+        .session
+        .table(TableReference::from(table_name.clone()))
+        .await?;
+    let schema = table.schema().as_arrow().clone();
+    if !is_vcf_schema(&schema) {
+        return Ok(table_name);
+    }
+    let start_shift = match filter_op {
+        FilterOp::Strict => " - 1",
+        FilterOp::Weak => "",
+    };
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|f| match f.name().as_str() {
+            "start" => format!(
+                "arrow_cast(CAST(\"start\" AS BIGINT){}, '{}') AS \"start\"",
+                start_shift,
+                f.data_type()
+            ),
+            "end" => format!(
+                "arrow_cast({}, '{}') AS \"end\"",
+                variant_end_expr(&schema),
+                f.data_type()
+            ),
+            name => format!("\"{}\"", name),
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    let view_name = format!("{}_vcf_intervals", table_name);
+    let query = format!("SELECT {} FROM {}", columns, table_name);
+    debug!("Query: {}", query);
+    let view = ctx.session.sql(&query).await?.into_view();
+    ctx.session.deregister_table(&view_name)?;
+    ctx.session.register_table(&view_name, view)?;
+    info!(
+        "Registered VCF table {} with derived interval bounds as {}",
+        table_name, view_name
+    );
+    Ok(view_name)
+}
@@ -0,0 +1,154 @@
+import polars as pl
+import pytest
+from _expected import DATA_DIR
+
+import polars_bio as pb
+
+BAM_PATH = f"{DATA_DIR}/io/bam/test.bam"
+
+
+class TestFlagstat:
+    expected = {
+        "total": (2231, 102),
+        "primary": (2231, 102),
+        "secondary": (0, 0),
+        "supplementary": (0, 0),
+        "duplicates": (1699, 88),
+        "mapped": (2229, 101),
+        "paired": (2231, 102),
+        "read1": (1928, 12),
+        "properly_paired": (2220, 95),
+        "singletons": (6, 5),
+        "mate_on_different_chrom": (3, 1),
+    }
+
+    def check(self, result: pl.DataFrame):
+        assert result.columns == ["category", "qc_passed", "qc_failed"]
+        assert result["category"][0] == "total"
+        counts = {
+            row["category"]: (row["qc_passed"], row["qc_failed"])
+            for row in result.to_dicts()
+        }
+        for category, count in self.expected.items():
+            assert counts[category] == count, category
+
+    def test_bam_path(self):
+        self.check(pb.flagstat(BAM_PATH))
+
+    def test_registered_table(self):
+        pb.register_bam(BAM_PATH, "flagstat_bam")
+        self.check(pb.flagstat("flagstat_bam"))
+
+    def test_frame(self):
+        self.check(pb.flagstat(pb.read_bam(BAM_PATH).collect()))
+
+
+class TestIdxstats:
+    def test_bam_path(self):
+        result = pb.idxstats(BAM_PATH)
+        assert result.columns == ["chrom", "length", "mapped", "unmapped"]
+        assert result.height == 46
+        assert result["chrom"].to_list()[:2] == ["chrM", "chr1"]
+        assert result["chrom"][-1] == "*"
+        assert result.row(1, named=True)["length"] == 247249719
+        assert result.row(1, named=True)["mapped"] == 2330
+        assert result["mapped"].sum() == 2330
+        assert result["unmapped"].sum() == 3
+
+    def test_frame(self):
+        result = pb.idxstats(pb.read_bam(BAM_PATH).collect())
+        assert result["length"].null_count() == result.height - 1
+        assert result.filter(pl.col("chrom") == "chr1")["mapped"].to_list() == [2330]
+        assert result["unmapped"].sum() == 3
+
+
+class TestInsertSize:
+    def test_histogram(self):
+        result = pb.insert_size_histogram(BAM_PATH)
+        assert result.columns == ["orientation", "insert_size", "count", "fraction"]
+        assert result["orientation"].unique().to_list() == ["FR"]
+        assert result["count"].sum() == 137
+        assert result["insert_size"].to_list()[:2] == [68, 86]
+        assert result["insert_size"].to_list() == sorted(result["insert_size"])
+        assert result["fraction"].sum() == pytest.approx(1.0)
+
+    def test_include_duplicates(self):
+        result = pb.insert_size_histogram(BAM_PATH, include_duplicates=True)
+        assert result["count"].sum() == 300
+
+    def test_group_by_chrom(self):
+        result = pb.insert_size_histogram(
+            pb.read_bam(BAM_PATH).collect(), group_by="chrom"
+        )
+        assert result.columns[:2] == ["chrom", "orientation"]
+        assert result["chrom"].unique().to_list() == ["chr1"]
+        assert result["count"].sum() == 137
+
+    def test_metrics(self):
+        result = pb.insert_size_metrics(BAM_PATH)
+        assert result.height == 1
+        metrics = result.row(0, named=True)
+        assert metrics["orientation"] == "FR"
+        assert metrics["read_pairs"] == 137
+        assert metrics["min_insert_size"] == 68
+        assert metrics["max_insert_size"] == 502
+        assert metrics["median_insert_size"] == 243
+        assert metrics["median_absolute_deviation"] > 0
+        assert metrics["standard_deviation"] > 0
+
+    def test_metrics_trimming(self):
+        metrics = pb.insert_size_metrics(BAM_PATH, deviations=1).row(0, named=True)
+        histogram = pb.insert_size_histogram(BAM_PATH).filter(
+            pl.col("insert_size")
+            <= metrics["median_insert_size"] + metrics["median_absolute_deviation"]
+        )
+        assert metrics["read_pairs"] == 137
+        assert metrics["max_insert_size"] == 502
+        assert metrics["mean_insert_size"] == pytest.approx(
+            (histogram["insert_size"] * histogram["count"]).sum()
+            / histogram["count"].sum()
+        )
+
+    def test_metrics_single_pair(self):
+        reads = pl.DataFrame(
+            {
+                "chrom": ["chr1"],
+                "start": [100],
+                "end": [150],
+                "flags": [163],
+                "mate_chrom": ["chr1"],
+                "mate_start": [300],
+            }
+        )
+        metrics = pb.insert_size_metrics(reads).row(0, named=True)
+        assert metrics["read_pairs"] == 1
+        assert metrics["mean_insert_size"] == 251
+        assert metrics["standard_deviation"] is None
+
+    def test_missing_group_column(self):
+        with pytest.raises(ValueError):
+            pb.insert_size_histogram(
+                pb.read_bam(BAM_PATH).collect(), group_by="read_group"
+            )
+
+
+class TestMapqDistribution:
+    def test_distribution(self):
+        result = pb.mapq_distribution(BAM_PATH)
+        assert result.columns == ["mapping_quality", "count", "fraction"]
+        counts = dict(zip(result["mapping_quality"], result["count"]))
+        assert result["count"].sum() == 2229
+        assert counts[0] == 1477
+        assert counts[29] == 53
+        assert result["fraction"].sum() == pytest.approx(1.0)
+
+    def test_group_by_read_group(self):
+        df = pb.read_bam(BAM_PATH).collect()
+        df = df.with_columns(
+            pl.col("name").str.split(":").list.get(1).alias("read_group")
+        )
+        result = pb.mapq_distribution(df, group_by=["read_group", "chrom"])
+        assert result.columns[:2] == ["read_group", "chrom"]
+        assert result["count"].sum() == 2229
+        for group in result.partition_by("read_group"):
+            assert group["fraction"].sum() == pytest.approx(1.0)
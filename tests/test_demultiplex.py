@@ -0,0 +1,75 @@
+import polars as pl
+import pytest
+
+import polars_bio as pb
+
+
+class TestDemultiplex:
+    sample_sheet = pl.DataFrame(
+        {
+            "sample": ["s1", "s2", "s3"],
+            "i7": ["AAAAAAAA", "CCCCCCCC", "GGGGGGGG"],
+            "i5": ["TTTTTTTT", "TTTTTTTT", "ACACACAC"],
+        }
+    )
+    reads = pl.DataFrame(
+        {
+            "name": [f"r{i}" for i in range(6)],
+            "description": [
+                "1:N:0:AAAAAAAA+TTTTTTTT",
+                "1:N:0:AAAAAAAT+TTTTTTTT",
+                "1:N:0:CCCCCCCC+TTTTTTTA",
+                "1:N:0:GGGGGGGG+TTTTTTTT",
+                "1:N:0:AAAAAATT+TTTTTTTT",
+                "1:N:0:GGGGGGGG+ACACACAC",
+            ],
+        }
+    )
+
+    def test_assignment(self):
+        reads, _ = pb.demultiplex(self.reads, self.sample_sheet)
+        assert reads["sample"].to_list() == [
+            "s1",
+            "s1",
+            "s2",
+            "Undetermined",
+            "Undetermined",
+            "s3",
+        ]
+        assert reads["barcode_mismatches"].to_list() == [0, 1, 1, None, None, 0]
+
+    def test_summary(self):
+        _, summary = pb.demultiplex(self.reads, self.sample_sheet)
+        assert summary["sample"].to_list() == ["s1", "s2", "s3", "Undetermined"]
+        assert summary["read_count"].to_list() == [2, 1, 1, 2]
+        assert summary["perfect_barcode_count"].to_list() == [1, 0, 1, 0]
+        assert summary["percent"].sum() == pytest.approx(100.0)
+
+    def test_exact_matching(self):
+        reads, _ = pb.demultiplex(self.reads, self.sample_sheet, max_mismatches=0)
+        assert reads["sample"].to_list() == [
+            "s1",
+            "Undetermined",
+            "Undetermined",
+            "Undetermined",
+            "Undetermined",
+            "s3",
+        ]
+
+    def test_single_index(self):
+        sheet = pl.DataFrame({"sample": ["a", "b"], "i7": ["ACGTAC", "TGCATG"]})
+        reads = pl.DataFrame({"description": ["ACGTAA", "TGCATG+NNNN", "GGGGGG"]})
+        result, summary = pb.demultiplex(reads, sheet)
+        assert result["sample"].to_list() == ["a", "b", "Undetermined"]
+        assert summary["read_count"].to_list() == [1, 1, 1]
+
+    def test_collisions_lower_mismatches(self):
+        sheet = pl.DataFrame({"sample": ["a", "b"], "i7": ["AAAA", "AATT"]})
+        reads = pl.DataFrame({"description": ["AAAA", "AAAT"]})
+        result, _ = pb.demultiplex(reads, sheet, max_mismatches=1)
+        assert result["sample"].to_list() == ["a", "Undetermined"]
+
+    def test_identical_barcodes(self):
+        sheet = pl.DataFrame({"sample": ["a", "b"], "i7": ["AAAA", "AAAA"]})
+        with pytest.raises(Exception):
+            pb.demultiplex(pl.DataFrame({"description": ["AAAA"]}), sheet)
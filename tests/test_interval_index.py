@@ -0,0 +1,104 @@
+import polars as pl
+from _expected import (
+    BIO_PD_DF1,
+    BIO_PD_DF2,
+    PL_COUNT_OVERLAPS_DF1,
+    PL_COUNT_OVERLAPS_DF2,
+    PL_DF1,
+    PL_DF2,
+    PL_DF_COUNT_OVERLAPS,
+    PL_DF_NEAREST,
+    PL_DF_OVERLAP,
+    PL_NEAREST_DF1,
+    PL_NEAREST_DF2,
+)
+
+import polars_bio as pb
+from polars_bio.polars_bio import FilterOp
+
+COLS = ["contig", "pos_start", "pos_end"]
+
+
+class TestOverlapIntervalIndex:
+    index = pb.build_index(PL_DF2, "overlap_index", cols=COLS)
+    result = pb.overlap(
+        PL_DF1,
+        index,
+        output_type="polars.DataFrame",
+        overlap_filter=FilterOp.Weak,
+        cols1=COLS,
+        cols2=COLS,
+    )
+
+    def test_index_info(self):
+        assert self.index.name == "overlap_index"
+        assert self.index.num_rows == len(PL_DF2)
+        assert self.index.path is None
+
+    def test_overlap_count(self):
+        assert len(self.result) == len(PL_DF_OVERLAP)
+
+    def test_overlap_schema_rows(self):
+        result = self.result.sort(by=self.result.columns)
+        assert PL_DF_OVERLAP.equals(result)
+
+
+class TestNearestIntervalIndex:
+    index = pb.build_index(PL_NEAREST_DF2, "nearest_index", cols=COLS)
+    result = pb.nearest(
+        PL_NEAREST_DF1,
+        index,
+        output_type="polars.DataFrame",
+        cols1=COLS,
+        cols2=COLS,
+    )
+
+    def test_nearest_schema_rows(self):
+        result = self.result.sort(by=self.result.columns)
+        assert PL_DF_NEAREST.equals(result)
+
+
+class TestCountOverlapsIntervalIndex:
+    index = pb.build_index(PL_COUNT_OVERLAPS_DF2, "count_index", cols=COLS)
+
+    def test_count_overlaps_reused(self):
+        for _ in range(2):
+            result = pb.count_overlaps(
+                PL_COUNT_OVERLAPS_DF1,
+                self.index,
+                output_type="polars.DataFrame",
+                cols1=COLS,
+                cols2=COLS,
+                overlap_filter=FilterOp.Weak,
+            )
+            result = result.sort(by=result.columns)
+            assert PL_DF_COUNT_OVERLAPS.equals(result)
+
+    def test_drop_index(self):
+        pb.build_index(PL_COUNT_OVERLAPS_DF2, "dropped_index", cols=COLS)
+        assert "dropped_index" in pb.ctx.list_interval_indexes()
+        assert pb.drop_index("dropped_index")
+        assert not pb.drop_index("dropped_index")
+
+
+class TestCoverageIntervalIndex:
+    df1 = pl.from_pandas(BIO_PD_DF1)
+    df2 = pl.from_pandas(BIO_PD_DF2)
+    index = pb.build_index(df2, "coverage_index", cols=COLS)
+
+    def test_coverage_schema_rows(self):
+        expected, result = (
+            pb.coverage(
+                self.df1,
+                df2,
+                output_type="polars.DataFrame",
+                overlap_filter=FilterOp.Strict,
+                cols1=COLS,
+                cols2=COLS,
+            )
+            for df2 in (self.df2, self.index)
+        )
+        assert len(result) == len(self.df1)
+        assert expected.sort(by=expected.columns).equals(
+            result.sort(by=result.columns)
+        )
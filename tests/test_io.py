@@ -1,5 +1,6 @@
 import bioframe as bf
 import pandas as pd
+import pytest
 from _expected import (
     DATA_DIR,
     PD_DF_OVERLAP,
@@ -223,3 +224,53 @@ class TestBED:
         assert projection["start"][1] == 66700001  # example of 1-based for start
         assert projection["end"][2] == 63934965
         assert projection["name"][4] == "FRA16E"
+
+
+class TestPairedFastq:
+    r1 = f"{DATA_DIR}/io/fastq/paired_R1.fastq"
+
+    def test_paired_files(self):
+        df = pb.read_fastq(self.r1, paired_path=f"{DATA_DIR}/io/fastq/paired_R2.fastq")
+        df = df.collect()
+        assert df.columns == ["name", "seq1", "qual1", "seq2", "qual2"]
+        assert df["name"].to_list() == ["pair1", "pair2", "pair3"]
+        assert df["seq1"][1] == "GGGGCCCC"
+        assert df["seq2"][1] == "CATGCATG"
+        assert df["qual2"][2] == "IIII####"
+
+    def test_interleaved(self):
+        interleaved = pb.read_fastq(
+            f"{DATA_DIR}/io/fastq/interleaved.fastq", interleaved=True
+        ).collect()
+        paired = pb.read_fastq(
+            self.r1, paired_path=f"{DATA_DIR}/io/fastq/paired_R2.fastq"
+        ).collect()
+        assert interleaved.equals(paired)
+
+    def test_register(self):
+        pb.register_fastq(
+            self.r1,
+            "paired_reads",
+            paired_path=f"{DATA_DIR}/io/fastq/paired_R2.fastq",
+        )
+        count = pb.sql("SELECT count(*) AS cnt FROM paired_reads").collect()
+        assert count["cnt"][0] == 3
+
+    def test_desync(self):
+        with pytest.raises(Exception, match="out of sync at pair 2"):
+            pb.read_fastq(
+                self.r1, paired_path=f"{DATA_DIR}/io/fastq/paired_desync_R2.fastq"
+            ).collect()
+
+    def test_missing_r2(self):
+        # Raised as a Python error rather than a Rust panic
+        with pytest.raises(Exception):
+            pb.read_fastq(
+                self.r1, paired_path=f"{DATA_DIR}/io/fastq/missing_R2.fastq"
+            ).collect()
+
+    def test_different_number_of_records(self):
+        with pytest.raises(Exception, match="different number of records"):
+            pb.read_fastq(
+                self.r1, paired_path=f"{DATA_DIR}/io/fastq/paired_short_R2.fastq"
+            ).collect()
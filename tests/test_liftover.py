@@ -0,0 +1,78 @@
+import polars as pl
+from _expected import DATA_DIR
+
+import polars_bio as pb
+
+CHAIN_PATH = f"{DATA_DIR}/liftover/test.chain"
+GAPPED_CHAIN_PATH = f"{DATA_DIR}/liftover/gapped.chain"
+
+DF = pl.DataFrame(
+    {
+        "chrom": ["chr1", "chr1", "chr2", "chr1", "chr1", "chr3"],
+        "start": [120, 150, 20, 650, 900, 1],
+        "end": [180, 300, 30, 750, 950, 10],
+        "ref": ["A", "C", "AC", "G", "T", "A"],
+        "alt": ["G", "T", "G,TA", "A", "C", "G"],
+    }
+)
+
+
+class TestLiftover:
+    mapped, unmapped = pb.liftover(DF, CHAIN_PATH, output_type="polars.DataFrame")
+    mapped = mapped.sort(by=["chrom", "start"])
+    unmapped = unmapped.sort(by=["chrom", "start"])
+
+    def test_mapped(self):
+        assert self.mapped["chrom"].to_list() == ["chr1_new", "chr2"]
+        assert self.mapped["start"].to_list() == [1120, 460]
+        assert self.mapped["end"].to_list() == [1180, 470]
+        assert self.mapped["liftover_strand"].to_list() == ["+", "-"]
+
+    def test_reverse_strand_alleles(self):
+        assert self.mapped["ref"].to_list() == ["A", "GT"]
+        assert self.mapped["alt"].to_list() == ["G", "C,TA"]
+
+    def test_unmapped(self):
+        assert self.unmapped["start"].to_list() == [150, 650, 900, 1]
+        assert self.unmapped["reason"].to_list() == [
+            "partially_deleted",
+            "split",
+            "no_overlap",
+            "no_chain",
+        ]
+
+    def test_min_match(self):
+        mapped, unmapped = pb.liftover(
+            DF, CHAIN_PATH, min_match=0.5, output_type="polars.DataFrame"
+        )
+        mapped = mapped.sort(by=["chrom", "start"])
+        assert mapped["start"].to_list() == [1120, 1150, 460]
+        assert mapped["end"].to_list() == [1180, 1420, 470]
+        assert len(unmapped) == 3
+
+    def test_one_based(self):
+        mapped, _ = pb.liftover(
+            DF.head(1),
+            CHAIN_PATH,
+            overlap_filter=pb.FilterOp.Weak,
+            output_type="polars.DataFrame",
+        )
+        assert mapped["start"].to_list() == [1120]
+        assert mapped["end"].to_list() == [1180]
+
+    def test_gapped_chain(self):
+        df = pl.DataFrame(
+            {
+                "chrom": ["chr7", "chr7", "chr7"],
+                "start": [10, 50, 120],
+                "end": [60, 150, 180],
+            }
+        )
+        mapped, unmapped = pb.liftover(
+            df, GAPPED_CHAIN_PATH, min_match=0.5, output_type="polars.DataFrame"
+        )
+        mapped = mapped.sort(by="start")
+        # like UCSC liftOver, a span across a gap of one chain is mapped
+        assert mapped["start"].to_list() == [10, 50, 150]
+        assert mapped["end"].to_list() == [60, 180, 210]
+        assert len(unmapped) == 0
@@ -1,4 +1,8 @@
+import json
+
+import pytest
 import polars as pl
+from polars.io.plugins import register_io_source
 import polars_bio as pb
 
 FASTQ_PATH = "./data/quality_control/example.fastq"
@@ -74,3 +78,572 @@ class TestBaseSequenceContent:
         self.test_with_polars_df()
         self.test_with_pandas_df()
         print("\n✓✓ All tests completed successfully!")
+
+
+def _nearest_rank(scores, percentile):
+    rank = max(1, -(-len(scores) * percentile // 1))
+    return float(sorted(scores)[int(rank) - 1])
+
+
+class TestBaseSequenceQuality:
+    fastq_df = pb.read_fastq(FASTQ_PATH).collect()
+    result = pb.base_sequence_quality(FASTQ_PATH)
+
+    def test_positions(self):
+        max_len = self.fastq_df["quality_scores"].str.len_bytes().max()
+        assert self.result["position"].to_list() == list(range(1, max_len + 1))
+
+    def test_statistics(self):
+        qualities = self.fastq_df["quality_scores"].to_list()
+        for row in self.result.iter_rows(named=True):
+            pos = row["position"] - 1
+            scores = [ord(q[pos]) - 33 for q in qualities if len(q) > pos]
+            assert abs(row["mean"] - sum(scores) / len(scores)) < 1e-9
+            assert row["median"] == _nearest_rank(scores, 0.5)
+            assert row["lower_quartile"] == _nearest_rank(scores, 0.25)
+            assert row["upper_quartile"] == _nearest_rank(scores, 0.75)
+            assert row["percentile_10"] == _nearest_rank(scores, 0.1)
+            assert row["percentile_90"] == _nearest_rank(scores, 0.9)
+
+    def test_with_frame(self):
+        assert self.result.equals(pb.base_sequence_quality(self.fastq_df))
+
+
+class TestPerSequenceQuality:
+    fastq_df = pb.read_fastq(FASTQ_PATH).collect()
+    result = pb.per_sequence_quality(FASTQ_PATH)
+
+    def test_histogram(self):
+        expected = (
+            self.fastq_df.select(
+                pl.col("quality_scores")
+                .map_elements(
+                    lambda q: sum(ord(c) - 33 for c in q) // len(q),
+                    return_dtype=pl.UInt64,
+                )
+                .alias("quality")
+            )
+            .group_by("quality")
+            .agg(pl.len().cast(pl.UInt64).alias("count"))
+            .sort("quality")
+        )
+        assert self.result.equals(expected)
+        assert self.result["count"].sum() == len(self.fastq_df)
+
+    def test_with_frame(self):
+        assert self.result.equals(pb.per_sequence_quality(self.fastq_df))
+
+
+class TestPerSequenceGcContent:
+    fastq_df = pb.read_fastq(FASTQ_PATH).collect()
+    result = pb.per_sequence_gc_content(FASTQ_PATH)
+
+    @staticmethod
+    def _gc_percent(sequence):
+        gc = sum(b in "GCgc" for b in sequence)
+        acgt = sum(b in "ACGTacgt" for b in sequence)
+        return (gc * 200 + acgt) // (2 * acgt)
+
+    def test_observed(self):
+        expected = (
+            self.fastq_df.select(
+                pl.col("sequence")
+                .map_elements(self._gc_percent, return_dtype=pl.UInt64)
+                .alias("gc_content")
+            )
+            .group_by("gc_content")
+            .agg(pl.len().cast(pl.UInt64).alias("count"))
+        )
+        observed = self.result.filter(pl.col("count") > 0).select(
+            "gc_content", "count"
+        )
+        assert observed.equals(expected.sort("gc_content"))
+        assert self.result["gc_content"].to_list() == list(range(101))
+
+    def test_theoretical(self):
+        assert (self.result["theoretical_count"] >= 0).all()
+        mode_count = self.result["count"].max()
+        mode = self.result.filter(pl.col("count") >= 0.9 * mode_count)[
+            "gc_content"
+        ].mean()
+        peak = self.result.sort("theoretical_count", descending=True)["gc_content"][0]
+        assert abs(peak - mode) <= 1
+
+
+class TestSequenceLengthDistribution:
+    fastq_df = pb.read_fastq(FASTQ_PATH).collect()
+
+    def _expected(self, bin_size):
+        return (
+            self.fastq_df.select(
+                (pl.col("sequence").str.len_bytes().cast(pl.UInt64) // bin_size * bin_size)
+                .alias("length")
+            )
+            .group_by("length")
+            .agg(pl.len().cast(pl.UInt64).alias("count"))
+            .sort("length")
+        )
+
+    def test_exact_lengths(self):
+        result = pb.sequence_length_distribution(FASTQ_PATH)
+        assert result.select("length", "count").equals(self._expected(1))
+        assert result["length"].equals(result["length_end"].alias("length"))
+
+    def test_binned(self):
+        result = pb.sequence_length_distribution(self.fastq_df, bin_size=10)
+        assert result.select("length", "count").equals(self._expected(10))
+        assert (result["length_end"] - result["length"] == 9).all()
+        assert result["count"].sum() == len(self.fastq_df)
+
+
+class TestSequenceDuplication:
+    # 5 distinct sequences: one seen 4 times, one twice, three once
+    reads = pl.DataFrame(
+        {
+            "sequence": ["AAAA"] * 4 + ["CCCC"] * 2 + ["GGGG", "TTTT", "ACGT"],
+        }
+    )
+
+    def test_levels(self):
+        levels, percent_remaining = pb.sequence_duplication_levels(self.reads)
+        assert len(levels) == 16
+        by_level = dict(
+            zip(levels["duplication_level"], levels["percent_deduplicated"])
+        )
+        assert by_level["1"] == pytest.approx(60.0)
+        assert by_level["2"] == pytest.approx(20.0)
+        assert by_level["4"] == pytest.approx(20.0)
+        totals = dict(zip(levels["duplication_level"], levels["percent_total"]))
+        assert totals["4"] == pytest.approx(400 / 9)
+        assert percent_remaining == pytest.approx(500 / 9)
+
+    def test_long_reads_truncated(self):
+        reads = pl.DataFrame({"sequence": ["A" * 50 + "C" * 30, "A" * 50 + "G" * 30]})
+        levels, percent_remaining = pb.sequence_duplication_levels(reads)
+        assert percent_remaining == pytest.approx(50.0)
+
+    def test_partitions(self):
+        # More distinct sequences than tracked, spread over batches of 100 reads
+        sequences = [
+            "".join("ACGT"[(j >> 2 * i) & 3] for i in range(6))
+            for j in range(2000)
+            for _ in range([1, 1, 2, 5][j % 4])
+        ]
+        reads = pl.DataFrame(
+            {"sequence": [sequences[i * 7 % 4500] for i in range(4500)]}
+        )
+        reads = pl.concat(
+            [reads.slice(i, 100) for i in range(0, 4500, 100)], rechunk=False
+        )
+        _, single = pb.sequence_duplication_levels(reads, max_unique=1000)
+        pb.set_option("datafusion.execution.target_partitions", "4")
+        try:
+            _, partitioned = pb.sequence_duplication_levels(reads, max_unique=1000)
+        finally:
+            pb.set_option("datafusion.execution.target_partitions", "1")
+        assert partitioned == pytest.approx(single, abs=4)
+
+    def test_overrepresented(self):
+        result = pb.overrepresented_sequences(self.reads, min_percentage=15)
+        assert result["sequence"].to_list() == ["AAAA", "CCCC"]
+        assert result["count"].to_list() == [4, 2]
+        assert result["percentage"].to_list() == pytest.approx([400 / 9, 200 / 9])
+
+    def test_fastq(self):
+        levels, percent_remaining = pb.sequence_duplication_levels(FASTQ_PATH)
+        assert 0 < percent_remaining <= 100
+        assert levels["percent_total"].sum() == pytest.approx(100.0)
+        overrepresented = pb.overrepresented_sequences(FASTQ_PATH)
+        assert (overrepresented["percentage"] > 0.1).all()
+
+
+class TestAdapterContent:
+    reads = pl.DataFrame(
+        {
+            "sequence": [
+                "ACGTAGATCGGAAGAGCA",
+                "AGATCGGAAGAGCACGTA",
+                "ACGTACGTACGTACGTAC",
+                "ACGTCTGTCTCTTATACA",
+            ]
+        }
+    )
+
+    def test_default_adapters(self):
+        result = pb.adapter_content(self.reads)
+        assert result["adapter"].n_unique() == 5
+        universal = result.filter(pl.col("adapter") == "Illumina Universal Adapter")
+        percentages = dict(zip(universal["position"], universal["percentage"]))
+        assert percentages[1] == pytest.approx(25.0)
+        assert percentages[4] == pytest.approx(25.0)
+        assert percentages[5] == pytest.approx(50.0)
+        assert percentages[18] == pytest.approx(50.0)
+        nextera = result.filter(pl.col("adapter") == "Nextera Transposase Sequence")
+        assert nextera["percentage"].max() == pytest.approx(25.0)
+
+    def test_custom_adapters(self):
+        result = pb.adapter_content(
+            self.reads,
+            adapters=pl.DataFrame({"name": ["ACGTAC"], "sequence": ["acgtac"]}),
+        )
+        assert result["adapter"].unique().to_list() == ["ACGTAC"]
+        assert result["percentage"].max() == pytest.approx(25.0)
+
+    def test_lowercase_reads(self):
+        reads = self.reads.with_columns(pl.col("sequence").str.to_lowercase())
+        expected = pb.adapter_content(self.reads)
+        result = pb.adapter_content(reads)
+        assert result.equals(expected)
+        assert result["percentage"].max() == pytest.approx(50.0)
+
+    def test_fastq(self):
+        result = pb.adapter_content(FASTQ_PATH, adapters={"polyA": "AAAAAAAAAAAA"})
+        assert result["percentage"].is_sorted()
+
+
+class TestKmerCount:
+    reads = pl.DataFrame({"sequence": ["ACGTAC", "GTACNAC", "acgt"]})
+
+    @staticmethod
+    def _reverse_complement(kmer):
+        return kmer[::-1].translate(str.maketrans("ACGT", "TGCA"))
+
+    def test_counts(self):
+        result = pb.kmer_count(self.reads, k=3)
+        counts = dict(zip(result["kmer"], result["count"]))
+        assert counts == {"ACG": 2, "CGT": 2, "GTA": 2, "TAC": 2}
+
+    def test_canonical(self):
+        result = pb.kmer_count(self.reads, k=3, canonical=True)
+        counts = dict(zip(result["kmer"], result["count"]))
+        # ACG/CGT and GTA/TAC are reverse complements of each other
+        assert counts == {"ACG": 4, "GTA": 4}
+
+    def test_min_count(self):
+        result = pb.kmer_count(self.reads, k=2, min_count=3)
+        assert result["kmer"].to_list() == ["AC", "GT"]
+        assert result["count"].to_list() == [5, 3]
+
+    def test_positions(self):
+        result = pb.kmer_count(self.reads, k=3, positions=True)
+        acg = result.filter(pl.col("kmer") == "ACG")
+        assert acg["position"].to_list() == [0]
+        assert acg["count"].to_list() == [2]
+        assert acg["total_count"].to_list() == [2]
+        # 3 of the 8 k-mers start at position 0, all ACG there: 2 * 8 / (2 * 3)
+        assert acg["enrichment"][0] == pytest.approx(8 / 3)
+
+    def test_fastq(self):
+        fastq_df = pb.read_fastq(FASTQ_PATH).collect()
+        result = pb.kmer_count(FASTQ_PATH, k=5)
+        expected = sum(
+            sum(
+                1
+                for i in range(len(s) - 4)
+                if set(s[i : i + 5]) <= set("ACGT")
+            )
+            for s in fastq_df["sequence"].str.to_uppercase()
+        )
+        assert result["count"].sum() == expected
+
+    def test_invalid_k(self):
+        with pytest.raises(ValueError):
+            pb.kmer_count(self.reads, k=32)
+
+
+class TestPhredEncoding:
+    fastq_df = pb.read_fastq(FASTQ_PATH).collect()
+    phred64_df = fastq_df.with_columns(
+        pl.col("quality_scores").map_elements(
+            lambda q: "".join(chr(ord(c) + 31) for c in q), return_dtype=pl.Utf8
+        )
+    )
+
+    def test_detect(self):
+        assert pb.detect_phred_encoding(FASTQ_PATH)["encoding"] == "phred33"
+        detected = pb.detect_phred_encoding(self.phred64_df)
+        assert detected["encoding"] == "phred64"
+        assert detected["offset"] == 64
+
+    def test_detect_solexa(self):
+        reads = pl.DataFrame({"quality_scores": [";;@Zh", "hhhh"]})
+        assert pb.detect_phred_encoding(reads)["encoding"] == "solexa"
+
+    def test_reencode(self):
+        result = pb.reencode_quality(self.phred64_df, source="phred64")
+        assert result["quality_scores"].equals(self.fastq_df["quality_scores"])
+        assert result.columns == self.fastq_df.columns
+        auto = pb.reencode_quality(self.phred64_df, source="auto")
+        assert auto["quality_scores"].equals(self.fastq_df["quality_scores"])
+
+    def test_reencode_solexa(self):
+        reads = pl.DataFrame({"quality_scores": [";@Jh"]})
+        result = pb.reencode_quality(reads, source="solexa")
+        # Solexa -5, 0, 10, 40 are Phred 1, 3, 10, 40
+        assert result["quality_scores"][0] == "\"$+I"
+
+    def test_auto_offset_rejects_solexa(self):
+        reads = pl.DataFrame({"quality_scores": [";;@Zh", "hhhh", "@@JJ"]})
+        with pytest.raises(ValueError, match="reencode_quality"):
+            pb.base_sequence_quality(reads, phred_offset="auto")
+        phred = pb.reencode_quality(reads, source="solexa")
+        result = pb.per_sequence_quality(phred, phred_offset="auto")
+        assert result["count"].sum() == 3
+        with pytest.raises(ValueError, match="reencode_quality"):
+            pb.fastq_qc_report(
+                reads.with_columns(sequence=pl.lit("ACGT")), phred_offset="auto"
+            )
+
+    def test_report_encoding(self):
+        report = pb.fastq_qc_report(self.phred64_df, phred_offset="auto")
+        assert report["basic_statistics"]["data"]["encoding"] == "phred64"
+        assert report["per_base_sequence_quality"]["data"] == (
+            pb.fastq_qc_report(self.fastq_df)["per_base_sequence_quality"]["data"]
+        )
+
+    def test_sql(self):
+        result = pb.sql(
+            "SELECT reencode_quality(column1, 'phred64', 'phred33') AS q "
+            "FROM (VALUES ('hhJ@'))"
+        ).collect()
+        assert result["q"].to_list() == ["II+!"]
+
+    def test_quality_offset(self):
+        expected = pb.base_sequence_quality(self.fastq_df)
+        assert pb.base_sequence_quality(self.phred64_df, phred_offset=64).equals(expected)
+        assert pb.base_sequence_quality(self.phred64_df, phred_offset="auto").equals(
+            expected
+        )
+        assert pb.per_sequence_quality(self.phred64_df, phred_offset=64).equals(
+            pb.per_sequence_quality(self.fastq_df)
+        )
+
+
+class TestFastqQcReport:
+    fastq_df = pb.read_fastq(FASTQ_PATH).collect()
+    report = pb.fastq_qc_report(FASTQ_PATH)
+
+    def test_modules(self):
+        assert set(self.report) == {
+            "basic_statistics",
+            "per_base_sequence_quality",
+            "per_sequence_quality_scores",
+            "per_base_sequence_content",
+            "per_sequence_gc_content",
+            "per_base_n_content",
+            "sequence_length_distribution",
+            "sequence_duplication_levels",
+            "overrepresented_sequences",
+            "adapter_content",
+        }
+        for module in self.report.values():
+            assert module["status"] in ("pass", "warn", "fail")
+        json.loads(json.dumps(self.report))
+
+    def test_basic_statistics(self):
+        stats = self.report["basic_statistics"]["data"]
+        lengths = self.fastq_df["sequence"].str.len_bytes()
+        assert stats["total_sequences"] == len(self.fastq_df)
+        assert stats["total_bases"] == lengths.sum()
+        assert stats["min_length"] == lengths.min()
+        assert stats["max_length"] == lengths.max()
+
+    def test_matches_modules(self):
+        expected = pb.base_sequence_quality(FASTQ_PATH)
+        assert (
+            sorted(
+                self.report["per_base_sequence_quality"]["data"],
+                key=lambda p: p["position"],
+            )
+            == expected.to_dicts()
+        )
+        _, percent_remaining = pb.sequence_duplication_levels(FASTQ_PATH)
+        assert self.report["sequence_duplication_levels"]["data"][
+            "percent_remaining"
+        ] == pytest.approx(percent_remaining)
+        gc = pl.DataFrame(self.report["per_sequence_gc_content"]["data"])
+        expected_gc = pb.per_sequence_gc_content(FASTQ_PATH)
+        assert gc["count"].to_list() == expected_gc["count"].to_list()
+        assert gc["theoretical_count"].to_list() == pytest.approx(
+            expected_gc["theoretical_count"].to_list()
+        )
+
+    def test_missing_quality_scores(self):
+        with pytest.raises(ValueError, match="quality_scores"):
+            pb.fastq_qc_report(pl.DataFrame({"sequence": ["ACGT"]}))
+
+    def test_thresholds(self):
+        reads = pl.DataFrame(
+            {
+                "sequence": ["AGATCGGAAGAGCA"] * 9 + ["ACGTACGTACGTAC"],
+                "quality_scores": ["I" * 14] * 10,
+            }
+        )
+        report = pb.fastq_qc_report(reads)
+        assert report["adapter_content"]["status"] == "fail"
+        assert report["per_base_sequence_quality"]["status"] == "pass"
+        assert report["sequence_duplication_levels"]["status"] == "fail"
+        relaxed = pb.fastq_qc_report(
+            reads, thresholds={"adapter": {"warn": 95, "error": 99}}
+        )
+        assert relaxed["adapter_content"]["status"] == "pass"
+        with pytest.raises(ValueError):
+            pb.fastq_qc_report(reads, thresholds={"unknown": {"warn": 1}})
+
+
+class TestWriteFastqcData:
+    report = pb.fastq_qc_report(FASTQ_PATH)
+
+    def test_layout(self, tmp_path):
+        path = tmp_path / "example_fastqc_data.txt"
+        pb.write_fastqc_data(self.report, str(path), "example.fastq")
+        lines = path.read_text().splitlines()
+        assert lines[0].startswith("##FastQC")
+        assert "Filename\texample.fastq" in lines
+        modules = [line[2:].split("\t") for line in lines if line.startswith(">>")]
+        titles = [m[0] for m in modules if m[0] != "END_MODULE"]
+        assert titles == [
+            "Basic Statistics",
+            "Per base sequence quality",
+            "Per sequence quality scores",
+            "Per base sequence content",
+            "Per sequence GC content",
+            "Per base N content",
+            "Sequence Length Distribution",
+            "Sequence Duplication Levels",
+            "Overrepresented sequences",
+            "Adapter Content",
+        ]
+        assert len([m for m in modules if m[0] == "END_MODULE"]) == len(titles)
+        assert (
+            f"Total Sequences\t{self.report['basic_statistics']['data']['total_sequences']}"
+            in lines
+        )
+        quality_start = lines.index(
+            f">>Per base sequence quality\t{self.report['per_base_sequence_quality']['status']}"
+        )
+        assert lines[quality_start + 1].startswith("#Base\tMean\tMedian")
+        first = lines[quality_start + 2].split("\t")
+        assert int(first[0]) == 1
+        assert len(first) == 7
+
+
+class TestGroupedBaseSequenceContent:
+    reads = pl.DataFrame(
+        {
+            "sample": ["s1", "s1", "s2"],
+            "lane": [1, 2, 1],
+            "read": ["ACG", "AAN", "TT"],
+        }
+    )
+
+    def test_group_by(self):
+        result = pb.base_sequence_content(self.reads, group_by="sample", column="read")
+        assert result.columns == [
+            "sample",
+            "a_count",
+            "c_count",
+            "g_count",
+            "t_count",
+            "n_count",
+            "other_count",
+            "position",
+        ]
+        s1 = result.filter(pl.col("sample") == "s1")
+        assert s1["position"].to_list() == [1, 2, 3]
+        assert s1["a_count"].to_list() == [2, 1, 0]
+        assert s1["c_count"].to_list() == [0, 1, 0]
+        assert s1["n_count"].to_list() == [0, 0, 1]
+        s2 = result.filter(pl.col("sample") == "s2")
+        assert s2["t_count"].to_list() == [1, 1]
+
+    def test_multiple_groups(self):
+        result = pb.base_sequence_content(
+            self.reads, group_by=["sample", "lane"], column="read"
+        )
+        assert result.select("sample", "lane").unique().height == 3
+        assert result["position"].max() == 3
+
+    def test_ungrouped_matches_total(self):
+        grouped = pb.base_sequence_content(FASTQ_PATH, group_by=[])
+        assert grouped.equals(pb.base_sequence_content(FASTQ_PATH))
+        assert "position" not in grouped.columns
+
+    def test_missing_column(self):
+        with pytest.raises(ValueError):
+            pb.base_sequence_content(self.reads, group_by="barcode", column="read")
+
+
+class TestBaseSequenceContentCategories:
+    reads = pl.DataFrame({"sequence": ["AcRn", "agY-", "T"]})
+
+    def test_other_category(self):
+        result = pb.base_sequence_content(self.reads)
+        assert result["a_count"].to_list() == [2, 0, 0, 0]
+        assert result["c_count"].to_list() == [0, 1, 0, 0]
+        assert result["g_count"].to_list() == [0, 1, 0, 0]
+        assert result["t_count"].to_list() == [1, 0, 0, 0]
+        assert result["n_count"].to_list() == [0, 0, 0, 1]
+        assert result["other_count"].to_list() == [0, 0, 2, 1]
+
+    def test_percentages(self):
+        result = pb.base_sequence_content(self.reads, percentages=True)
+        assert result["a_percent"].to_list() == pytest.approx(
+            [200 / 3, 0.0, 0.0, 0.0]
+        )
+        assert result["other_percent"].to_list() == pytest.approx(
+            [0.0, 0.0, 100.0, 50.0]
+        )
+        percent_columns = [c for c in result.columns if c.endswith("_percent")]
+        assert len(percent_columns) == 6
+        totals = result.select(pl.sum_horizontal(percent_columns).alias("total"))
+        assert totals["total"].to_list() == pytest.approx([100.0] * 4)
+
+
+class TestStreamingBaseSequenceContent:
+    def test_frame_input(self):
+        df = pb.read_fastq(FASTQ_PATH).collect()
+        result = pb.base_sequence_content(df, streaming=True)
+        assert isinstance(result, pl.LazyFrame)
+        assert result.collect().equals(pb.base_sequence_content(df))
+
+    def test_lazy_frame_input(self):
+        seen = []
+
+        def track(sequence):
+            seen.append(sequence)
+            return sequence
+
+        lazy = pb.read_fastq(FASTQ_PATH).with_columns(
+            pl.col("sequence").map_elements(track, return_dtype=pl.Utf8)
+        )
+        result = pb.base_sequence_content(lazy, streaming=True)
+        assert isinstance(result, pl.LazyFrame)
+        assert seen == []
+        assert result.collect().equals(pb.base_sequence_content(FASTQ_PATH))
+        assert len(seen) == pb.read_fastq(FASTQ_PATH).collect().height
+
+    def test_lazy_frame_single_pass(self):
+        reads = pb.read_fastq(FASTQ_PATH).collect()
+        executions = []
+
+        def source(with_columns, predicate, n_rows, batch_size):
+            executions.append(1)
+            yield from reads.iter_slices(100)
+
+        lazy = register_io_source(source, schema=reads.schema)
+        result = pb.base_sequence_content(lazy, streaming=True).collect()
+        assert executions == [1]
+        assert result.equals(pb.base_sequence_content(reads))
+
+    def test_fastq_path(self):
+        result = pb.base_sequence_content(FASTQ_PATH, streaming=True)
+        assert isinstance(result, pl.LazyFrame)
+        assert result.collect().equals(pb.base_sequence_content(FASTQ_PATH))
+
+    def test_grouped(self):
+        reads = pl.DataFrame({"sample": ["s1", "s2"], "sequence": ["AC", "G"]})
+        result = pb.base_sequence_content(
+            reads, group_by="sample", streaming=True
+        ).collect()
+        assert result.equals(pb.base_sequence_content(reads, group_by="sample"))
@@ -0,0 +1,127 @@
+import polars as pl
+
+import polars_bio as pb
+
+SCHEMA = {
+    "chrom": pl.Utf8,
+    "start": pl.UInt32,
+    "end": pl.UInt32,
+    "id": pl.Utf8,
+    "ref": pl.Utf8,
+    "alt": pl.Utf8,
+    "qual": pl.Float64,
+    "filter": pl.Utf8,
+    "SVTYPE": pl.Utf8,
+    "SVLEN": pl.Int32,
+    "END": pl.Int32,
+    "CIPOS": pl.List(pl.Int32),
+    "CIEND": pl.List(pl.Int32),
+}
+
+
+def _sv_frame(rows):
+    return pl.DataFrame(
+        [
+            {
+                "chrom": chrom,
+                "start": pos,
+                "end": pos,
+                "id": id,
+                "ref": "N",
+                "alt": alt,
+                "qual": None,
+                "filter": "PASS",
+                "SVTYPE": svtype,
+                "SVLEN": svlen,
+                "END": end,
+                "CIPOS": cipos,
+                "CIEND": ciend[0] if ciend else None,
+            }
+            for chrom, pos, id, alt, svtype, svlen, end, cipos, *ciend in rows
+        ],
+        schema=SCHEMA,
+    )
+
+
+CALLS = _sv_frame(
+    [
+        ("chr1", 1000, "c1", "<DEL>", "DEL", -1000, 2000, [-10, 10]),
+        ("chr1", 5000, "c2", "<INS>", "INS", 300, None, None),
+        ("chr2", 100, "c3", "N[chr3:500[", "BND", None, None, None),
+        ("chr1", 9000, "c4", "<DUP>", "DUP", None, 9500, None),
+    ]
+)
+
+TRUTH = _sv_frame(
+    [
+        ("chr1", 1100, "t1", "<DEL>", "DEL", -950, 2050, [-50, 50]),
+        ("chr1", 5200, "t2", "<INS>", "INS", 280, None, None),
+        ("chr2", 120, "t3", "N[chr3:520[", "BND", None, None, None),
+        ("chr1", 1010, "t4", "<DUP>", "DUP", None, 2000, None),
+        ("chr1", 9000, "t5", "<DEL>", "DEL", None, 9500, None),
+    ]
+)
+
+
+class TestSvMatch:
+    result = pb.sv_match(CALLS, TRUTH, output_type="polars.DataFrame").sort("id_1")
+
+    def test_best_pairs(self):
+        assert self.result["id_1"].to_list() == ["c1", "c2", "c3"]
+        assert self.result["id_2"].to_list() == ["t1", "t2", "t3"]
+
+    def test_confidence_intervals(self):
+        assert self.result["start_distance"].to_list() == [40, 200, 20]
+        assert self.result["end_distance"].to_list() == [50, 200, 20]
+
+    def test_metrics(self):
+        del_match = self.result.row(0, named=True)
+        assert abs(del_match["reciprocal_overlap"] - 901 / 1001) < 1e-9
+        assert abs(del_match["size_similarity"] - 0.95) < 1e-9
+        ins_match = self.result.row(1, named=True)
+        assert ins_match["reciprocal_overlap"] is None
+        assert self.result["svtype_1"].to_list() == ["DEL", "INS", "BND"]
+
+    def test_without_confidence_intervals(self):
+        result = pb.sv_match(
+            CALLS,
+            TRUTH,
+            use_confidence_intervals=False,
+            output_type="polars.DataFrame",
+        ).sort("id_1")
+        assert result["start_distance"].to_list() == [100, 200, 20]
+
+    def test_min_reciprocal_overlap(self):
+        result = pb.sv_match(
+            CALLS,
+            TRUTH,
+            min_reciprocal_overlap=0.95,
+            output_type="polars.DataFrame",
+        ).sort("id_1")
+        assert result["id_1"].to_list() == ["c2", "c3"]
+
+    def test_ignore_type(self):
+        result = pb.sv_match(
+            CALLS, TRUTH, match_type=False, output_type="polars.DataFrame"
+        ).sort("id_1")
+        assert result["id_1"].to_list() == ["c1", "c2", "c3", "c4"]
+        assert result["id_2"].to_list() == ["t4", "t2", "t3", "t5"]
+
+    def test_breakend_mate_confidence_intervals(self):
+        # the mates are 700bp apart, within max_distance only with CIEND
+        calls = _sv_frame(
+            [("chr4", 100, "c5", "N[chr5:1000[", "BND", None, None, None, [-300, 300])]
+        )
+        truth = _sv_frame(
+            [("chr4", 100, "t6", "N[chr5:1700[", "BND", None, None, None, [-300, 300])]
+        )
+        result = pb.sv_match(calls, truth, output_type="polars.DataFrame")
+        assert result["id_2"].to_list() == ["t6"]
+        assert result["end_distance"].to_list() == [100]
+        result = pb.sv_match(
+            calls,
+            truth,
+            use_confidence_intervals=False,
+            output_type="polars.DataFrame",
+        )
+        assert len(result) == 0
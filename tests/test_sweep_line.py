@@ -0,0 +1,147 @@
+import bioframe as bf
+import pandas as pd
+import polars as pl
+import pytest
+from _expected import (
+    BIO_PD_DF1,
+    BIO_PD_DF2,
+    PL_COUNT_OVERLAPS_DF1,
+    PL_COUNT_OVERLAPS_DF2,
+    PL_DF1,
+    PL_DF2,
+    PL_DF_COUNT_OVERLAPS,
+    PL_DF_NEAREST,
+    PL_DF_OVERLAP,
+    PL_NEAREST_DF1,
+    PL_NEAREST_DF2,
+)
+
+import polars_bio as pb
+from polars_bio.polars_bio import FilterOp
+
+COLS = ("contig", "pos_start", "pos_end")
+
+
+def _sorted(df):
+    return df.sort(by=["contig", "pos_start"])
+
+
+class TestOverlapSweepLine:
+    result = pb.overlap(
+        _sorted(PL_DF1),
+        _sorted(PL_DF2),
+        output_type="polars.DataFrame",
+        overlap_filter=FilterOp.Weak,
+        cols1=COLS,
+        cols2=COLS,
+        sorted_input=True,
+    )
+
+    def test_overlap_count(self):
+        assert len(self.result) == len(PL_DF_OVERLAP)
+
+    def test_overlap_schema_rows(self):
+        result = self.result.sort(by=self.result.columns)
+        assert PL_DF_OVERLAP.equals(result)
+
+
+class TestNearestSweepLine:
+    result = pb.nearest(
+        _sorted(PL_NEAREST_DF1),
+        _sorted(PL_NEAREST_DF2),
+        output_type="polars.DataFrame",
+        cols1=COLS,
+        cols2=COLS,
+        sorted_input=True,
+    )
+
+    def test_nearest_count(self):
+        assert len(self.result) == len(PL_DF_NEAREST)
+
+    def test_nearest_schema_rows(self):
+        result = self.result.sort(by=self.result.columns)
+        assert PL_DF_NEAREST.equals(result)
+
+
+class TestCountOverlapsSweepLine:
+    result = pb.count_overlaps(
+        _sorted(PL_COUNT_OVERLAPS_DF1),
+        _sorted(PL_COUNT_OVERLAPS_DF2),
+        output_type="polars.DataFrame",
+        cols1=COLS,
+        cols2=COLS,
+        overlap_filter=FilterOp.Weak,
+        sorted_input=True,
+    )
+
+    def test_count_overlaps_schema_rows(self):
+        result = self.result.sort(by=self.result.columns)
+        assert PL_DF_COUNT_OVERLAPS.equals(result)
+
+    def test_unsorted_input_raises(self):
+        with pytest.raises(Exception):
+            pb.count_overlaps(
+                PL_COUNT_OVERLAPS_DF1.sort(by="pos_start", descending=True),
+                _sorted(PL_COUNT_OVERLAPS_DF2),
+                output_type="polars.DataFrame",
+                cols1=COLS,
+                cols2=COLS,
+                sorted_input=True,
+            )
+
+
+class TestCoverageSweepLine:
+    result = pb.coverage(
+        _sorted(pl.from_pandas(BIO_PD_DF1)),
+        _sorted(pl.from_pandas(BIO_PD_DF2)),
+        cols1=COLS,
+        cols2=COLS,
+        output_type="pandas.DataFrame",
+        overlap_filter=FilterOp.Strict,
+        sorted_input=True,
+    )
+    result_bio = bf.coverage(
+        BIO_PD_DF1,
+        BIO_PD_DF2,
+        cols1=COLS,
+        cols2=COLS,
+        suffixes=("_1", "_2"),
+    )
+
+    def test_coverage_count(self):
+        assert len(self.result) == len(self.result_bio)
+
+    def test_coverage_schema_rows(self):
+        result = self.result.sort_values(by=list(self.result.columns)).reset_index(
+            drop=True
+        )
+        expected = (
+            self.result_bio.sort_values(by=list(self.result.columns))
+            .reset_index(drop=True)
+            .astype({"coverage": "int64"})
+        )
+        pd.testing.assert_frame_equal(result, expected)
+
+    @pytest.mark.parametrize("overlap_filter", [FilterOp.Weak, FilterOp.Strict])
+    def test_coverage_matches_interval_tree(self, overlap_filter):
+        df1 = pl.DataFrame(
+            {"contig": ["chr1"] * 3, "pos_start": [5, 12, 40], "pos_end": [30, 14, 45]}
+        )
+        df2 = pl.DataFrame(
+            {"contig": ["chr1"] * 2, "pos_start": [10, 41], "pos_end": [20, 43]}
+        )
+        expected, result = (
+            pb.coverage(
+                df1,
+                df2,
+                cols1=COLS,
+                cols2=COLS,
+                output_type="polars.DataFrame",
+                overlap_filter=overlap_filter,
+                sorted_input=sorted_input,
+            )
+            for sorted_input in (False, True)
+        )
+        assert expected.sort(by=expected.columns).equals(
+            result.sort(by=result.columns)
+        )
@@ -0,0 +1,138 @@
+import polars_bio as pb
+
+
+def trim(function: str, sequence: str, quality: str, *params) -> tuple:
+    arguments = ", ".join(["column1", "column2"] + [str(p) for p in params])
+    result = pb.sql(
+        f"SELECT {function}({arguments}) AS t "
+        f"FROM (VALUES ('{sequence}', '{quality}'))"
+    ).collect()
+    read = result["t"][0]
+    return read["sequence"], read["quality_scores"]
+
+
+class TestQualityTrimming:
+    def test_leading_trailing(self):
+        assert trim("trim_leading", "ACGTA", "##III", 20) == ("GTA", "III")
+        assert trim("trim_trailing", "ACGTA", "III##", 20) == ("ACG", "III")
+        assert trim("trim_trailing", "ACG", "###", 20) == ("", "")
+
+    def test_sliding_window(self):
+        assert trim("trim_sliding_window", "ACGTACGT", "IIII##II", 2, 20) == (
+            "ACGT",
+            "IIII",
+        )
+        assert trim("trim_sliding_window", "ACGT", "IIII", 10, 20) == ("ACGT", "IIII")
+
+    def test_bwa(self):
+        assert trim("trim_quality_bwa", "ACGTACGT", "IIIIII##", 20) == (
+            "ACGTAC",
+            "IIIIII",
+        )
+        assert trim("trim_quality_bwa", "ACGT", "IIII", 20) == ("ACGT", "IIII")
+
+    def test_phred_offset(self):
+        assert trim("trim_trailing", "ACG", "hh@", 20, 64) == ("AC", "hh")
+
+
+class TestSequenceTrimming:
+    def test_poly_g(self):
+        sequence = "ACGT" + "G" * 12
+        assert trim("trim_poly_g", sequence, "I" * 16, 10) == ("ACGT", "IIII")
+        assert trim("trim_poly_g", "ACGTGGG", "I" * 7, 10) == ("ACGTGGG", "I" * 7)
+
+    def test_poly_a_with_mismatch(self):
+        sequence = "CCCC" + "A" * 6 + "C" + "A" * 6
+        assert trim("trim_poly_a", sequence, "I" * 17, 10) == ("CCCC", "IIII")
+
+    def test_crop(self):
+        assert trim("trim_crop", "ACGTACGT", "ABCDEFGH", 2, 3) == ("GTA", "CDE")
+        assert trim("trim_crop", "ACG", "III", 2, 3) == ("", "")
+
+
+class TestTrimmingSql:
+    def test_chained_on_fastq(self):
+        pb.register_fastq("./data/quality_control/example.fastq", "trim_reads")
+        result = pb.sql(
+            """
+            WITH cropped AS (
+                SELECT trim_crop(sequence, quality_scores, 5, 0) AS r
+                FROM trim_reads
+            )
+            SELECT
+                trim_trailing(r['sequence'], r['quality_scores'], 20) AS r
+            FROM cropped
+            """
+        ).collect()
+        reads = pb.read_fastq("./data/quality_control/example.fastq").collect()
+        trimmed = result["r"].struct.unnest()
+        assert trimmed.height == reads.height
+        assert (
+            trimmed["sequence"].str.len_chars()
+            == trimmed["quality_scores"].str.len_chars()
+        ).all()
+        assert (
+            trimmed["sequence"].str.len_chars()
+            <= reads["sequence"].str.len_chars() - 5
+        ).all()
+
+    def test_null_read(self):
+        result = pb.sql(
+            "SELECT t['sequence'] AS s FROM ("
+            "SELECT trim_leading(column1, column2, 20) AS t "
+            "FROM (VALUES (CAST(NULL AS VARCHAR), CAST(NULL AS VARCHAR))))"
+        ).collect()
+        assert result["s"].to_list() == [None]
+
+
+ADAPTER = "AGATCGGAAGAGC"
+
+
+class TestAdapterTrimming:
+    def test_partial_adapter_at_read_end(self):
+        read = "ACGTACGTAC" + ADAPTER[:10]
+        assert trim("trim_adapter", read, "I" * 20, f"'{ADAPTER}'") == (
+            "ACGTACGTAC",
+            "I" * 10,
+        )
+
+    def test_mismatch(self):
+        read = "ACGTACGTAC" + "AGATCGGTAG"
+        assert trim("trim_adapter", read, "I" * 20, f"'{ADAPTER}'")[0] == "ACGTACGTAC"
+        assert trim("trim_adapter", read, "I" * 20, f"'{ADAPTER}'", 0.0, 3)[0] == read
+
+    def test_full_adapter(self):
+        read = "ACGT" + ADAPTER + "TTTT"
+        assert trim("trim_adapter", read, "I" * 21, f"'{ADAPTER}'")[0] == "ACGT"
+
+    def test_min_overlap(self):
+        read = "ACGTACGTACAG"
+        assert trim("trim_adapter", read, "I" * 12, f"'{ADAPTER}'")[0] == read
+        assert trim("trim_adapter", read, "I" * 12, f"'{ADAPTER}'", 0.1, 2)[0] == (
+            "ACGTACGTAC"
+        )
+
+    def test_paired_overlap(self):
+        insert = "ACGTTGCAAGGCTTAC"
+        reverse = "GTAAGCCTTGCAACGT"
+        read1, read2 = insert + ADAPTER[:10], reverse + ADAPTER[:10]
+        result = pb.sql(
+            "SELECT trim_adapter_paired(column1, column2, column3, column4, 0.1, 10) AS t "
+            f"FROM (VALUES ('{read1}', '{'I' * 26}', '{read2}', '{'#' * 26}'))"
+        ).collect()
+        pair = result["t"][0]
+        assert pair == {
+            "seq1": insert,
+            "qual1": "I" * 16,
+            "seq2": reverse,
+            "qual2": "#" * 16,
+        }
+
+    def test_paired_without_adapters(self):
+        read1, read2 = "ACGTTGCAAGGCTTAC", "TTTTGGGGCCCCAAAA"
+        result = pb.sql(
+            "SELECT trim_adapter_paired(column1, column2, column3, column4, 0.1, 10) AS t "
+            f"FROM (VALUES ('{read1}', '{'I' * 16}', '{read2}', '{'I' * 16}'))"
+        ).collect()
+        assert result["t"][0]["seq1"] == read1
+        assert result["t"][0]["seq2"] == read2
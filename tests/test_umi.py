@@ -0,0 +1,93 @@
+import polars as pl
+import pytest
+
+import polars_bio as pb
+
+
+class TestExtractUmi:
+    reads = pl.DataFrame(
+        {
+            "name": ["r1", "r2", "r3"],
+            "sequence": ["AAACCCGGTT", "TTTGGGCCAA", "ACG"],
+            "quality_scores": ["ABCDEFGHIJ", "IIIIIIIIII", "III"],
+        }
+    )
+
+    def test_pattern(self):
+        result = pb.extract_umi(self.reads, "NNNNNNXX")
+        assert result.columns == ["name", "sequence", "quality_scores", "umi"]
+        assert result["name"].to_list() == ["r1", "r2"]
+        assert result["umi"].to_list() == ["AAACCC", "TTTGGG"]
+        assert result["sequence"].to_list() == ["GGTT", "CCAA"]
+        assert result["quality_scores"][0] == "GHIJ"
+
+    def test_cell_barcode(self):
+        result = pb.extract_umi(self.reads, "CCNNNX")
+        assert result["cell_barcode"].to_list() == ["AA", "TT"]
+        assert result["umi"].to_list() == ["ACC", "TGG"]
+        assert result["sequence"].to_list() == ["CGGTT", "GCCAA"]
+
+    def test_length_mismatch(self):
+        reads = pl.DataFrame({"sequence": ["ACGTACGT"], "quality_scores": ["IIII"]})
+        with pytest.raises(Exception, match="lengths differ"):
+            pb.extract_umi(reads, "NNNN")
+
+    def test_invalid_pattern(self):
+        with pytest.raises(Exception):
+            pb.extract_umi(self.reads, "NNNY")
+
+    def test_umi_from_name(self):
+        result = pb.sql(
+            "SELECT umi_from_name(column1) AS umi, umi_from_name(column1, ':') AS colon "
+            "FROM (VALUES ('read1_ACGTAC'), ('read2'), ('a:b:GGCC'))"
+        ).collect()
+        assert result["umi"].to_list() == ["ACGTAC", None, None]
+        assert result["colon"].to_list() == [None, None, "GGCC"]
+
+
+class TestUmiDedup:
+    reads = pl.DataFrame(
+        {
+            "sequence": ["ACGT"] * 8 + ["TTTT"],
+            "umi": ["AAAA"] * 5 + ["AAAT", "CCCC", "CCCC", "AAAT"],
+        }
+    )
+
+    def test_groups(self):
+        result = pb.umi_dedup(self.reads, output="groups")
+        assert result.height == 9
+        assert result["group_id"].n_unique() == 3
+        merged = result.filter(
+            (pl.col("sequence") == "ACGT") & (pl.col("umi") == "AAAT")
+        )
+        assert merged["final_umi"].to_list() == ["AAAA"]
+        other = result.filter(pl.col("sequence") == "TTTT")
+        assert other["final_umi"].to_list() == ["AAAT"]
+
+    def test_reads(self):
+        result = pb.umi_dedup(self.reads)
+        assert result.height == 3
+        assert sorted(zip(result["sequence"], result["umi"])) == [
+            ("ACGT", "AAAA"),
+            ("ACGT", "CCCC"),
+            ("TTTT", "AAAT"),
+        ]
+
+    def test_directional_counts(self):
+        reads = pl.DataFrame({"sequence": ["ACGT"] * 6, "umi": ["AAAA"] * 3 + ["AAAT"] * 3})
+        assert pb.umi_dedup(reads).height == 2
+        assert pb.umi_dedup(reads, max_distance=0).height == 2
+
+    def test_prefix_length(self):
+        reads = pl.DataFrame(
+            {"sequence": ["ACGTAA", "ACGTCC"], "umi": ["GGGG", "GGGG"]}
+        )
+        assert pb.umi_dedup(reads).height == 2
+        assert pb.umi_dedup(reads, prefix_length=4).height == 1
+
+    def test_null_sequences(self):
+        reads = pl.DataFrame(
+            {"sequence": [None] * 3 + [""], "umi": ["AAAA"] * 3 + ["AAAT"]}
+        )
+        result = pb.umi_dedup(reads, output="groups")
+        assert result["final_umi"].to_list() == ["AAAT"]
@@ -0,0 +1,61 @@
+import polars as pl
+
+import polars_bio as pb
+from polars_bio.polars_bio import FilterOp
+
+VCF_DF = pl.DataFrame(
+    {
+        "chrom": ["chr1"] * 5,
+        "start": [100, 200, 300, 1000, 2000],
+        "end": [100, 200, 300, 1000, 2000],
+        "id": ["snv", "del", "sv_del", "sv_dup", "sv_ins"],
+        "ref": ["A", "ACGTA", "N", "N", "N"],
+        "alt": ["G", "A", "<DEL>", "<DUP>", "<INS>"],
+        "qual": [None] * 5,
+        "filter": ["PASS"] * 5,
+        "END": [None, None, 500, None, None],
+        "SVLEN": [None, None, None, 300, 50],
+    },
+    schema_overrides={
+        "start": pl.UInt32,
+        "end": pl.UInt32,
+        "qual": pl.Float64,
+        "END": pl.Int32,
+        "SVLEN": pl.Int32,
+    },
+)
+
+TARGETS_DF = pl.DataFrame(
+    {
+        "chrom": ["chr1"] * 4,
+        "start": [203, 450, 1250, 2001],
+        "end": [210, 460, 1260, 2100],
+    }
+)
+
+
+class TestVcfPointIntervals:
+    result = pb.overlap(
+        VCF_DF,
+        TARGETS_DF,
+        overlap_filter=FilterOp.Weak,
+        output_type="polars.DataFrame",
+    ).sort(by="start_1")
+
+    def test_overlap_ids(self):
+        assert self.result["id_1"].to_list() == ["del", "sv_del", "sv_dup"]
+
+    def test_derived_end(self):
+        assert self.result["start_1"].to_list() == [200, 300, 1000]
+        assert self.result["end_1"].to_list() == [204, 500, 1300]
+        assert self.result["end_1"].dtype == pl.UInt32
+
+    def test_zero_based(self):
+        result = pb.overlap(
+            VCF_DF,
+            TARGETS_DF,
+            overlap_filter=FilterOp.Strict,
+            output_type="polars.DataFrame",
+        ).sort(by="start_1")
+        assert result["start_1"].to_list() == [199, 299, 999]
+        assert result["end_1"].to_list() == [204, 500, 1300]